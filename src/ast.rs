@@ -6,11 +6,22 @@ pub struct Program {
     pub declarations: Vec<Declaration>,
 }
 
+/// One input of the REPL: either declarations or statements with an optional final
+/// expression whose value is displayed.
+#[derive(Debug, Clone)]
+pub enum ReplEntry {
+    Declarations(Program),
+    Statements(Vec<Spanned<Statement>>, Option<Spanned<Expression>>),
+}
+
 #[derive(Debug, Clone)]
 pub enum Declaration {
     Struct(Struct),
+    Enum(Enum),
     ExternFunction(ExternFunction),
     Function(Function),
+    Test(Function), // only built and run by `yalc --test`
+    Global(Global),
 }
 
 #[derive(Debug, Clone)]
@@ -20,6 +31,47 @@ pub struct Struct {
     pub span: Span,
 }
 
+/// An enum, C-like if none of its variants has a payload, else a tagged union.
+#[derive(Debug, Clone)]
+pub struct Enum {
+    pub name: String,
+    pub variants: Vec<EnumVariant>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    pub name: Spanned<String>,
+    pub payload: Vec<Spanned<Type>>,
+    pub discriminant: Option<Spanned<i64>>, // the previous one plus one by default
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Spanned<Pattern>,
+    pub value: Spanned<Expression>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Variant {
+        enum_name: String,
+        variant: Spanned<String>,
+        bindings: Vec<Spanned<String>>, // the fields of the payload, `_` ignores one
+    },
+    Wildcard,
+}
+
+/// A global variable, or a constant if it can't be assigned.
+#[derive(Debug, Clone)]
+pub struct Global {
+    pub name: String,
+    pub ty: Option<Spanned<Type>>,
+    pub value: Spanned<Expression>,
+    pub is_const: bool,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct ExternFunction {
     pub return_ty: Spanned<Type>,
@@ -46,7 +98,7 @@ pub struct Function {
     pub return_ty: Spanned<Type>,
     pub name: String,
     pub parameters: Vec<(String, Spanned<Type>)>,
-    pub body: BlockStatement,
+    pub body: Spanned<BlockStatement>,
     pub span: Span,
 }
 
@@ -73,6 +125,7 @@ pub enum Statement {
     If(IfStatement),
     While(WhileStatement),
     For(ForStatement),
+    ForEach(ForEachStatement),
     Return(Option<Spanned<Expression>>),
     Expression(Spanned<Expression>),
     Break,
@@ -118,6 +171,15 @@ pub struct ForStatement {
     pub body: Box<Spanned<Statement>>,
 }
 
+/// The loop `for (T x : array)` over the items of an array.
+#[derive(Debug, Clone)]
+pub struct ForEachStatement {
+    pub ty: Spanned<Type>,
+    pub name: Spanned<String>,
+    pub array: Spanned<Expression>,
+    pub body: Box<Spanned<Statement>>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Expression {
     Literal(Literal),
@@ -127,6 +189,11 @@ pub enum Expression {
         lhs: Box<Spanned<Expression>>,
         rhs: Box<Spanned<Expression>>,
     },
+    CompoundAssign {
+        binop: BinaryOperatorKind,
+        lhs: Box<Spanned<Expression>>,
+        rhs: Box<Spanned<Expression>>,
+    },
     BinaryOperator {
         binop: BinaryOperatorKind,
         lhs: Box<Spanned<Expression>>,
@@ -153,6 +220,11 @@ pub enum Expression {
         array: Box<Spanned<Expression>>,
         index: Box<Spanned<Expression>>,
     },
+    Slice {
+        array: Box<Spanned<Expression>>,
+        begin: Box<Spanned<Expression>>,
+        end: Box<Spanned<Expression>>,
+    },
     FunctionCall {
         function: Box<Spanned<Expression>>,
         args: Vec<Spanned<Expression>>,
@@ -176,6 +248,27 @@ pub enum Expression {
         field: Field,
     },
     Nullptr,
+    EnumVariant {
+        enum_name: String,
+        variant: Spanned<String>,
+        payload: Vec<Spanned<Expression>>,
+    },
+    Match {
+        scrutinee: Box<Spanned<Expression>>,
+        arms: Vec<MatchArm>,
+    },
+    New {
+        ty: Spanned<Type>,
+        value: Option<Box<Spanned<Expression>>>, // the struct literal of `new T { fields }`
+    },
+    NewArray {
+        ty: Spanned<Type>,
+        size: Box<Spanned<Expression>>,
+        rows: Vec<Spanned<Expression>>, // the sizes of the nested arrays of `new T[n][m]`
+    },
+    Delete(Box<Spanned<Expression>>),
+    SizeOf(Spanned<Type>),
+    AlignOf(Spanned<Type>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -191,6 +284,11 @@ pub enum BinaryOperatorKind {
     LessEqual,
     Greater,
     GreaterEqual,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -203,6 +301,7 @@ pub enum LazyOperatorKind {
 pub enum UnaryOperatorKind {
     Minus,
     LogicalNot,
+    BitNot,
     PtrDeref,
 }
 
@@ -219,6 +318,8 @@ pub enum Type {
     Identifier(String),
     Pointer(Box<Spanned<Type>>),
     Array(Box<Spanned<Type>>, usize),
+    DynArray(Box<Spanned<Type>>),
+    Slice(Box<Spanned<Type>>),
     Function(Box<FunctionType>),
     Tuple(Vec<Spanned<Type>>),
 }
@@ -1,14 +1,49 @@
 use std;
+use std::fs;
 use std::ptr;
+use std::env;
 use std::ffi::CString;
+use std::path::Path;
+use std::process::Command;
 
 use llvm;
 use llvm::execution_engine::LLVMExecutionEngineRef;
+use llvm::target_machine::*;
 use llvm::core::*;
 use libc;
 
 use backend::helper::{Context, Module};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitKind {
+    Object,
+    Assembly,
+    LLVMIR,
+    Bitcode,
+    Executable,
+}
+
+impl EmitKind {
+    pub fn default_extension(&self) -> &'static str {
+        match *self {
+            EmitKind::Object => "o",
+            EmitKind::Assembly => "s",
+            EmitKind::LLVMIR => "ll",
+            EmitKind::Bitcode => "bc",
+            EmitKind::Executable => "",
+        }
+    }
+}
+
+/// The result of a function run by the JIT, converted from its LLVM type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JitValue {
+    Int(i64),
+    Double(f64),
+    Pointer(*mut libc::c_void),
+    Void,
+}
+
 pub struct ExecutionModule {
     module: Module,
     _context: Context,
@@ -86,42 +121,91 @@ impl ExecutionModule {
         }
     }
 
-    pub fn run_main(&self, exec_engine: LLVMExecutionEngineRef) {
-        let main_func = self.module
-            .get_named_function(&CString::new("main").unwrap());
+    fn emit_with_target_machine(
+        &self,
+        path: &Path,
+        file_type: LLVMCodeGenFileType,
+    ) -> Result<(), CString> {
+        let target_machine = self.module.host_target_machine()?;
+        let c_path = path_to_cstring(path)?;
 
         unsafe {
-            llvm::execution_engine::LLVMRunFunction(exec_engine, main_func, 0, ptr::null_mut());
-        }
-    }
-
-    pub fn interpret_main(&self) -> Result<(), CString> {
-        /*
-        let mut exec_engine: LLVMExecutionEngineRef = std::ptr::null_mut();
-        unsafe {
-            let mut error: *mut libc::c_char = std::ptr::null_mut();
-            llvm::execution_engine::LLVMLinkInInterpreter();
-            if llvm::execution_engine::LLVMCreateInterpreterForModule(
-                &mut exec_engine,
+            let mut error: *mut libc::c_char = ptr::null_mut();
+            let failed = LLVMTargetMachineEmitToFile(
+                target_machine,
                 self.module.module,
+                c_path.as_ptr() as *mut _,
+                file_type,
                 &mut error,
-            ) != 0
-            {
-                return Err(CString::from_raw(error));
+            ) != 0;
+            LLVMDisposeTargetMachine(target_machine);
+
+            if failed {
+                Err(CString::from_raw(error))
+            } else {
+                Ok(())
             }
         }
+    }
 
-        self.run_main(exec_engine);
+    fn emit_executable(&self, path: &Path) -> Result<(), CString> {
+        let object_path = env::temp_dir().join(format!("yalc-{}.o", std::process::id()));
+        self.emit_with_target_machine(&object_path, LLVMCodeGenFileType::LLVMObjectFile)?;
 
-        unsafe {
-            llvm::execution_engine::LLVMDisposeExecutionEngine(exec_engine);
+        // the C compiler driver links the libc, which provides printf and scanf
+        let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let status = Command::new(&cc)
+            .arg(&object_path)
+            .arg("-o")
+            .arg(path)
+            .status();
+        let _ = fs::remove_file(&object_path);
+
+        match status {
+            Ok(ref status) if status.success() => Ok(()),
+            Ok(status) => Err(error_message(format!("'{}' failed with {}", cc, status))),
+            Err(err) => Err(error_message(format!("Can't run '{}': {}", cc, err))),
+        }
+    }
+
+    pub fn emit(&self, kind: EmitKind, path: &Path) -> Result<(), CString> {
+        match kind {
+            EmitKind::Object => {
+                self.emit_with_target_machine(path, LLVMCodeGenFileType::LLVMObjectFile)
+            }
+            EmitKind::Assembly => {
+                self.emit_with_target_machine(path, LLVMCodeGenFileType::LLVMAssemblyFile)
+            }
+            EmitKind::Executable => self.emit_executable(path),
+            EmitKind::LLVMIR => {
+                let c_path = path_to_cstring(path)?;
+                unsafe {
+                    let mut error: *mut libc::c_char = ptr::null_mut();
+                    if LLVMPrintModuleToFile(self.module.module, c_path.as_ptr(), &mut error) != 0
+                    {
+                        return Err(CString::from_raw(error));
+                    }
+                }
+                Ok(())
+            }
+            EmitKind::Bitcode => {
+                let c_path = path_to_cstring(path)?;
+                let res = unsafe {
+                    llvm::bit_writer::LLVMWriteBitcodeToFile(self.module.module, c_path.as_ptr())
+                };
+                if res != 0 {
+                    Err(error_message(format!(
+                        "Can't write bitcode to '{}'",
+                        path.display()
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
         }
-        Ok(())
-        */
-        unimplemented!()
     }
 
-    pub fn jit_main(&self) -> Result<(), CString> {
+    fn create_jit_engine(&self) -> Result<LLVMExecutionEngineRef, CString> {
         use llvm::execution_engine::LLVMMCJITCompilerOptions;
 
         let mut exec_engine: LLVMExecutionEngineRef = std::ptr::null_mut();
@@ -145,12 +229,137 @@ impl ExecutionModule {
                 return Err(CString::from_raw(error));
             }
         }
+        Ok(exec_engine)
+    }
 
-        self.run_main(exec_engine);
+    pub fn run_main(&self, exec_engine: LLVMExecutionEngineRef) -> i32 {
+        match self.run_function(exec_engine, "main") {
+            JitValue::Int(exit_code) => exit_code as i32,
+            _ => panic!("main must return an int"),
+        }
+    }
+
+    // the function must not take parameters, MCJIT can't pass them
+    fn run_function(&self, exec_engine: LLVMExecutionEngineRef, name: &str) -> JitValue {
+        use llvm::execution_engine::*;
+        use llvm::LLVMTypeKind;
+
+        let func = self.module
+            .get_named_function(&CString::new(name).unwrap());
+
+        unsafe {
+            let return_ty = LLVMGetReturnType(LLVMGetElementType(LLVMTypeOf(func)));
+            let result = LLVMRunFunction(exec_engine, func, 0, ptr::null_mut());
+            let value = match LLVMGetTypeKind(return_ty) {
+                LLVMTypeKind::LLVMIntegerTypeKind => {
+                    let signed = LLVMGetIntTypeWidth(return_ty) > 1;
+                    JitValue::Int(LLVMGenericValueToInt(result, signed as _) as i64)
+                }
+                LLVMTypeKind::LLVMDoubleTypeKind => {
+                    JitValue::Double(LLVMGenericValueToFloat(return_ty, result))
+                }
+                LLVMTypeKind::LLVMPointerTypeKind => {
+                    JitValue::Pointer(LLVMGenericValueToPointer(result))
+                }
+                _ => JitValue::Void,
+            };
+            LLVMDisposeGenericValue(result);
+
+            // the program writes through the C stdio, keep it in sync with our output
+            libc::fflush(ptr::null_mut());
+            value
+        }
+    }
+
+    pub fn jit_main(&self) -> Result<i32, CString> {
+        let exec_engine = self.create_jit_engine()?;
+        let exit_code = self.run_main(exec_engine);
 
         unsafe {
             llvm::execution_engine::LLVMDisposeExecutionEngine(exec_engine);
         }
-        Ok(())
+        Ok(exit_code)
+    }
+
+    // pointers into the module are only valid inside `f`, the engine is disposed after it
+    pub fn jit_function<F, R>(&self, name: &str, f: F) -> Result<R, CString>
+    where
+        F: FnOnce(JitValue) -> R,
+    {
+        let exec_engine = self.create_jit_engine()?;
+        let value = f(self.run_function(exec_engine, name));
+
+        unsafe {
+            llvm::execution_engine::LLVMDisposeExecutionEngine(exec_engine);
+        }
+        Ok(value)
+    }
+
+    /// Run an entry of a REPL session, with the globals saved by the previous entries restored
+    /// before the call and saved after it, a global without saved bytes keeps its initializer.
+    /// The saved values can point to the strings and the functions of the module, so the engine
+    /// is returned to be kept as long as the session.
+    pub fn jit_session_function<F, R>(
+        self,
+        name: &str,
+        globals: &mut [(String, Vec<u8>)],
+        f: F,
+    ) -> Result<(R, SessionEngine), CString>
+    where
+        F: FnOnce(JitValue) -> R,
+    {
+        use llvm::execution_engine::*;
+        use llvm::target::LLVMABISizeOfType;
+
+        let exec_engine = self.create_jit_engine()?;
+        let mut addresses = Vec::with_capacity(globals.len());
+        unsafe {
+            let target_data = LLVMGetExecutionEngineTargetData(exec_engine);
+            for &mut (ref name, ref saved) in globals.iter_mut() {
+                let c_name = CString::new(name.clone()).unwrap();
+                let global = LLVMGetNamedGlobal(self.module.module, c_name.as_ptr());
+                let ty = LLVMGetElementType(LLVMTypeOf(global));
+                let size = LLVMABISizeOfType(target_data, ty) as usize;
+                let address = LLVMGetGlobalValueAddress(exec_engine, c_name.as_ptr()) as *mut u8;
+                if saved.len() == size {
+                    ptr::copy_nonoverlapping(saved.as_ptr(), address, size);
+                }
+                addresses.push((address, size));
+            }
+        }
+
+        let value = f(self.run_function(exec_engine, name));
+
+        for (&mut (_, ref mut saved), (address, size)) in globals.iter_mut().zip(addresses) {
+            *saved = unsafe { std::slice::from_raw_parts(address, size) }.to_vec();
+        }
+        let engine = SessionEngine {
+            exec_engine,
+            _module: self,
+        };
+        Ok((value, engine))
+    }
+}
+
+/// The engine of a REPL entry with its module, disposed when the session ends.
+pub struct SessionEngine {
+    exec_engine: LLVMExecutionEngineRef,
+    _module: ExecutionModule,
+}
+
+impl Drop for SessionEngine {
+    fn drop(&mut self) {
+        // the engine owns the module, the context is disposed after it
+        unsafe { llvm::execution_engine::LLVMDisposeExecutionEngine(self.exec_engine) };
     }
 }
+
+fn error_message(msg: String) -> CString {
+    CString::new(msg).unwrap()
+}
+
+fn path_to_cstring(path: &Path) -> Result<CString, CString> {
+    path.to_str()
+        .and_then(|s| CString::new(s).ok())
+        .ok_or_else(|| error_message(format!("Invalid output path '{}'", path.display())))
+}
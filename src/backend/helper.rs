@@ -1,9 +1,12 @@
 use std::ops::Drop;
+use std::ptr;
 use std::ffi::{CStr, CString};
 
+use libc;
 use llvm;
 use llvm::core::*;
 use llvm::prelude::*;
+use llvm::target_machine::*;
 
 use backend::utils;
 use self::utils::c_str;
@@ -31,10 +34,18 @@ impl Context {
         unsafe { LLVMInt8TypeInContext(self.context) }
     }
 
+    pub fn i16_ty(&self) -> LLVMTypeRef {
+        unsafe { LLVMInt16TypeInContext(self.context) }
+    }
+
     pub fn i32_ty(&self) -> LLVMTypeRef {
         unsafe { LLVMInt32TypeInContext(self.context) }
     }
 
+    pub fn int_ty(&self, bits: usize) -> LLVMTypeRef {
+        unsafe { LLVMIntTypeInContext(self.context, bits as _) }
+    }
+
     pub fn i64_ty(&self) -> LLVMTypeRef {
         unsafe { LLVMInt64TypeInContext(self.context) }
     }
@@ -61,6 +72,29 @@ impl Context {
     pub fn append_bb_to_func(&self, func: LLVMValueRef, name: &[u8]) -> LLVMBasicBlockRef {
         unsafe { LLVMAppendBasicBlockInContext(self.context, func, c_str(name)) }
     }
+
+    pub fn const_string(&self, s: &str) -> LLVMValueRef {
+        // the string is null terminated
+        unsafe {
+            LLVMConstStringInContext(
+                self.context,
+                s.as_ptr() as *const _,
+                s.len() as _,
+                false as _,
+            )
+        }
+    }
+
+    pub fn const_struct(&self, mut values: Vec<LLVMValueRef>, packed: bool) -> LLVMValueRef {
+        unsafe {
+            LLVMConstStructInContext(
+                self.context,
+                values.as_mut_ptr(),
+                values.len() as _,
+                packed as _,
+            )
+        }
+    }
 }
 
 impl Drop for Context {
@@ -89,6 +123,49 @@ impl Module {
         unsafe { LLVMGetNamedFunction(self.module, name.as_ptr()) }
     }
 
+    pub fn add_global(&self, ty: LLVMTypeRef, name: &CStr) -> LLVMValueRef {
+        unsafe { LLVMAddGlobal(self.module, ty, name.as_ptr()) }
+    }
+
+    pub fn get_named_global(&self, name: &CStr) -> LLVMValueRef {
+        unsafe { LLVMGetNamedGlobal(self.module, name.as_ptr()) }
+    }
+
+    /// A target machine for the host, the module is given its triple and data layout.
+    pub fn host_target_machine(&self) -> Result<LLVMTargetMachineRef, CString> {
+        unsafe {
+            llvm::target::LLVM_InitializeNativeTarget();
+            let triple = LLVMGetDefaultTargetTriple();
+            let mut target = ptr::null_mut();
+            let mut error: *mut libc::c_char = ptr::null_mut();
+            if LLVMGetTargetFromTriple(triple, &mut target, &mut error) != 0 {
+                LLVMDisposeMessage(triple);
+                return Err(CString::from_raw(error));
+            }
+
+            let target_machine = LLVMCreateTargetMachine(
+                target,
+                triple,
+                b"generic\0".as_ptr() as *const _,
+                b"\0".as_ptr() as *const _,
+                LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+                LLVMRelocMode::LLVMRelocPIC,
+                LLVMCodeModel::LLVMCodeModelDefault,
+            );
+
+            // the module must agree with the target machine before any emission
+            LLVMSetTarget(self.module, triple);
+            let data_layout = llvm::target_machine::LLVMCreateTargetDataLayout(target_machine);
+            let data_layout_str = llvm::target::LLVMCopyStringRepOfTargetData(data_layout);
+            LLVMSetDataLayout(self.module, data_layout_str);
+            LLVMDisposeMessage(data_layout_str);
+            llvm::target::LLVMDisposeTargetData(data_layout);
+            LLVMDisposeMessage(triple);
+
+            Ok(target_machine)
+        }
+    }
+
     /* currently unused
     pub fn load_runtime(&self, context: &Context) {
         let mut bytes: Vec<_> = include_bytes!("./runtime.ll").as_ref().into();
@@ -238,10 +315,42 @@ impl IRBuilder {
         unsafe { LLVMBuildSDiv(self.builder, lhs, rhs, c_str(name)) }
     }
 
+    pub fn build_udiv(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildUDiv(self.builder, lhs, rhs, c_str(name)) }
+    }
+
+    pub fn build_urem(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildURem(self.builder, lhs, rhs, c_str(name)) }
+    }
+
     pub fn build_srem(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
         unsafe { LLVMBuildSRem(self.builder, lhs, rhs, c_str(name)) }
     }
 
+    pub fn build_and(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildAnd(self.builder, lhs, rhs, c_str(name)) }
+    }
+
+    pub fn build_or(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildOr(self.builder, lhs, rhs, c_str(name)) }
+    }
+
+    pub fn build_xor(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildXor(self.builder, lhs, rhs, c_str(name)) }
+    }
+
+    pub fn build_shl(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildShl(self.builder, lhs, rhs, c_str(name)) }
+    }
+
+    pub fn build_ashr(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildAShr(self.builder, lhs, rhs, c_str(name)) }
+    }
+
+    pub fn build_lshr(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
+        unsafe { LLVMBuildLShr(self.builder, lhs, rhs, c_str(name)) }
+    }
+
     pub fn build_fadd(&self, lhs: LLVMValueRef, rhs: LLVMValueRef, name: &[u8]) -> LLVMValueRef {
         unsafe { LLVMBuildFAdd(self.builder, lhs, rhs, c_str(name)) }
     }
@@ -287,6 +396,15 @@ impl IRBuilder {
         unsafe { LLVMBuildZExt(self.builder, value, dest_ty, c_str(name)) }
     }
 
+    pub fn build_sext(
+        &self,
+        value: LLVMValueRef,
+        dest_ty: LLVMTypeRef,
+        name: &[u8],
+    ) -> LLVMValueRef {
+        unsafe { LLVMBuildSExt(self.builder, value, dest_ty, c_str(name)) }
+    }
+
     pub fn build_si_to_fp(
         &self,
         value: LLVMValueRef,
@@ -296,6 +414,24 @@ impl IRBuilder {
         unsafe { LLVMBuildSIToFP(self.builder, value, dest_ty, c_str(name)) }
     }
 
+    pub fn build_ui_to_fp(
+        &self,
+        value: LLVMValueRef,
+        dest_ty: LLVMTypeRef,
+        name: &[u8],
+    ) -> LLVMValueRef {
+        unsafe { LLVMBuildUIToFP(self.builder, value, dest_ty, c_str(name)) }
+    }
+
+    pub fn build_fp_to_ui(
+        &self,
+        value: LLVMValueRef,
+        dest_ty: LLVMTypeRef,
+        name: &[u8],
+    ) -> LLVMValueRef {
+        unsafe { LLVMBuildFPToUI(self.builder, value, dest_ty, c_str(name)) }
+    }
+
     pub fn build_fp_to_si(
         &self,
         value: LLVMValueRef,
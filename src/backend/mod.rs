@@ -21,8 +21,8 @@ pub fn llvm_codegen_program(
     program: ir::Program,
     strings: &Interner<String>,
     types: &trans::tables::TypeTable,
-) -> ExecutionModule {
-    let mut backend = Backend::new(strings, types);
+) -> Result<ExecutionModule, CString> {
+    let mut backend = Backend::new(strings, types)?;
 
     for decl in &program.declarations {
         match *decl {
@@ -30,6 +30,7 @@ pub fn llvm_codegen_program(
                 backend.pre_codegen_extern_function(exfunc)
             }
             ir::Declaration::Function(ref func) => backend.pre_codegen_function(func),
+            ir::Declaration::GlobalVariable(ref global) => backend.codegen_global(global),
         }
     }
 
@@ -39,7 +40,7 @@ pub fn llvm_codegen_program(
         }
     }
 
-    backend.into_exec_module()
+    Ok(backend.into_exec_module())
 }
 
 #[derive(Debug, Clone)]
@@ -57,12 +58,19 @@ struct Backend<'s, 't> {
 }
 
 impl<'s, 't> Backend<'s, 't> {
-    fn new(strings: &'s Interner<String>, tyctxt: &'t trans::tables::TypeTable) -> Self {
+    fn new(
+        strings: &'s Interner<String>,
+        tyctxt: &'t trans::tables::TypeTable,
+    ) -> Result<Self, CString> {
         let context = Context::new();
         let module = Module::new_in_context(&context, b"main\0");
         let builder = IRBuilder::new_in_context(&context);
 
-        Backend {
+        // the tagged unions are laid out with the data layout of the host
+        let target_machine = module.host_target_machine()?;
+        unsafe { llvm::target_machine::LLVMDisposeTargetMachine(target_machine) };
+
+        Ok(Backend {
             context,
             module,
             builder,
@@ -73,7 +81,7 @@ impl<'s, 't> Backend<'s, 't> {
             current_func: ptr::null_mut(),
             current_break: ptr::null_mut(),
             current_continue: ptr::null_mut(),
-        }
+        })
     }
 
     fn codegen_type(&mut self, ty: ty::Type) -> LLVMTypeRef {
@@ -83,8 +91,44 @@ impl<'s, 't> Backend<'s, 't> {
 
         let llvm_ty = match *ty {
             ty::TypeValue::Incomplete => panic!("Incomplete type in backend"),
+            ty::TypeValue::Error => panic!("Error type in backend"),
             ty::TypeValue::Void => self.context.void_ty(),
-            ty::TypeValue::Int => self.context.i32_ty(),
+            ty::TypeValue::Enum(ref enum_ty) if enum_ty.has_payload() => {
+                let name = format!("enum.{}", enum_ty.name);
+                let name = CString::new(name.clone()).unwrap();
+                let llvm_enum_ty = self.context.create_struct_named(name.as_bytes_with_nul());
+                self.ty_cache.insert(ty, llvm_enum_ty); // for recursive types
+
+                // the tag, then a buffer fitting the payload of any variant
+                let (mut size, mut align) = (0, 1);
+                for variant in &enum_ty.variants {
+                    let payload_ty = self.tyctxt.tuple_of(variant.payload.clone());
+                    let payload_ty = self.codegen_type(payload_ty);
+                    size = size.max(utils::abi_size_of(self.module.module, payload_ty));
+                    align = align.max(utils::abi_align_of(self.module.module, payload_ty));
+                }
+                let unit_ty = self.context.int_ty(align * 8);
+                let buffer_ty = utils::array_ty(unit_ty, (size + align - 1) / align);
+                let mut fields = vec![self.context.i32_ty(), buffer_ty];
+
+                unsafe {
+                    llvm::core::LLVMStructSetBody(
+                        llvm_enum_ty,
+                        fields.as_mut_ptr(),
+                        fields.len() as _,
+                        false as _,
+                    )
+                }
+                llvm_enum_ty
+            }
+            ty::TypeValue::Int | ty::TypeValue::Enum(_) => self.context.i32_ty(),
+            ty::TypeValue::Char => self.context.i8_ty(),
+            ty::TypeValue::SizedInt(kind) => match kind.bits() {
+                8 => self.context.i8_ty(),
+                16 => self.context.i16_ty(),
+                32 => self.context.i32_ty(),
+                _ => self.context.i64_ty(),
+            },
             ty::TypeValue::Double => self.context.double_ty(),
             ty::TypeValue::Boolean => self.context.i1_ty(),
             ty::TypeValue::String => utils::pointer_ty(self.context.i8_ty()),
@@ -118,6 +162,10 @@ impl<'s, 't> Backend<'s, 't> {
                 self.context.struct_ty(types, false)
             }
             ty::TypeValue::Array(sub, size) => utils::array_ty(self.codegen_type(sub), size),
+            ty::TypeValue::DynArray(sub) | ty::TypeValue::Slice(sub) => {
+                let data_ty = utils::pointer_ty(self.codegen_type(sub));
+                self.context.struct_ty(vec![self.context.i32_ty(), data_ty], false)
+            }
             ty::TypeValue::FunctionPtr(ref func_ty) => {
                 let func_ty = self.codegen_function_type(func_ty);
                 utils::pointer_ty(func_ty)
@@ -160,6 +208,61 @@ impl<'s, 't> Backend<'s, 't> {
         self.module.add_function(&c_name, func_ty);
     }
 
+    fn codegen_global(&mut self, global: &ir::GlobalVariable) {
+        let llvm_ty = self.codegen_type(global.ty);
+        let c_name = CString::new(global.name.clone()).unwrap();
+        let llvm_global = self.module.add_global(llvm_ty, &c_name);
+
+        let value = self.codegen_constant(global.ty, &global.value);
+        utils::set_initializer(llvm_global, value);
+        utils::set_global_constant(llvm_global, global.is_const);
+    }
+
+    fn codegen_constant(&mut self, ty: ty::Type, constant: &ir::Constant) -> LLVMValueRef {
+        let llvm_ty = self.codegen_type(ty);
+        match *constant {
+            ir::Constant::Literal(common::Literal::StringLiteral(id)) => {
+                // the builder can't make a global string outside of a function
+                let s = self.context.const_string(self.strings.get_ref(id));
+                let global = self.module.add_global(utils::type_of(s), &CString::new("").unwrap());
+                utils::set_initializer(global, s);
+                utils::set_global_constant(global, true);
+                utils::set_private_linkage(global);
+                utils::const_bitcast(global, llvm_ty)
+            }
+            ir::Constant::Literal(lit) => self.codegen_literal(lit),
+            ir::Constant::Null => utils::const_null(llvm_ty),
+            ir::Constant::Aggregate(ref constants) => match *ty {
+                ty::TypeValue::Struct(ref struct_ty) => {
+                    let values = struct_ty
+                        .fields
+                        .iter()
+                        .zip(constants)
+                        .map(|(&(_, ty), constant)| self.codegen_constant(ty, constant))
+                        .collect();
+                    utils::const_named_struct(llvm_ty, values)
+                }
+                ty::TypeValue::Tuple(ref types) => {
+                    let values = types
+                        .iter()
+                        .zip(constants)
+                        .map(|(&ty, constant)| self.codegen_constant(ty, constant))
+                        .collect();
+                    self.context.const_struct(values, false)
+                }
+                ty::TypeValue::Array(sub, _) => {
+                    let values = constants
+                        .iter()
+                        .map(|constant| self.codegen_constant(sub, constant))
+                        .collect();
+                    let sub_ty = self.codegen_type(sub);
+                    utils::const_array(sub_ty, values)
+                }
+                _ => panic!("Aggregate constant of a scalar type in backend"),
+            },
+        }
+    }
+
     fn codegen_function(&mut self, function: ir::Function) {
         let func_ref = self.module
             .get_named_function(&CString::new(function.name).unwrap());
@@ -334,11 +437,27 @@ impl<'s, 't> Backend<'s, 't> {
                 self.codegen_funccall(*function, args)
             }
             ir::Expression::FieldAccess { sub, index } => self.codegen_field_access(*sub, index),
+            ir::Expression::VariantPayload { sub, payload_ty } => {
+                let sub = self.codegen_expression(*sub);
+                let buffer = self.builder.build_struct_gep(sub, 1, b"\0");
+                let payload_ptr_ty = utils::pointer_ty(self.codegen_type(payload_ty));
+                self.builder.build_bitcast(buffer, payload_ptr_ty, b"\0")
+            }
             ir::Expression::Ternary {
                 condition,
                 true_expr,
                 false_expr,
             } => self.codegen_ternary(*condition, *true_expr, *false_expr),
+            ir::Expression::New(ty) => self.codegen_new(ty),
+            ir::Expression::NewArray { ty, size } => self.codegen_new_array(ty, *size),
+            ir::Expression::Delete(sub) => self.codegen_delete(*sub),
+            // constant expressions folded with the data layout of the module
+            ir::Expression::SizeOf(ty) => utils::size_of(self.codegen_type(ty)),
+            ir::Expression::AlignOf(ty) => utils::align_of(self.codegen_type(ty)),
+            ir::Expression::BoundsCheck { index, len, line } => {
+                self.codegen_bounds_check(*index, *len, line)
+            }
+            ir::Expression::LengthCheck { len, line } => self.codegen_length_check(*len, line),
         }
     }
 
@@ -347,13 +466,137 @@ impl<'s, 't> Backend<'s, 't> {
             ir::Value::Literal(lit) => self.codegen_literal(lit),
             ir::Value::Local(id) => self.codegen_identifier(id),
             ir::Value::Global(global_name) => {
-                // TODO: currently globals can only be functions
-                self.module
-                    .get_named_function(&CString::new(global_name.to_string()).unwrap())
+                // a function or the address of a global variable
+                let c_name = CString::new(global_name).unwrap();
+                let function = self.module.get_named_function(&c_name);
+                if function.is_null() {
+                    self.module.get_named_global(&c_name)
+                } else {
+                    function
+                }
             }
         }
     }
 
+    fn codegen_new(&mut self, ty: ty::Type) -> LLVMValueRef {
+        let llvm_ty = self.codegen_type(ty);
+        let i8_ptr_ty = utils::pointer_ty(self.context.i8_ty());
+        let malloc_ty = utils::function_ty(i8_ptr_ty, vec![self.context.i64_ty()], false);
+        let malloc = self.libc_function("malloc", malloc_ty);
+
+        let size = utils::size_of(llvm_ty);
+        let memory = self.builder.build_call(malloc, vec![size], b"\0");
+        let ptr = self.builder.build_bitcast(memory, utils::pointer_ty(llvm_ty), b"\0");
+        self.builder.build_store(utils::const_null(llvm_ty), ptr);
+        ptr
+    }
+
+    fn codegen_new_array(&mut self, ty: ty::Type, size: ir::Expression) -> LLVMValueRef {
+        let llvm_ty = self.codegen_type(ty);
+        let i64_ty = self.context.i64_ty();
+        let i8_ptr_ty = utils::pointer_ty(self.context.i8_ty());
+        let calloc_ty = utils::function_ty(i8_ptr_ty, vec![i64_ty, i64_ty], false);
+        let calloc = self.libc_function("calloc", calloc_ty);
+
+        let size = self.codegen_expression(size);
+        let count = self.builder.build_sext(size, i64_ty, b"\0");
+        let item_size = utils::size_of(llvm_ty);
+        let memory = self.builder.build_call(calloc, vec![count, item_size], b"\0");
+        self.builder.build_bitcast(memory, utils::pointer_ty(llvm_ty), b"\0")
+    }
+
+    fn codegen_delete(&mut self, sub: ir::Expression) -> LLVMValueRef {
+        let ptr = self.codegen_expression(sub);
+        let i8_ptr_ty = utils::pointer_ty(self.context.i8_ty());
+        let free_ty = utils::function_ty(self.context.void_ty(), vec![i8_ptr_ty], false);
+        let free = self.libc_function("free", free_ty);
+
+        let memory = self.builder.build_bitcast(ptr, i8_ptr_ty, b"\0");
+        self.builder.build_call(free, vec![memory], b"\0")
+    }
+
+    fn codegen_bounds_check(
+        &mut self,
+        index: ir::Expression,
+        len: ir::Expression,
+        line: usize,
+    ) -> LLVMValueRef {
+        let index = self.codegen_expression(index);
+        let len = self.codegen_expression(len);
+
+        let fail_bb = self.context
+            .append_bb_to_func(self.current_func, b"out_of_bounds_bb\0");
+        let ok_bb = self.context
+            .append_bb_to_func(self.current_func, b"in_bounds_bb\0");
+        // a negative index is a too big unsigned one
+        let in_bounds = self.builder
+            .build_icmp(llvm::LLVMIntPredicate::LLVMIntULT, index, len, b"\0");
+        self.builder.build_cond_br(in_bounds, ok_bb, fail_bb);
+
+        self.builder.position_at_end(fail_bb);
+        self.codegen_runtime_error("index %d is out of bounds at line %d\n", index, line);
+
+        self.builder.position_at_end(ok_bb);
+        index
+    }
+
+    fn codegen_length_check(&mut self, len: ir::Expression, line: usize) -> LLVMValueRef {
+        let len = self.codegen_expression(len);
+
+        let fail_bb = self.context
+            .append_bb_to_func(self.current_func, b"negative_length_bb\0");
+        let ok_bb = self.context
+            .append_bb_to_func(self.current_func, b"length_ok_bb\0");
+        let zero = utils::const_int(self.context.i32_ty(), 0, false);
+        let not_negative = self.builder
+            .build_icmp(llvm::LLVMIntPredicate::LLVMIntSGE, len, zero, b"\0");
+        self.builder.build_cond_br(not_negative, ok_bb, fail_bb);
+
+        self.builder.position_at_end(fail_bb);
+        self.codegen_runtime_error("negative array length %d at line %d\n", len, line);
+
+        self.builder.position_at_end(ok_bb);
+        len
+    }
+
+    // prints the message, formatted with the value and the line, and exits with code 1
+    fn codegen_runtime_error(&mut self, message: &str, value: LLVMValueRef, line: usize) {
+        let i32_ty = self.context.i32_ty();
+        let i8_ptr_ty = utils::pointer_ty(self.context.i8_ty());
+        let fflush_ty = utils::function_ty(i32_ty, vec![i8_ptr_ty], false);
+        let dprintf_ty = utils::function_ty(i32_ty, vec![i32_ty, i8_ptr_ty], true);
+        let exit_ty = utils::function_ty(self.context.void_ty(), vec![i32_ty], false);
+        let fflush = self.libc_function("fflush", fflush_ty);
+        let dprintf = self.libc_function("dprintf", dprintf_ty);
+        let exit = self.libc_function("exit", exit_ty);
+
+        // what the program printed before comes first
+        self.builder
+            .build_call(fflush, vec![utils::const_null(i8_ptr_ty)], b"\0");
+        let message = self.builder
+            .build_global_string_ptr(format!("runtime error: {}", message), b"\0");
+        let line = utils::const_int(i32_ty, line as _, false);
+        self.builder.build_call(
+            dprintf,
+            vec![utils::const_int(i32_ty, 2, false), message, value, line],
+            b"\0",
+        );
+        self.builder
+            .build_call(exit, vec![utils::const_int(i32_ty, 1, false)], b"\0");
+        self.builder.build_unreachable();
+    }
+
+    // the program can declare the same function as an extern, with its own types
+    fn libc_function(&mut self, name: &str, func_ty: LLVMTypeRef) -> LLVMValueRef {
+        let c_name = CString::new(name).unwrap();
+        let function = self.module.get_named_function(&c_name);
+        if function.is_null() {
+            self.module.add_function(&c_name, func_ty)
+        } else {
+            utils::const_bitcast(function, utils::pointer_ty(func_ty))
+        }
+    }
+
     fn codegen_expr_block(&mut self, block: ir::BlockExpression) -> LLVMValueRef {
         for stmt in block.stmts {
             self.codegen_statement(stmt);
@@ -380,6 +623,14 @@ impl<'s, 't> Backend<'s, 't> {
                 let ty = self.codegen_type(self.tyctxt.get_int_ty());
                 utils::const_int(ty, i, true)
             }
+            common::Literal::CharLiteral(c) => {
+                let ty = self.codegen_type(self.tyctxt.get_char_ty());
+                utils::const_int(ty, i64::from(c), false)
+            }
+            common::Literal::SizedIntLiteral(i, kind) => {
+                let ty = self.codegen_type(self.tyctxt.get_integer_ty(kind));
+                utils::const_int(ty, i, kind.is_signed())
+            }
             common::Literal::DoubleLiteral(d) => {
                 let ty = self.codegen_type(self.tyctxt.get_double_ty());
                 utils::const_real(ty, d)
@@ -446,6 +697,24 @@ impl<'s, 't> Backend<'s, 't> {
             };
         }
 
+        // like the interpreter, the shift amount is masked, a bigger one is poison in LLVM
+        macro_rules! shift_builder {
+            ($func:ident) => {
+                {
+                    fn tmp(b: &IRBuilder,
+                        l: LLVMValueRef,
+                        r: LLVMValueRef,
+                        n: &[u8]) -> LLVMValueRef {
+                        let ty = utils::type_of(l);
+                        let mask = utils::const_int(ty, i64::from(utils::int_width(ty) - 1), false);
+                        let r = b.build_and(r, mask, b"\0");
+                        b.$func(l, r, n)
+                    }
+                    tmp
+                }
+            };
+        }
+
         fn build_ptr_plus_offset(
             b: &IRBuilder,
             l: LLVMValueRef,
@@ -455,6 +724,27 @@ impl<'s, 't> Backend<'s, 't> {
             b.build_gep(l, vec![r], n)
         }
 
+        // the pointers compared with `nullptr` don't have its type
+        fn build_ptr_equal(
+            b: &IRBuilder,
+            l: LLVMValueRef,
+            r: LLVMValueRef,
+            n: &[u8],
+        ) -> LLVMValueRef {
+            let r = b.build_bitcast(r, utils::type_of(l), b"\0");
+            b.build_icmp(LLVMIntEQ, l, r, n)
+        }
+
+        fn build_ptr_not_equal(
+            b: &IRBuilder,
+            l: LLVMValueRef,
+            r: LLVMValueRef,
+            n: &[u8],
+        ) -> LLVMValueRef {
+            let r = b.build_bitcast(r, utils::type_of(l), b"\0");
+            b.build_icmp(LLVMIntNE, l, r, n)
+        }
+
         fn build_ptr_minus_offset(
             b: &IRBuilder,
             l: LLVMValueRef,
@@ -475,8 +765,16 @@ impl<'s, 't> Backend<'s, 't> {
             bok::IntMultiply => IRBuilder::build_mul,
             bok::DoubleMultiply => IRBuilder::build_fmul,
             bok::IntDivide => IRBuilder::build_sdiv,
+            bok::UIntDivide => IRBuilder::build_udiv,
             bok::DoubleDivide => IRBuilder::build_fdiv,
             bok::IntModulo => IRBuilder::build_srem,
+            bok::UIntModulo => IRBuilder::build_urem,
+            bok::IntAnd => IRBuilder::build_and,
+            bok::IntOr => IRBuilder::build_or,
+            bok::IntXor => IRBuilder::build_xor,
+            bok::IntShiftLeft => shift_builder!(build_shl),
+            bok::IntShiftRight => shift_builder!(build_ashr),
+            bok::UIntShiftRight => shift_builder!(build_lshr),
             bok::IntEqual => cmp_builder!(@i LLVMIntEQ),
             bok::DoubleEqual => cmp_builder!(@f LLVMRealUEQ),
             bok::BooleanEqual => cmp_builder!(@i LLVMIntEQ),
@@ -484,16 +782,30 @@ impl<'s, 't> Backend<'s, 't> {
             bok::DoubleNotEqual => cmp_builder!(@f LLVMRealUNE),
             bok::BooleanNotEqual => cmp_builder!(@i LLVMIntNE),
             bok::IntLess => cmp_builder!(@i LLVMIntSLT),
+            bok::UIntLess => cmp_builder!(@i LLVMIntULT),
             bok::DoubleLess => cmp_builder!(@f LLVMRealULT),
             bok::IntLessEqual => cmp_builder!(@i LLVMIntSLE),
+            bok::UIntLessEqual => cmp_builder!(@i LLVMIntULE),
             bok::DoubleLessEqual => cmp_builder!(@f LLVMRealULE),
             bok::IntGreater => cmp_builder!(@i LLVMIntSGT),
+            bok::UIntGreater => cmp_builder!(@i LLVMIntUGT),
             bok::DoubleGreater => cmp_builder!(@f LLVMRealUGT),
             bok::IntGreaterEqual => cmp_builder!(@i LLVMIntSGE),
+            bok::UIntGreaterEqual => cmp_builder!(@i LLVMIntUGE),
             bok::DoubleGreaterEqual => cmp_builder!(@f LLVMRealUGE),
             bok::PtrPlusOffset => build_ptr_plus_offset,
             bok::PtrMinusOffset => build_ptr_minus_offset,
             bok::PtrDiff => unimplemented!(),
+            // the string operators are calls to the runtime after the translation
+            bok::StringConcat
+            | bok::StringEqual
+            | bok::StringNotEqual
+            | bok::StringLess
+            | bok::StringLessEqual
+            | bok::StringGreater
+            | bok::StringGreaterEqual => unreachable!(),
+            bok::PtrEqual => build_ptr_equal,
+            bok::PtrNotEqual => build_ptr_not_equal,
         };
 
         func(&self.builder, lhs, rhs, b"\0")
@@ -504,15 +816,16 @@ impl<'s, 't> Backend<'s, 't> {
 
         match unop {
             ir::UnaryOperatorKind::IntMinus => {
-                let const0 =
-                    utils::const_int(self.codegen_type(self.tyctxt.get_int_ty()), 0, false);
+                let const0 = utils::const_int(utils::type_of(sub), 0, false);
                 self.builder.build_sub(const0, sub, b"\0")
             }
             ir::UnaryOperatorKind::DoubleMinus => {
                 let const0 = utils::const_real(self.codegen_type(self.tyctxt.get_double_ty()), 0.0);
                 self.builder.build_fsub(const0, sub, b"\0")
             }
-            ir::UnaryOperatorKind::BooleanNot => self.builder.build_not(sub, b"\0"),
+            ir::UnaryOperatorKind::BooleanNot | ir::UnaryOperatorKind::IntNot => {
+                self.builder.build_not(sub, b"\0")
+            }
             ir::UnaryOperatorKind::PointerDeref => sub,
         }
     }
@@ -532,9 +845,10 @@ impl<'s, 't> Backend<'s, 't> {
     fn codegen_incdecrement(&mut self, sub: ir::Expression, inc: bool) -> LLVMValueRef {
         let ptr = self.codegen_expression(sub);
 
-        let c1 = utils::const_int(self.codegen_type(self.tyctxt.get_int_ty()), 1, true);
-
         let value = self.builder.build_load(ptr, b"\0");
+        // the integer can be of any width
+        let c1 = utils::const_int(utils::type_of(value), 1, true);
+
         let value = if inc {
             self.builder.build_add(value, c1, b"\0")
         } else {
@@ -558,11 +872,28 @@ impl<'s, 't> Backend<'s, 't> {
         let llvm_boolean_ty = self.codegen_type(self.tyctxt.get_boolean_ty());
 
         match kind {
-            ir::CastKind::IntToDouble => self.builder.build_si_to_fp(sub, llvm_double_ty, b"\0"),
-            ir::CastKind::DoubleToInt => self.builder.build_fp_to_si(sub, llvm_int_ty, b"\0"),
+            ir::CastKind::IntToDouble(kind) if kind.is_signed() => {
+                self.builder.build_si_to_fp(sub, llvm_double_ty, b"\0")
+            }
+            ir::CastKind::IntToDouble(_) => self.builder.build_ui_to_fp(sub, llvm_double_ty, b"\0"),
+            ir::CastKind::DoubleToInt(kind) => {
+                let llvm_dest_ty = self.codegen_type(self.tyctxt.get_integer_ty(kind));
+                if kind.is_signed() {
+                    self.builder.build_fp_to_si(sub, llvm_dest_ty, b"\0")
+                } else {
+                    self.builder.build_fp_to_ui(sub, llvm_dest_ty, b"\0")
+                }
+            }
+            ir::CastKind::IntToInt(from, to) => self.codegen_int_cast(sub, from, to),
+            // a char is lowered like an `u8`
+            ir::CastKind::CharToInt(to) => self.codegen_int_cast(sub, ty::IntKind::U8, to),
+            ir::CastKind::IntToChar(from) => self.codegen_int_cast(sub, from, ty::IntKind::U8),
             ir::CastKind::BooleanToInt => self.builder.build_zext(sub, llvm_int_ty, b"\0"),
             ir::CastKind::IntToBoolean => self.builder.build_trunc(sub, llvm_boolean_ty, b"\0"),
-            ir::CastKind::PtrToInt => self.builder.build_ptr_to_int(sub, llvm_int_ty, b"\0"),
+            ir::CastKind::PtrToInt(kind) => {
+                let llvm_dest_ty = self.codegen_type(self.tyctxt.get_integer_ty(kind));
+                self.builder.build_ptr_to_int(sub, llvm_dest_ty, b"\0")
+            }
             ir::CastKind::IntToPtr(ptr) => {
                 let llvm_ptr_ty = self.codegen_type(ptr);
                 self.builder.build_int_to_ptr(sub, llvm_ptr_ty, b"\0")
@@ -570,6 +901,25 @@ impl<'s, 't> Backend<'s, 't> {
         }
     }
 
+    fn codegen_int_cast(
+        &mut self,
+        sub: LLVMValueRef,
+        from: ty::IntKind,
+        to: ty::IntKind,
+    ) -> LLVMValueRef {
+        let llvm_dest_ty = self.codegen_type(self.tyctxt.get_integer_ty(to));
+        // the signedness of the source chooses how it is widened
+        if from.bits() > to.bits() {
+            self.builder.build_trunc(sub, llvm_dest_ty, b"\0")
+        } else if from.bits() == to.bits() {
+            sub
+        } else if from.is_signed() {
+            self.builder.build_sext(sub, llvm_dest_ty, b"\0")
+        } else {
+            self.builder.build_zext(sub, llvm_dest_ty, b"\0")
+        }
+    }
+
     fn codegen_bitcast(&mut self, dest_ty: ty::Type, sub: ir::Expression) -> LLVMValueRef {
         let sub = self.codegen_expression(sub);
         let llvm_dest_ty = self.codegen_type(dest_ty);
@@ -622,6 +972,10 @@ impl<'s, 't> Backend<'s, 't> {
         self.builder.build_br(final_bb);
 
         self.builder.position_at_end(final_bb);
+        // the arms of a `match` used as a statement have no value
+        if utils::is_void(true_expr) {
+            return true_expr;
+        }
         self.builder.build_phi(
             vec![(true_expr, true_from), (false_expr, false_from)],
             b"\0",
@@ -1,4 +1,5 @@
 use libc;
+use llvm;
 use llvm::core::*;
 use llvm::prelude::*;
 
@@ -44,3 +45,58 @@ pub fn const_real(ty: LLVMTypeRef, r: f64) -> LLVMValueRef {
 pub fn type_of(v: LLVMValueRef) -> LLVMTypeRef {
     unsafe { LLVMTypeOf(v) }
 }
+
+pub fn int_width(ty: LLVMTypeRef) -> u32 {
+    unsafe { LLVMGetIntTypeWidth(ty) }
+}
+
+pub fn size_of(ty: LLVMTypeRef) -> LLVMValueRef {
+    unsafe { LLVMSizeOf(ty) }
+}
+
+pub fn align_of(ty: LLVMTypeRef) -> LLVMValueRef {
+    unsafe { LLVMAlignOf(ty) }
+}
+
+// the sizes known once the module has a data layout
+pub fn abi_size_of(module: LLVMModuleRef, ty: LLVMTypeRef) -> usize {
+    use llvm::target::*;
+    unsafe { LLVMABISizeOfType(LLVMGetModuleDataLayout(module), ty) as _ }
+}
+
+pub fn abi_align_of(module: LLVMModuleRef, ty: LLVMTypeRef) -> usize {
+    use llvm::target::*;
+    unsafe { LLVMABIAlignmentOfType(LLVMGetModuleDataLayout(module), ty) as _ }
+}
+
+pub fn is_void(v: LLVMValueRef) -> bool {
+    unsafe { LLVMGetTypeKind(LLVMTypeOf(v)) == llvm::LLVMTypeKind::LLVMVoidTypeKind }
+}
+
+pub fn const_null(ty: LLVMTypeRef) -> LLVMValueRef {
+    unsafe { LLVMConstNull(ty) }
+}
+
+pub fn const_named_struct(ty: LLVMTypeRef, mut values: Vec<LLVMValueRef>) -> LLVMValueRef {
+    unsafe { LLVMConstNamedStruct(ty, values.as_mut_ptr(), values.len() as _) }
+}
+
+pub fn const_array(sub_ty: LLVMTypeRef, mut values: Vec<LLVMValueRef>) -> LLVMValueRef {
+    unsafe { LLVMConstArray(sub_ty, values.as_mut_ptr(), values.len() as _) }
+}
+
+pub fn const_bitcast(value: LLVMValueRef, ty: LLVMTypeRef) -> LLVMValueRef {
+    unsafe { LLVMConstBitCast(value, ty) }
+}
+
+pub fn set_initializer(global: LLVMValueRef, value: LLVMValueRef) {
+    unsafe { LLVMSetInitializer(global, value) }
+}
+
+pub fn set_global_constant(global: LLVMValueRef, is_const: bool) {
+    unsafe { LLVMSetGlobalConstant(global, is_const as _) }
+}
+
+pub fn set_private_linkage(global: LLVMValueRef) {
+    unsafe { LLVMSetLinkage(global, llvm::LLVMLinkage::LLVMPrivateLinkage) }
+}
@@ -12,7 +12,7 @@ impl<'name, 'input> CodeMap<'name, 'input> {
 
         fn string_builder(input: &str, start: usize, end: usize) -> &str {
             let s = &input[start..end];
-            s.trim_right_matches(|c| c == '\r' || c == '\n')
+            s.trim_end_matches(['\r', '\n'])
         }
 
         for (index, c) in input.char_indices() {
@@ -45,8 +45,58 @@ impl<'name, 'input> CodeMap<'name, 'input> {
             }
         }
         SourceLocation {
-            line: self.lines.len(),
-            column: self.lines.last().map(|&(s, e, _)| e - s).unwrap(),
+            line: self.lines.len().max(1),
+            column: self.lines.last().map_or(0, |&(s, e, _)| e - s),
+        }
+    }
+
+    // the column is clamped to the end of its line
+    pub fn sourceloc_to_bytepos(&self, loc: SourceLocation) -> usize {
+        if let Some(&(start, end, _)) = self.lines.get(loc.line.wrapping_sub(1)) {
+            (start + loc.column).min(end)
+        } else {
+            self.input.len()
+        }
+    }
+
+    /// The text of a line without its line break, lines are 1-based.
+    pub fn line(&self, line: usize) -> &'input str {
+        self.lines
+            .get(line.wrapping_sub(1))
+            .map_or("", |&(_, _, s)| s)
+    }
+
+    pub fn print_error_line(&self, span: Span) {
+        let input = self.input;
+        let mut arrow = String::with_capacity(input.len());
+
+        for (i, c) in input.chars().enumerate() {
+            arrow.push(match c {
+                '\n' => '\n',
+                '\t' => '\t',
+                '\r' => '\r',
+                _ if span.start <= i && i < span.end => '^',
+                _ => ' ',
+            });
+        }
+
+        if span.end >= input.len() {
+            unsafe {
+                let bytes = arrow.as_bytes_mut();
+                *bytes.last_mut().unwrap() = b'^';
+            }
+        }
+
+        let iter = input
+            .lines()
+            .map(String::from)
+            .zip(arrow.lines().map(String::from))
+            .enumerate()
+            .filter(|&(_, (_, ref arrow))| arrow.contains('^'));
+
+        for (n, (line, arrow)) in iter {
+            eprintln!("{:05}|{}", n + 1, line);
+            eprintln!("     |{}", arrow);
         }
     }
 }
@@ -59,7 +109,7 @@ pub struct Span {
 
 impl Span {
     pub fn dummy() -> Self {
-        Span::new(0, usize::max_value())
+        Span::new(0, usize::MAX)
     }
 
     pub fn new(start: usize, end: usize) -> Self {
@@ -5,8 +5,10 @@ use trans::tables::TypeTable;
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Literal {
     IntLiteral(i64),
+    SizedIntLiteral(i64, ty::IntKind), // the bits of the value, unsigned ones included
     DoubleLiteral(f64),
     BooleanLiteral(bool),
+    CharLiteral(u8),
     StringLiteral(InternerId),
 }
 
@@ -15,8 +17,10 @@ impl Literal {
         use self::Literal::*;
         match *self {
             IntLiteral(_) => ty_ctxt.get_int_ty(),
+            SizedIntLiteral(_, kind) => ty_ctxt.get_integer_ty(kind),
             DoubleLiteral(_) => ty_ctxt.get_double_ty(),
             BooleanLiteral(_) => ty_ctxt.get_boolean_ty(),
+            CharLiteral(_) => ty_ctxt.get_char_ty(),
             StringLiteral(_) => ty_ctxt.get_string_ty(),
         }
     }
@@ -9,6 +9,7 @@ pub struct Context {
     ty_arena: Mutex<Arena<ty::TypeValue>>,
     ty_interner: Mutex<HashMap<ty::TypeValue, ty::Type>>,
     struct_arena: Mutex<Arena<ty::StructTypeValue>>,
+    enum_arena: Mutex<Arena<ty::EnumTypeValue>>,
 }
 
 impl Context {
@@ -17,6 +18,7 @@ impl Context {
             ty_arena: Mutex::new(Arena::new()),
             ty_interner: Mutex::new(HashMap::new()),
             struct_arena: Mutex::new(Arena::new()),
+            enum_arena: Mutex::new(Arena::new()),
         }
     }
 
@@ -31,7 +33,16 @@ impl Context {
         }
     }
 
+    // allocate a type that is not shared with other equal types, to be completed later
+    pub fn alloc_unique_type(&self, value: ty::TypeValue) -> ty::Type {
+        ty::Type::from_raw(self.ty_arena.lock().unwrap().alloc(value))
+    }
+
     pub fn alloc_struct_type(&self, value: ty::StructTypeValue) -> ty::StructType {
         ty::StructType::from_raw(self.struct_arena.lock().unwrap().alloc(value))
     }
+
+    pub fn alloc_enum_type(&self, value: ty::EnumTypeValue) -> ty::EnumType {
+        ty::EnumType::from_raw(self.enum_arena.lock().unwrap().alloc(value))
+    }
 }
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use codemap::{CodeMap, SourceLocation, Span, Spanned};
+use errors::{Lint, UserError, Warning, LINTS};
+use json::Json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// The level of every lint, set from the -A, -W and -D flags.
+#[derive(Debug, Clone)]
+pub struct LintLevels(HashMap<Lint, LintLevel>);
+
+impl Default for LintLevels {
+    fn default() -> Self {
+        let mut levels = HashMap::new();
+        for &lint in &LINTS {
+            // shadowing in a nested block is allowed by the language, so only on request
+            let level = match lint {
+                Lint::ShadowedLocals => LintLevel::Allow,
+                _ => LintLevel::Warn,
+            };
+            levels.insert(lint, level);
+        }
+        LintLevels(levels)
+    }
+}
+
+impl LintLevels {
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.0.insert(lint, level);
+    }
+
+    pub fn set_all(&mut self, level: LintLevel) {
+        for &lint in &LINTS {
+            self.set(lint, level);
+        }
+    }
+
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.0[&lint]
+    }
+}
+
+struct Diagnostic<'a> {
+    severity: &'a str,
+    code: &'a str,
+    kind: &'a str,
+    message: String,
+    lint: Option<Lint>,
+}
+
+/// Print an error to stderr, either for people or as one JSON object per line for tools.
+pub fn emit_error(codemap: &CodeMap, error: &Spanned<UserError>, format: ErrorFormat) {
+    let diagnostic = Diagnostic {
+        severity: "error",
+        code: error.inner.code(),
+        kind: error.inner.kind(),
+        message: error.inner.to_string(),
+        lint: None,
+    };
+    emit(codemap, &diagnostic, error.span, format);
+}
+
+/// Print a warning at the level of its lint, return true if the lint is denied.
+pub fn emit_warning(
+    codemap: &CodeMap,
+    warning: &Spanned<Warning>,
+    levels: &LintLevels,
+    format: ErrorFormat,
+) -> bool {
+    let lint = warning.inner.lint();
+    let severity = match levels.level(lint) {
+        LintLevel::Allow => return false,
+        LintLevel::Warn => "warning",
+        LintLevel::Deny => "error",
+    };
+
+    let diagnostic = Diagnostic {
+        severity,
+        code: warning.inner.code(),
+        kind: warning.inner.kind(),
+        message: warning.inner.to_string(),
+        lint: Some(lint),
+    };
+    emit(codemap, &diagnostic, warning.span, format);
+    levels.level(lint) == LintLevel::Deny
+}
+
+fn emit(codemap: &CodeMap, diagnostic: &Diagnostic, span: Span, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Human => {
+            let source_loc = codemap.bytepos_to_sourceloc(span.start);
+            eprintln!(
+                "{}:{}:{}: {}[{}]: {}",
+                codemap.input_name,
+                source_loc.line,
+                source_loc.column,
+                diagnostic.severity,
+                diagnostic.code,
+                diagnostic.message
+            );
+            codemap.print_error_line(span);
+            if let Some(lint) = diagnostic.lint {
+                eprintln!("note: reported by the lint '{}'", lint.name());
+            }
+        }
+        ErrorFormat::Json => eprintln!("{}", diagnostic_to_json(codemap, diagnostic, span)),
+    }
+}
+
+fn diagnostic_to_json(codemap: &CodeMap, diagnostic: &Diagnostic, span: Span) -> Json {
+    // the span of the errors without location covers the whole input
+    let start = span.start.min(codemap.input.len());
+    let end = span.end.min(codemap.input.len());
+
+    Json::object(vec![
+        ("file", codemap.input_name.into()),
+        ("severity", diagnostic.severity.into()),
+        ("code", diagnostic.code.into()),
+        ("kind", diagnostic.kind.into()),
+        ("message", diagnostic.message.clone().into()),
+        (
+            "span",
+            Json::object(vec![("start", start.into()), ("end", end.into())]),
+        ),
+        ("start", json_location(codemap.bytepos_to_sourceloc(start))),
+        ("end", json_location(codemap.bytepos_to_sourceloc(end))),
+    ])
+}
+
+fn json_location(loc: SourceLocation) -> Json {
+    Json::object(vec![("line", loc.line.into()), ("column", loc.column.into())])
+}
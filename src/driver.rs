@@ -2,13 +2,15 @@ extern crate clap;
 extern crate yal;
 
 use std::fs::File;
-use std::io::{self, Read};
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, SubCommand};
 
 use yal::*;
-use codemap::{Span, Spanned};
+use codemap::Spanned;
+use backend::execution_module::EmitKind;
+use diagnostics::{ErrorFormat, LintLevel, LintLevels};
 
 fn slurp_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
     let mut file = File::open(path)?;
@@ -17,36 +19,11 @@ fn slurp_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
     Ok(buffer)
 }
 
-fn print_error_line(input: &str, span: Span) {
-    let mut arrow = String::with_capacity(input.len());
-
-    for (i, c) in input.chars().enumerate() {
-        arrow.push(match c {
-            '\n' => '\n',
-            '\t' => '\t',
-            '\r' => '\r',
-            _ if span.start <= i && i < span.end => '^',
-            _ => ' ',
-        });
-    }
-
-    if span.end >= input.len() {
-        unsafe {
-            let bytes = arrow.as_bytes_mut();
-            *bytes.last_mut().unwrap() = b'^';
-        }
-    }
-
-    let iter = input
-        .lines()
-        .map(String::from)
-        .zip(arrow.lines().map(String::from))
-        .enumerate()
-        .filter(|&(_, (_, ref arrow))| arrow.contains('^'));
-
-    for (n, (line, arrow)) in iter {
-        eprintln!("{:05}|{}", n + 1, line);
-        eprintln!("     |{}", arrow);
+// the paths are compared as given when one of them doesn't exist yet
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
     }
 }
 
@@ -72,6 +49,11 @@ struct Options<'a> {
     print_ir: bool,
     print_ast: bool,
     print_llvm: bool,
+    emit: Option<EmitKind>,
+    output_path: Option<&'a str>,
+    error_format: ErrorFormat,
+    lint_levels: LintLevels,
+    test: bool,
 }
 
 impl<'a> Options<'a> {
@@ -103,6 +85,42 @@ impl<'a> Options<'a> {
             _ => OptOption::Default,
         };
 
+        let emit = match matches.value_of("EMIT") {
+            Some("obj") => Some(EmitKind::Object),
+            Some("asm") => Some(EmitKind::Assembly),
+            Some("llvm-ir") => Some(EmitKind::LLVMIR),
+            Some("bc") => Some(EmitKind::Bitcode),
+            Some("exe") => Some(EmitKind::Executable),
+            _ => None,
+        };
+
+        let error_format = match matches.value_of("ERROR_FORMAT") {
+            Some("json") => ErrorFormat::Json,
+            _ => ErrorFormat::Human,
+        };
+
+        // the flags apply in the order of the command line, the last one wins
+        let mut flags = Vec::new();
+        for &(flag, level) in &[
+            ("ALLOW", LintLevel::Allow),
+            ("WARN", LintLevel::Warn),
+            ("DENY", LintLevel::Deny),
+        ] {
+            let names = matches.values_of(flag).into_iter().flatten();
+            let indices = matches.indices_of(flag).into_iter().flatten();
+            flags.extend(indices.zip(names).map(|(index, name)| (index, name, level)));
+        }
+        flags.sort_by_key(|&(index, _, _)| index);
+
+        let mut lint_levels = LintLevels::default();
+        for (_, name, level) in flags {
+            if let Some(lint) = errors::Lint::from_name(name) {
+                lint_levels.set(lint, level);
+            } else {
+                lint_levels.set_all(level);
+            }
+        }
+
         Options {
             input_path,
             backend,
@@ -110,6 +128,25 @@ impl<'a> Options<'a> {
             print_ir,
             print_ast,
             print_llvm,
+            emit,
+            output_path: matches.value_of("OUTPUT"),
+            error_format,
+            lint_levels,
+            test: matches.is_present("TEST"),
+        }
+    }
+
+    fn emit_path(&self, kind: EmitKind) -> PathBuf {
+        if let Some(path) = self.output_path {
+            return PathBuf::from(path);
+        }
+        let input = Path::new(self.input_path);
+        let path = input.with_extension(kind.default_extension());
+        // an executable built from an input without extension would take its name
+        if path == input {
+            PathBuf::from(format!("{}.out", self.input_path))
+        } else {
+            path
         }
     }
 }
@@ -120,34 +157,40 @@ fn compile_program(
     tables: &mut trans::tables::Tables,
     previous: Option<ir::Program>,
     print_ast: bool,
-) -> Result<ir::Program, Spanned<errors::UserError>> {
+    warnings: &mut trans::Warnings,
+) -> Result<ir::Program, Vec<Spanned<errors::UserError>>> {
     let lexer = lexer::Lexer::new(input);
-    let ast = parser::parse_program(lexer, string_interner)?;
+    let ast = parser::parse_program(lexer, string_interner).map_err(errors::into_user_errors)?;
 
     if print_ast {
         eprintln!("{:#?}", ast);
     }
 
-    let ir = trans::translate_program(tables, ast, previous)?;
+    tables.set_source(input);
+    let ir = trans::translate_program(tables, ast, previous, warnings)
+        .map_err(errors::into_user_errors)?;
     Ok(ir)
 }
 
+struct CompiledProgram {
+    program: ir::Program,
+    strings: interner::Interner<String>,
+    tables: trans::tables::Tables,
+}
+
 fn do_compilation(
     options: &Options,
     input: &str,
-) -> Result<backend::execution_module::ExecutionModule, Spanned<errors::UserError>> {
+    warnings: &mut trans::Warnings,
+) -> Result<CompiledProgram, Vec<Spanned<errors::UserError>>> {
     let mut string_interner = interner::Interner::<String>::new();
     let mut tables = trans::tables::Tables::default();
 
-    // load runtime
-    let runtime_input = include_str!("../runtime/io.yal");
-    let runtime = compile_program(
-        runtime_input,
-        &mut string_interner,
-        &mut tables,
-        None,
-        false,
-    )?;
+    let runtime = trans::translate_runtime(&mut string_interner, &mut tables);
+
+    if options.test {
+        tables.tests = Some(Vec::new());
+    }
 
     let main = compile_program(
         input,
@@ -155,8 +198,12 @@ fn do_compilation(
         &mut tables,
         Some(runtime),
         options.print_ast,
+        warnings,
     )?;
-    trans::check_if_main_declaration(&tables, &main)?;
+    // the tests are run without main
+    if !options.test {
+        trans::check_if_main_declaration(&tables, &main).map_err(|err| vec![err.into()])?;
+    }
 
     if options.print_ir {
         let mut w = std::io::stderr();
@@ -164,7 +211,30 @@ fn do_compilation(
         pp.pp_program(&main).expect("ir_pp error");
     }
 
-    let mut llvm_exec = backend::llvm_codegen_program(main, &string_interner, &tables.types);
+    Ok(CompiledProgram {
+        program: main,
+        strings: string_interner,
+        tables,
+    })
+}
+
+// exit when LLVM can't target the host
+fn do_llvm_codegen(
+    options: &Options,
+    compiled: &CompiledProgram,
+) -> backend::execution_module::ExecutionModule {
+    let llvm_exec = backend::llvm_codegen_program(
+        compiled.program.clone(),
+        &compiled.strings,
+        &compiled.tables.types,
+    );
+    let mut llvm_exec = match llvm_exec {
+        Ok(llvm_exec) => llvm_exec,
+        Err(err) => {
+            eprintln!("{}: {}", options.input_path, err.to_string_lossy());
+            std::process::exit(1);
+        }
+    };
     llvm_exec.verify_module();
     match options.opt {
         OptOption::None => {}
@@ -175,13 +245,84 @@ fn do_compilation(
     if options.print_llvm {
         llvm_exec.print_module();
     }
-    Ok(llvm_exec)
+    llvm_exec
+}
+
+fn run_tests(matches: &clap::ArgMatches) -> i32 {
+    let jobs = match matches.value_of("JOBS").map(str::parse) {
+        Some(Ok(jobs)) => jobs,
+        Some(Err(_)) => {
+            eprintln!("the number of jobs must be an integer");
+            return 1;
+        }
+        None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let options = testsuite::TestOptions {
+        backend: matches.value_of("BACKEND").unwrap_or("jit").to_string(),
+        jobs,
+    };
+
+    let dir = matches.value_of("DIR").unwrap();
+    match testsuite::run_tests(Path::new(dir), &options) {
+        Ok(0) => 0,
+        Ok(_) => 1,
+        Err(err) => {
+            eprintln!("{}: {}", dir, err);
+            1
+        }
+    }
+}
+
+fn format_files(matches: &clap::ArgMatches) -> i32 {
+    let check = matches.is_present("CHECK");
+    let stdout = matches.is_present("STDOUT");
+
+    let mut exit_code = 0;
+    for path in matches.values_of("FILES").unwrap() {
+        let input = match slurp_file(path) {
+            Ok(input) => input,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                exit_code = 1;
+                continue;
+            }
+        };
+
+        match formatter::format_program(&input) {
+            Ok(ref formatted) if check => if *formatted != input {
+                println!("{}: not formatted", path);
+                exit_code = 1;
+            },
+            Ok(ref formatted) if stdout => print!("{}", formatted),
+            Ok(formatted) => if formatted != input {
+                let written =
+                    File::create(path).and_then(|mut file| file.write_all(formatted.as_bytes()));
+                if let Err(err) = written {
+                    eprintln!("{}: {}", path, err);
+                    exit_code = 1;
+                }
+            },
+            Err(errors) => {
+                let codemap = codemap::CodeMap::new(path, &input);
+                for error in errors::into_user_errors(errors) {
+                    diagnostics::emit_error(&codemap, &error, ErrorFormat::Human);
+                }
+                exit_code = 1;
+            }
+        }
+    }
+    exit_code
 }
 
 fn main() {
+    let mut lint_names = vec!["warnings"];
+    lint_names.extend(errors::LINTS.iter().map(|lint| lint.name()));
+
     let matches = App::new("Javalette interpreter")
         .version("0.1")
         .author("Paul CACHEUX <paulcacheux@gmail.com>")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .setting(AppSettings::InferSubcommands)
         .arg(
             Arg::with_name("INPUT")
                 .help("Sets the input file.")
@@ -203,6 +344,60 @@ fn main() {
                 .takes_value(true)
                 .possible_values(&["jit", "interpreter", "check"]),
         )
+        .arg(
+            Arg::with_name("EMIT")
+                .help("Emit a compiled artifact.")
+                .long("emit")
+                .takes_value(true)
+                .possible_values(&["obj", "asm", "llvm-ir", "bc", "exe"]),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .help("Sets the output file of --emit.")
+                .short("o")
+                .takes_value(true)
+                .requires("EMIT"),
+        )
+        .arg(
+            Arg::with_name("ERROR_FORMAT")
+                .help("Choose how errors are printed. Human readable by default.")
+                .long("error-format")
+                .takes_value(true)
+                .possible_values(&["human", "json"]),
+        )
+        .arg(
+            Arg::with_name("ALLOW")
+                .help("Silence a lint, or all of them with 'warnings'.")
+                .short("A")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .possible_values(&lint_names),
+        )
+        .arg(
+            Arg::with_name("WARN")
+                .help("Report a lint as a warning, or all of them with 'warnings'.")
+                .short("W")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .possible_values(&lint_names),
+        )
+        .arg(
+            Arg::with_name("DENY")
+                .help("Report a lint as an error, or all of them with 'warnings'.")
+                .short("D")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .possible_values(&lint_names),
+        )
+        .arg(
+            Arg::with_name("TEST")
+                .help("Run the test functions with the JIT instead of main.")
+                .long("test")
+                .conflicts_with_all(&["BACKEND", "EMIT"]),
+        )
         .arg(
             Arg::with_name("DEBUG")
                 .help("Print debug information to stderr.")
@@ -211,29 +406,166 @@ fn main() {
                 .multiple(true)
                 .possible_values(&["ir", "ast", "llvm"]),
         )
+        .subcommand(SubCommand::with_name("repl").about("Start an interactive session."))
+        .subcommand(SubCommand::with_name("lsp").about("Start a language server on stdio."))
+        .subcommand(
+            SubCommand::with_name("test")
+                .about("Run the tests of a directory.")
+                .arg(
+                    Arg::with_name("DIR")
+                        .help("The directory holding the tests.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("BACKEND")
+                        .help("Choose the backend running the programs. JIT by default.")
+                        .long("backend")
+                        .takes_value(true)
+                        .possible_values(&["jit", "interpreter"]),
+                )
+                .arg(
+                    Arg::with_name("JOBS")
+                        .help("The number of tests run in parallel.")
+                        .short("j")
+                        .long("jobs")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Format source files in place.")
+                .arg(
+                    Arg::with_name("FILES")
+                        .help("The files to format.")
+                        .required(true)
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("CHECK")
+                        .help("Only report the files that are not formatted.")
+                        .long("check"),
+                )
+                .arg(
+                    Arg::with_name("STDOUT")
+                        .help("Print the formatted files instead of rewriting them.")
+                        .long("stdout")
+                        .conflicts_with("CHECK"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Explain an error or warning code.")
+                .arg(
+                    Arg::with_name("CODE")
+                        .help("The code of an error or a warning, like E0012.")
+                        .required(true),
+                ),
+        )
         .get_matches();
 
+    if matches.subcommand_matches("repl").is_some() {
+        repl::Repl::new().run();
+        return;
+    }
+
+    if matches.subcommand_matches("lsp").is_some() {
+        std::process::exit(lsp::Server::new().run());
+    }
+
+    if let Some(matches) = matches.subcommand_matches("test") {
+        std::process::exit(run_tests(matches));
+    }
+
+    if let Some(matches) = matches.subcommand_matches("fmt") {
+        std::process::exit(format_files(matches));
+    }
+
+    if let Some(matches) = matches.subcommand_matches("explain") {
+        let code = matches.value_of("CODE").unwrap();
+        if let Some(explanation) = explanations::explain(code) {
+            print!("{}", explanation);
+            return;
+        } else {
+            eprintln!("{} is not an error code", code);
+            std::process::exit(1);
+        }
+    }
+
     let options = Options::from_matches(&matches);
 
     let input = slurp_file(options.input_path).unwrap(); // check for errors
     let codemap = codemap::CodeMap::new(options.input_path, &input);
 
-    let llvm_exec = match do_compilation(&options, &input) {
-        Ok(exec) => exec,
-        Err(Spanned { inner: error, span }) => {
-            let source_loc = codemap.bytepos_to_sourceloc(span.start);
-            eprintln!(
-                "{}:{}:{}: {}",
-                options.input_path, source_loc.line, source_loc.column, error
-            );
-            print_error_line(codemap.input, span);
+    let mut warnings = Vec::new();
+    let result = do_compilation(&options, &input, &mut warnings);
+
+    let mut denied = false;
+    for warning in &warnings {
+        denied |= diagnostics::emit_warning(
+            &codemap,
+            warning,
+            &options.lint_levels,
+            options.error_format,
+        );
+    }
+
+    let compiled = match result {
+        Ok(_) if denied => std::process::exit(1),
+        Ok(compiled) => compiled,
+        Err(errors) => {
+            for error in &errors {
+                diagnostics::emit_error(&codemap, error, options.error_format);
+            }
             std::process::exit(1);
         }
     };
 
-    match options.backend {
-        BackendType::Check => {}
-        BackendType::JIT => llvm_exec.jit_main().expect("run jit error"),
-        BackendType::Interpreter => llvm_exec.interpret_main().expect("run interpreter error"),
+    if options.test {
+        let llvm_exec = do_llvm_codegen(&options, &compiled);
+        let tests = compiled.tables.tests.as_ref().unwrap();
+        let failures = test_harness::run_tests(&llvm_exec, tests, options.input_path, &input);
+        std::process::exit(if failures == 0 { 0 } else { 1 });
     }
+
+    let mut exit_code = 0;
+
+    // the interpreter works on the IR, LLVM is only needed for the other outputs
+    if options.backend != BackendType::Interpreter || options.emit.is_some()
+        || options.print_llvm
+    {
+        let llvm_exec = do_llvm_codegen(&options, &compiled);
+
+        if let Some(kind) = options.emit {
+            let path = options.emit_path(kind);
+            if is_same_file(&path, Path::new(options.input_path)) {
+                eprintln!("{}: the output would overwrite the input", path.display());
+                std::process::exit(1);
+            }
+            if let Err(err) = llvm_exec.emit(kind, &path) {
+                eprintln!("{}: {}", path.display(), err.to_string_lossy());
+                std::process::exit(1);
+            }
+        }
+
+        if options.backend == BackendType::JIT {
+            exit_code = llvm_exec.jit_main().expect("run jit error");
+        }
+    }
+
+    if options.backend == BackendType::Interpreter {
+        match interpreter::interpret_program(
+            &compiled.program,
+            &compiled.strings,
+            &compiled.tables.types,
+        ) {
+            Ok(code) => exit_code = code,
+            Err(err) => {
+                eprintln!("{}: runtime error: {}", options.input_path, err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // main's result is the exit code of the program, like a native executable
+    std::process::exit(exit_code);
 }
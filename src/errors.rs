@@ -15,6 +15,8 @@ pub enum LexingError {
     UnparsableNumber,
     ReservedIdentifier(String),
     UnknownChar(char),
+    InvalidCharLiteral,
+    InvalidStringEscape,
 }
 
 #[derive(Debug, Clone)]
@@ -58,6 +60,47 @@ pub enum TranslationError {
     LengthOnNonArray(ty::Type),
     MemberUndefined,
     UnexpectedVoid,
+    TestWrongType,
+    AssertOutsideTest,
+    GlobalAlreadyDefined(String),
+    NonConstantInitializer,
+    DeleteNonPointer(ty::Type),
+    SliceUnsized(ty::Type),
+    LiteralOutOfRange(ty::Type),
+    NonEnumType(String),
+    UndefinedVariant(String, String), // enum, variant
+    VariantAlreadyDefined(String),
+    NonExhaustiveMatch(Vec<String>), // the missing variants
+    VariantArityMismatch(String, usize, usize), // variant, expected, given
+    MatchNonEnum(ty::Type),
+    SharedDiscriminant(String),
+}
+
+/// The lints reported as warnings, each one can be allowed, warned or denied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedParameters,
+    UnusedFunctions,
+    UnreachableCode,
+    ShadowedLocals,
+}
+
+pub const LINTS: [Lint; 5] = [
+    Lint::UnusedVariables,
+    Lint::UnusedParameters,
+    Lint::UnusedFunctions,
+    Lint::UnreachableCode,
+    Lint::ShadowedLocals,
+];
+
+#[derive(Debug, Clone)]
+pub enum Warning {
+    UnusedVariable(String),
+    UnusedParameter(String),
+    UnusedFunction(String),
+    UnreachableCode,
+    ShadowedLocal(String),
 }
 
 impl From<ParsingError> for UserError {
@@ -96,6 +139,232 @@ spanned_hack!(LexingError => ParsingError);
 spanned_hack!(ParsingError => UserError);
 spanned_hack!(TranslationError => UserError);
 
+pub fn into_user_errors<E>(errors: Vec<Spanned<E>>) -> Vec<Spanned<UserError>>
+where
+    Spanned<E>: Into<Spanned<UserError>>,
+{
+    errors.into_iter().map(Into::into).collect()
+}
+
+impl UserError {
+    /// The stable code of the error, explained by `yalc explain`.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            UserError::Parsing(ref pe) => pe.code(),
+            UserError::Translation(ref te) => te.code(),
+        }
+    }
+
+    /// A stable name for the kind of the error, for tools consuming the diagnostics.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            UserError::Parsing(ref pe) => pe.kind(),
+            UserError::Translation(ref te) => te.kind(),
+        }
+    }
+}
+
+impl LexingError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            LexingError::UnparsableNumber => "E0001",
+            LexingError::ReservedIdentifier(_) => "E0002",
+            LexingError::UnknownChar(_) => "E0003",
+            LexingError::InvalidCharLiteral => "E0045",
+            LexingError::InvalidStringEscape => "E0053",
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            LexingError::UnparsableNumber => "lexing.unparsable_number",
+            LexingError::ReservedIdentifier(_) => "lexing.reserved_identifier",
+            LexingError::UnknownChar(_) => "lexing.unknown_char",
+            LexingError::InvalidCharLiteral => "lexing.invalid_char_literal",
+            LexingError::InvalidStringEscape => "lexing.invalid_string_escape",
+        }
+    }
+}
+
+impl ParsingError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ParsingError::LexingError(ref le) => le.code(),
+            ParsingError::Unexpected(_) => "E0004",
+            ParsingError::InvalidType => "E0005",
+            ParsingError::UnexpectedVoid => "E0006",
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            ParsingError::LexingError(ref le) => le.kind(),
+            ParsingError::Unexpected(_) => "parsing.unexpected",
+            ParsingError::InvalidType => "parsing.invalid_type",
+            ParsingError::UnexpectedVoid => "parsing.unexpected_void",
+        }
+    }
+}
+
+impl TranslationError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            TranslationError::FunctionAlreadyDefined(_) => "E0007",
+            TranslationError::ParameterAlreadyDefined(_) => "E0008",
+            TranslationError::LocalAlreadyDefined(_) => "E0009",
+            TranslationError::TypeAlreadyDefined(_) => "E0010",
+            TranslationError::FieldAlreadyDefined(_) => "E0011",
+            TranslationError::MismatchingTypes(_, _) => "E0012",
+            TranslationError::UnexpectedType(_, _) => "E0013",
+            TranslationError::NonStructType(_) => "E0014",
+            TranslationError::StructCycle(_) => "E0015",
+            TranslationError::UndefinedVariable(_) => "E0016",
+            TranslationError::UndefinedType(_) => "E0017",
+            TranslationError::NonLValueAssign => "E0018",
+            TranslationError::FieldAreadySet(_) => "E0019",
+            TranslationError::FieldNotSet => "E0020",
+            TranslationError::UndefinedField(_) => "E0021",
+            TranslationError::BinOpUndefined(_, _, _) => "E0022",
+            TranslationError::LazyOpUndefined(_, _, _) => "E0023",
+            TranslationError::UnOpUndefined(_, _) => "E0024",
+            TranslationError::LValueUnOpUndefined(_, _) => "E0025",
+            TranslationError::CastUndefined(_, _) => "E0026",
+            TranslationError::FunctionCallArityMismatch(_, _) => "E0027",
+            TranslationError::NotAFunctionCall => "E0028",
+            TranslationError::LValueUnopNonLValue => "E0029",
+            TranslationError::BreakContinueOutOfLoop => "E0030",
+            TranslationError::MainWrongType => "E0031",
+            TranslationError::NoMain => "E0032",
+            TranslationError::NotAllPathsReturn => "E0033",
+            TranslationError::SubscriptNotArray(_) => "E0034",
+            TranslationError::LengthOnNonArray(_) => "E0035",
+            TranslationError::MemberUndefined => "E0036",
+            TranslationError::UnexpectedVoid => "E0037",
+            TranslationError::TestWrongType => "E0038",
+            TranslationError::AssertOutsideTest => "E0039",
+            TranslationError::GlobalAlreadyDefined(_) => "E0040",
+            TranslationError::NonConstantInitializer => "E0041",
+            TranslationError::DeleteNonPointer(_) => "E0042",
+            TranslationError::SliceUnsized(_) => "E0043",
+            TranslationError::LiteralOutOfRange(_) => "E0044",
+            TranslationError::NonEnumType(_) => "E0046",
+            TranslationError::UndefinedVariant(_, _) => "E0047",
+            TranslationError::VariantAlreadyDefined(_) => "E0048",
+            TranslationError::NonExhaustiveMatch(_) => "E0049",
+            TranslationError::VariantArityMismatch(_, _, _) => "E0050",
+            TranslationError::MatchNonEnum(_) => "E0051",
+            TranslationError::SharedDiscriminant(_) => "E0052",
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            TranslationError::FunctionAlreadyDefined(_) => "translation.function_already_defined",
+            TranslationError::ParameterAlreadyDefined(_) => {
+                "translation.parameter_already_defined"
+            }
+            TranslationError::LocalAlreadyDefined(_) => "translation.local_already_defined",
+            TranslationError::TypeAlreadyDefined(_) => "translation.type_already_defined",
+            TranslationError::FieldAlreadyDefined(_) => "translation.field_already_defined",
+            TranslationError::MismatchingTypes(_, _) => "translation.mismatching_types",
+            TranslationError::UnexpectedType(_, _) => "translation.unexpected_type",
+            TranslationError::NonStructType(_) => "translation.non_struct_type",
+            TranslationError::StructCycle(_) => "translation.struct_cycle",
+            TranslationError::UndefinedVariable(_) => "translation.undefined_variable",
+            TranslationError::UndefinedType(_) => "translation.undefined_type",
+            TranslationError::NonLValueAssign => "translation.non_lvalue_assign",
+            TranslationError::FieldAreadySet(_) => "translation.field_already_set",
+            TranslationError::FieldNotSet => "translation.field_not_set",
+            TranslationError::UndefinedField(_) => "translation.undefined_field",
+            TranslationError::BinOpUndefined(_, _, _) => "translation.binop_undefined",
+            TranslationError::LazyOpUndefined(_, _, _) => "translation.lazyop_undefined",
+            TranslationError::UnOpUndefined(_, _) => "translation.unop_undefined",
+            TranslationError::LValueUnOpUndefined(_, _) => "translation.lvalue_unop_undefined",
+            TranslationError::CastUndefined(_, _) => "translation.cast_undefined",
+            TranslationError::FunctionCallArityMismatch(_, _) => {
+                "translation.function_call_arity_mismatch"
+            }
+            TranslationError::NotAFunctionCall => "translation.not_a_function_call",
+            TranslationError::LValueUnopNonLValue => "translation.lvalue_unop_non_lvalue",
+            TranslationError::BreakContinueOutOfLoop => "translation.break_continue_out_of_loop",
+            TranslationError::MainWrongType => "translation.main_wrong_type",
+            TranslationError::NoMain => "translation.no_main",
+            TranslationError::NotAllPathsReturn => "translation.not_all_paths_return",
+            TranslationError::SubscriptNotArray(_) => "translation.subscript_not_array",
+            TranslationError::LengthOnNonArray(_) => "translation.length_on_non_array",
+            TranslationError::MemberUndefined => "translation.member_undefined",
+            TranslationError::UnexpectedVoid => "translation.unexpected_void",
+            TranslationError::TestWrongType => "translation.test_wrong_type",
+            TranslationError::AssertOutsideTest => "translation.assert_outside_test",
+            TranslationError::GlobalAlreadyDefined(_) => "translation.global_already_defined",
+            TranslationError::NonConstantInitializer => "translation.non_constant_initializer",
+            TranslationError::DeleteNonPointer(_) => "translation.delete_non_pointer",
+            TranslationError::SliceUnsized(_) => "translation.slice_unsized",
+            TranslationError::LiteralOutOfRange(_) => "translation.literal_out_of_range",
+            TranslationError::NonEnumType(_) => "translation.non_enum_type",
+            TranslationError::UndefinedVariant(_, _) => "translation.undefined_variant",
+            TranslationError::VariantAlreadyDefined(_) => {
+                "translation.variant_already_defined"
+            }
+            TranslationError::NonExhaustiveMatch(_) => "translation.non_exhaustive_match",
+            TranslationError::VariantArityMismatch(_, _, _) => {
+                "translation.variant_arity_mismatch"
+            }
+            TranslationError::MatchNonEnum(_) => "translation.match_non_enum",
+            TranslationError::SharedDiscriminant(_) => "translation.shared_discriminant",
+        }
+    }
+}
+
+impl Lint {
+    /// The name of the lint on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedParameters => "unused_parameters",
+            Lint::UnusedFunctions => "unused_functions",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::ShadowedLocals => "shadowed_locals",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        LINTS.iter().cloned().find(|lint| lint.name() == name)
+    }
+}
+
+impl Warning {
+    pub fn lint(&self) -> Lint {
+        match *self {
+            Warning::UnusedVariable(_) => Lint::UnusedVariables,
+            Warning::UnusedParameter(_) => Lint::UnusedParameters,
+            Warning::UnusedFunction(_) => Lint::UnusedFunctions,
+            Warning::UnreachableCode => Lint::UnreachableCode,
+            Warning::ShadowedLocal(_) => Lint::ShadowedLocals,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match *self {
+            Warning::UnusedVariable(_) => "W0001",
+            Warning::UnusedParameter(_) => "W0002",
+            Warning::UnusedFunction(_) => "W0003",
+            Warning::UnreachableCode => "W0004",
+            Warning::ShadowedLocal(_) => "W0005",
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Warning::UnusedVariable(_) => "lint.unused_variables",
+            Warning::UnusedParameter(_) => "lint.unused_parameters",
+            Warning::UnusedFunction(_) => "lint.unused_functions",
+            Warning::UnreachableCode => "lint.unreachable_code",
+            Warning::ShadowedLocal(_) => "lint.shadowed_locals",
+        }
+    }
+}
+
 impl fmt::Display for UserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -113,6 +382,12 @@ impl fmt::Display for LexingError {
                 write!(f, "'{}' is a reserved identifier", id)
             }
             LexingError::UnknownChar(c) => write!(f, "Unknown char  '{}'", c),
+            LexingError::InvalidCharLiteral => {
+                write!(f, "A char literal holds one ASCII character or escape")
+            }
+            LexingError::InvalidStringEscape => {
+                write!(f, "A string literal holds an unknown or non ASCII escape")
+            }
         }
     }
 }
@@ -164,7 +439,7 @@ impl fmt::Display for TranslationError {
             }
             TranslationError::StructCycle(ref name) => write!(
                 f,
-                "The struct '{}' is cyclic (of infinite size), maybe use a pointer",
+                "The type '{}' is cyclic (of infinite size), maybe use a pointer",
                 name
             ),
             TranslationError::UndefinedVariable(ref variable) => {
@@ -235,6 +510,76 @@ impl fmt::Display for TranslationError {
             }
             TranslationError::MemberUndefined => write!(f, "Undefined member"),
             TranslationError::UnexpectedVoid => write!(f, "Void type can't be used here"),
+            TranslationError::TestWrongType => {
+                write!(f, "A test takes no parameters and returns nothing")
+            }
+            TranslationError::AssertOutsideTest => {
+                write!(f, "assert can only be used in a test function")
+            }
+            TranslationError::GlobalAlreadyDefined(ref name) => {
+                write!(f, "The global '{}' is already defined", name)
+            }
+            TranslationError::NonConstantInitializer => {
+                write!(f, "A global must be initialized with a constant value")
+            }
+            TranslationError::DeleteNonPointer(ref ty) => {
+                write!(
+                    f,
+                    "Only a pointer or a dynamic array can be deleted, not a value of type '{}'",
+                    ty
+                )
+            }
+            TranslationError::SliceUnsized(ref ty) => {
+                write!(f, "Type '{}' doesn't have a length and can't be sliced", ty)
+            }
+            TranslationError::LiteralOutOfRange(ref ty) => {
+                write!(f, "The literal is out of the range of type '{}'", ty)
+            }
+            TranslationError::NonEnumType(ref name) => write!(f, "'{}' is not an enum type", name),
+            TranslationError::UndefinedVariant(ref name, ref variant) => write!(
+                f,
+                "The enum '{}' has no variant '{}'",
+                name, variant
+            ),
+            TranslationError::VariantAlreadyDefined(ref variant) => {
+                write!(f, "The variant '{}' is already defined", variant)
+            }
+            TranslationError::NonExhaustiveMatch(ref missing) => write!(
+                f,
+                "The match doesn't cover the variant(s) '{}'",
+                missing.join("', '")
+            ),
+            TranslationError::VariantArityMismatch(ref variant, expected, given) => write!(
+                f,
+                "The variant '{}' has {} field(s) but {} are given",
+                variant, expected, given
+            ),
+            TranslationError::MatchNonEnum(ref ty) => {
+                write!(f, "Type '{}' is not an enum and can't be matched", ty)
+            }
+            TranslationError::SharedDiscriminant(ref variant) => write!(
+                f,
+                "The discriminant of the variant '{}' is already used by a variant with fields",
+                variant
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::UnusedVariable(ref local) => write!(f, "The local '{}' is never used", local),
+            Warning::UnusedParameter(ref param) => {
+                write!(f, "The parameter '{}' is never used", param)
+            }
+            Warning::UnusedFunction(ref func) => {
+                write!(f, "The function '{}' is never called", func)
+            }
+            Warning::UnreachableCode => write!(f, "This statement is unreachable"),
+            Warning::ShadowedLocal(ref local) => {
+                write!(f, "The local '{}' shadows a previous declaration", local)
+            }
         }
     }
 }
@@ -0,0 +1,605 @@
+// the long descriptions printed by `yalc explain`, indexed by the codes of `errors`
+
+static EXPLANATIONS: &'static [(&'static str, &'static str)] = &[
+    (
+        "E0001",
+        r#"A number literal can't be represented.
+
+Integer literals must fit in 64 bits and double literals must be valid decimals.
+
+    let x = 99999999999999999999; // too large
+"#,
+    ),
+    (
+        "E0002",
+        r#"An identifier starts with `___`.
+
+These names are reserved for the functions and values generated by the compiler.
+
+    let ___x = 1; // error
+    let x = 1;    // ok
+"#,
+    ),
+    (
+        "E0003",
+        r#"A character doesn't start any token of the language.
+
+    let x = 3 $ 4; // `$` is not an operator
+"#,
+    ),
+    (
+        "E0004",
+        r#"The parser found a token it didn't expect here.
+
+The message lists the tokens that would have been accepted. A missing `;`, `,`
+or closing bracket is the usual cause.
+
+    fn main() -> int {
+        let x = 1 // missing `;`
+        return x;
+    }
+"#,
+    ),
+    (
+        "E0005",
+        r#"A type is invalid.
+
+This error is not emitted by the current compiler.
+"#,
+    ),
+    (
+        "E0006",
+        r#"`void` is used where a value type is expected.
+
+This error is not emitted by the current compiler, see E0037.
+"#,
+    ),
+    (
+        "E0007",
+        r#"Two functions have the same name.
+
+Functions, including the extern ones and the runtime ones like `printInt`, share
+one namespace.
+
+    fn foo() {}
+    fn foo() {} // error
+"#,
+    ),
+    (
+        "E0008",
+        r#"Two parameters of a function have the same name.
+
+    fn add(x: int, x: int) -> int { // error
+        return x + x;
+    }
+"#,
+    ),
+    (
+        "E0009",
+        r#"A local variable is declared twice in the same scope.
+
+A variable can only be shadowed in a nested block.
+
+    let x = 1;
+    let x = 2; // error
+    {
+        let x = 3; // ok
+    }
+"#,
+    ),
+    (
+        "E0010",
+        r#"Two types have the same name.
+
+    struct Point { x: int, y: int }
+    struct Point { x: double, y: double } // error
+"#,
+    ),
+    (
+        "E0011",
+        r#"A struct declares the same field twice.
+
+    struct Point {
+        x: int,
+        x: int, // error
+    }
+"#,
+    ),
+    (
+        "E0012",
+        r#"Two types must be the same but are not.
+
+There is no implicit conversion between the types of the language, use `as` to
+convert a value.
+
+    let x: int = 2.5;           // error
+    let x: int = 2.5 as int;    // ok
+"#,
+    ),
+    (
+        "E0013",
+        r#"An expression doesn't have the type required by its context.
+
+The conditions of `if`, `while` and `for` must be booleans.
+
+    let n = 3;
+    if (n) {}       // error
+    if (n != 0) {}  // ok
+"#,
+    ),
+    (
+        "E0014",
+        r#"A struct literal names a type that is not a struct.
+
+    let x = int { value: 1 }; // error
+"#,
+    ),
+    (
+        "E0015",
+        r#"A struct or an enum contains itself, so it would be of infinite size.
+
+Use a pointer to refer to a value of the same type.
+
+    struct Node { next: Node }  // error
+    struct Node { next: *Node } // ok
+    enum List { Nil, Cons(int, List) }  // error
+    enum List { Nil, Cons(int, *List) } // ok
+"#,
+    ),
+    (
+        "E0016",
+        r#"A name doesn't refer to any local variable or function in scope.
+
+    fn main() -> int {
+        return y; // error, `y` is not declared
+    }
+"#,
+    ),
+    (
+        "E0017",
+        r#"A type name is not defined.
+
+    let p: Point = nullptr; // error if no `struct Point` is declared
+"#,
+    ),
+    (
+        "E0018",
+        r#"The left side of an assignment is not a location.
+
+Only variables, fields, subscripts and dereferenced pointers can be assigned.
+
+    1 = 2;         // error
+    f() = 2;       // error
+    *ptr = 2;      // ok
+"#,
+    ),
+    (
+        "E0019",
+        r#"A struct literal sets the same field twice.
+
+    let p = Point { x: 1, x: 2, y: 3 }; // error
+"#,
+    ),
+    (
+        "E0020",
+        r#"A struct literal doesn't set all the fields of the struct.
+
+    struct Point { x: int, y: int }
+    let p = Point { x: 1 }; // error, `y` is missing
+"#,
+    ),
+    (
+        "E0021",
+        r#"A field doesn't exist in the accessed type.
+
+Structs have named fields, tuples have numbered fields and arrays only have `len`.
+
+    let t = (1, 2.5);
+    t.0;   // ok
+    t.2;   // error
+"#,
+    ),
+    (
+        "E0022",
+        r#"A binary operator can't be applied to the types of its operands.
+
+Both operands must have the same type, convert one of them with `as`.
+
+    let x = 1 + 2.5;           // error
+    let x = 1 as double + 2.5; // ok
+"#,
+    ),
+    (
+        "E0023",
+        r#"`&&` or `||` is applied to values that are not booleans.
+
+    if (1 && true) {} // error
+"#,
+    ),
+    (
+        "E0024",
+        r#"A unary operator can't be applied to the type of its operand.
+
+`-` applies to numbers, `!` to booleans and `*` to pointers to a value.
+
+    let b = !3;    // error
+    let n = -true; // error
+"#,
+    ),
+    (
+        "E0025",
+        r#"`++` or `--` is applied to a value that is not an int.
+
+    let d = 2.5;
+    d++; // error
+"#,
+    ),
+    (
+        "E0026",
+        r#"A value can't be converted to the type of an `as` cast.
+
+Casts exist between numbers, booleans and ints, pointers and ints, and between
+pointers.
+
+    let s = "hello" as int; // error
+"#,
+    ),
+    (
+        "E0027",
+        r#"A function is called with a wrong number of arguments.
+
+    fn add(a: int, b: int) -> int { return a + b; }
+    add(1);       // error
+    add(1, 2, 3); // error
+"#,
+    ),
+    (
+        "E0028",
+        r#"A value that is not a function is called.
+
+    let x = 1;
+    x(); // error
+"#,
+    ),
+    (
+        "E0029",
+        r#"`++`, `--` or `&` is applied to a value that is not a location.
+
+    let p = &3; // error
+    3++;        // error
+"#,
+    ),
+    (
+        "E0030",
+        r#"`break` or `continue` is used outside of a loop.
+
+    fn main() -> int {
+        break; // error
+        return 0;
+    }
+"#,
+    ),
+    (
+        "E0031",
+        r#"`main` doesn't have the right type.
+
+The main function takes no parameter and returns an int, the exit code of the
+program.
+
+    fn main() {}        // error
+    fn main() -> int {  // ok
+        return 0;
+    }
+"#,
+    ),
+    (
+        "E0032",
+        r#"The program doesn't define a `main` function.
+
+    fn main() -> int {
+        return 0;
+    }
+"#,
+    ),
+    (
+        "E0033",
+        r#"A function returning a value can end without a `return`.
+
+Every path through the function must return, add a final `return` if needed.
+
+    fn sign(x: int) -> int {
+        if (x < 0) {
+            return -1;
+        }
+    } // error, nothing is returned when x >= 0
+"#,
+    ),
+    (
+        "E0034",
+        r#"A value that is neither an array, a slice, a pointer nor a string is subscripted.
+
+    let x = 3;
+    x[0]; // error
+"#,
+    ),
+    (
+        "E0035",
+        r#"`len` is used on a value that is not an array.
+
+This error is not emitted by the current compiler, see E0021.
+"#,
+    ),
+    (
+        "E0036",
+        r#"A member is undefined.
+
+This error is not emitted by the current compiler, see E0021.
+"#,
+    ),
+    (
+        "E0037",
+        r#"`void` is used where a value type is expected.
+
+`void` can only be the return type of a function or the target of a pointer.
+
+    fn f(x: void) {} // error
+    let p: *void = nullptr; // ok
+"#,
+    ),
+    (
+        "E0038",
+        r#"A test function has parameters or a return type.
+
+The tests are run by `yalc --test` without arguments, and only their asserts
+decide whether they pass.
+
+    test fn sum(x: int) -> int {} // error
+    test fn sum() {}              // ok
+"#,
+    ),
+    (
+        "E0039",
+        r#"`assert` is used outside a test function.
+
+The builtin `assert(condition, "message")` reports its failures to the test
+harness of `yalc --test`, so it only exists in the `test fn` declarations.
+Normal builds drop the tests with their asserts.
+
+    fn main() -> int {
+        assert(true, "never"); // error
+        return 0;
+    }
+"#,
+    ),
+    (
+        "E0040",
+        r#"A global variable or constant takes a name already used at the top level.
+
+The globals share their names with the functions and the other globals, a
+local variable can still shadow them.
+
+    fn counter() -> int { return 0; }
+    let counter = 0; // error
+"#,
+    ),
+    (
+        "E0041",
+        r#"A global is initialized with a value that is not a constant.
+
+The globals are built before the program runs, so their value can only be made
+of literals, `nullptr`, tuple, array and struct literals of constants, the
+`const` declared before, and the operators applied to constants. A division by
+zero or a string concatenation has no value before the program runs.
+
+    fn start() -> int { return 1; }
+    let counter = start(); // error
+    let counter = 1;       // ok
+    const LIMIT = 2 * 3 + 1; // ok
+    const BAD = 1 / 0;       // error
+    let table = [LIMIT, 0];  // ok
+    let copy = counter;      // error, `counter` can be assigned
+"#,
+    ),
+    (
+        "E0042",
+        r#"`delete` is applied to a value that is neither a pointer nor a dynamic array.
+
+`delete` frees the memory allocated by `new`, so it takes the pointer or the
+dynamic array that `new` returned.
+
+    let node = new Node { value: 1 };
+    delete *node; // error
+    delete node;  // ok
+
+    let items = new [int; n];
+    delete items; // ok, frees the items
+"#,
+    ),
+    (
+        "E0043",
+        r#"A range `a[i..j]` is taken of a value without a length.
+
+Arrays, dynamic arrays and slices can be sliced. A pointer doesn't know how
+many items follow it, so it can't be.
+
+    let array = [1, 2, 3, 4];
+    let ptr = &array[0];
+    ptr[1..3];   // error
+    array[1..3]; // ok, a `&[int]` of 2 items
+"#,
+    ),
+    (
+        "E0044",
+        r#"An integer literal is too large for its type.
+
+A literal without a suffix is an `int`, which holds 32 bits. The suffixes
+`i8`, `i16`, `i64`, `u8`, `u32` and `u64` give a literal one of the sized
+integer types. A negative literal of an unsigned type is out of range too.
+
+    let big = 3000000000;      // error
+    let big = 3000000000i64;   // ok
+    let byte = 256u8;          // error
+    let min = -2147483648;     // ok, the minus is part of the literal
+"#,
+    ),
+    (
+        "E0045",
+        r#"A char literal is empty, holds several characters or an unknown escape.
+
+A `char` is a byte, its literal is one ASCII character between single quotes,
+or one of the escapes `\n`, `\r`, `\t`, `\b`, `\f`, `\0`, `\\`, `\'`, `\"` and `\xHH`
+with two hexadecimal digits.
+
+    let a = 'ab';   // error
+    let b = '\q';   // error
+    let c = '\x41'; // ok, 'A'
+"#,
+    ),
+    (
+        "E0046",
+        r#"A variant is accessed with `::` on a type that is not an enum.
+
+    struct Point { x: int, y: int }
+    let p = Point::x; // error
+"#,
+    ),
+    (
+        "E0047",
+        r#"An enum doesn't declare the accessed variant.
+
+    enum Color { Red, Green, Blue }
+    let c = Color::Yellow; // error
+"#,
+    ),
+    (
+        "E0048",
+        r#"An enum declares the same variant twice.
+
+Two variants can still share a discriminant.
+
+    enum Color {
+        Red,
+        Red,        // error
+        Crimson = 0 // ok
+    }
+"#,
+    ),
+    (
+        "E0049",
+        r#"A match expression doesn't cover all the variants of the enum.
+
+List the missing variants, or end the match with a `_` arm.
+
+    enum Shape { Circle(double), Rect(double, double) }
+
+    match (s) { Shape::Circle(r) => r }            // error, `Rect` is missing
+    match (s) { Shape::Circle(r) => r, _ => 0.0 } // ok
+"#,
+    ),
+    (
+        "E0050",
+        r#"A variant is built or matched with the wrong number of fields.
+
+    enum Shape { Circle(double), Rect(double, double) }
+
+    Shape::Rect(1.0)      // error
+    Shape::Rect(1.0, 2.0) // ok
+"#,
+    ),
+    (
+        "E0051",
+        r#"Only the values of an enum can be matched.
+
+    match (3) { _ => 0 } // error, `3` is an `int`
+"#,
+    ),
+    (
+        "E0052",
+        r#"A variant with fields shares its discriminant with another variant.
+
+The discriminant is the tag telling which fields a value holds, so it must be unique.
+
+    enum Token {
+        Number(int),
+        Word(string) = 0, // error, `Number` is already 0
+    }
+"#,
+    ),
+    (
+        "E0053",
+        r#"A string literal holds an unknown escape, or an escape of a non ASCII byte.
+
+A string literal has the escapes of the char literals, see E0045. A string is
+UTF-8 text, so a `\xHH` escape is at most `\x7f`, the other characters are
+written as they are. A `\0` ends the string, like in C.
+
+    let a = "\q";      // error
+    let b = "\xff";    // error
+    let c = "\x41\n";  // ok, "A" and a newline
+    let d = "é";       // ok
+"#,
+    ),
+    (
+        "W0001",
+        r#"A local variable is declared but never used (lint `unused_variables`).
+
+Remove it, or start its name with `_` to keep it.
+
+    let count = 0; // warning
+    let _count = 0; // ok
+"#,
+    ),
+    (
+        "W0002",
+        r#"A parameter is never used in the body of its function (lint `unused_parameters`).
+
+Start its name with `_` if the signature must keep it.
+
+    fn first(a: int, b: int) -> int { // warning for `b`
+        return a;
+    }
+"#,
+    ),
+    (
+        "W0003",
+        r#"A function is never called nor referenced (lint `unused_functions`).
+
+`main` and the functions starting with `_` are never reported.
+
+    fn helper() {} // warning
+    fn main() -> int {
+        return 0;
+    }
+"#,
+    ),
+    (
+        "W0004",
+        r#"A statement comes after a `return`, `break` or `continue` and can't run (lint
+`unreachable_code`).
+
+    while (true) {
+        break;
+        printInt(1); // warning
+    }
+"#,
+    ),
+    (
+        "W0005",
+        r#"A local variable hides another one declared in an enclosing scope (lint
+`shadowed_locals`, allowed by default, enable it with `-W shadowed_locals`).
+
+    let x = 1;
+    {
+        let x = 2; // warning
+    }
+"#,
+    ),
+];
+
+/// The long description of an error code, with an example.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|&&(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|&(_, explanation)| explanation)
+}
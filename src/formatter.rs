@@ -0,0 +1,656 @@
+use ast;
+use codemap::{Span, Spanned};
+use common::Field;
+use errors::ParsingError;
+use interner::Interner;
+use lexer::{Lexer, Token};
+use parser;
+
+const INDENT: &str = "    ";
+
+/// Print a program in the canonical style, keeping its comments.
+pub fn format_program(input: &str) -> Result<String, Vec<Spanned<ParsingError>>> {
+    let mut strings = Interner::new();
+    let program = parser::parse_program(Lexer::new(input), &mut strings)?;
+
+    let mut formatter = Formatter {
+        input,
+        comments: collect_comments(input),
+        next_comment: 0,
+        output: String::with_capacity(input.len()),
+        indent: 0,
+        last_end: 0,
+    };
+    formatter.program(&program);
+    Ok(formatter.output)
+}
+
+// the parser never sees the comments, so the input is lexed a second time to find them
+fn collect_comments(input: &str) -> Vec<Span> {
+    let mut lexer = Lexer::new(input);
+    loop {
+        if let Ok(Spanned {
+            inner: Token::EOF, ..
+        }) = lexer.next_token()
+        {
+            break;
+        }
+    }
+    lexer.comments
+}
+
+// true if a line of the gap is empty
+fn has_blank_line(gap: &str) -> bool {
+    let lines: Vec<_> = gap.split('\n').collect();
+    lines.len() > 2 && lines[1..lines.len() - 1]
+        .iter()
+        .any(|line| line.trim().is_empty())
+}
+
+struct Formatter<'input> {
+    input: &'input str,
+    comments: Vec<Span>,
+    next_comment: usize,
+    output: String,
+    indent: usize,
+    last_end: usize, // the end in the input of what was written last
+}
+
+impl<'input> Formatter<'input> {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    fn blank_line(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with("\n\n")
+            && !self.output.ends_with("{\n")
+        {
+            self.output.push('\n');
+        }
+    }
+
+    // write the comments starting before the position, on the line of the previous code
+    // if they were on it in the input
+    fn comments_before(&mut self, pos: usize) {
+        while self.next_comment < self.comments.len() && self.comments[self.next_comment].start < pos
+        {
+            let span = self.comments[self.next_comment];
+            self.next_comment += 1;
+            let text = self.input[span.start..span.end].trim_end();
+
+            let gap = self.input.get(self.last_end..span.start);
+            let trailing = gap.is_some_and(|gap| !gap.contains('\n'));
+            if trailing && self.output.ends_with('\n') {
+                self.output.pop();
+                self.output.push(' ');
+                self.output.push_str(text);
+                self.output.push('\n');
+            } else {
+                if gap.is_some_and(has_blank_line) {
+                    self.blank_line();
+                }
+                self.line(text);
+            }
+            self.last_end = self.last_end.max(span.end);
+        }
+    }
+
+    // write the comments left on the line of the code written last
+    fn trailing_comments(&mut self) {
+        while let Some(&span) = self.comments.get(self.next_comment) {
+            match self.input.get(self.last_end..span.start) {
+                Some(gap) if !gap.contains('\n') => self.comments_before(span.end),
+                _ => break,
+            }
+        }
+    }
+
+    // called before writing a node: its comments first, then the blank line if any
+    fn before(&mut self, span: Span) {
+        self.comments_before(span.start);
+        if self.input
+            .get(self.last_end..span.start)
+            .is_some_and(has_blank_line)
+        {
+            self.blank_line();
+        }
+        // the blank lines before the node are written, they don't count for its children
+        self.last_end = self.last_end.max(span.start);
+    }
+
+    fn after(&mut self, span: Span) {
+        self.last_end = self.last_end.max(span.end);
+    }
+
+    fn program(&mut self, program: &ast::Program) {
+        let mut previous_group = None;
+        for declaration in &program.declarations {
+            // consecutive extern functions or globals can be grouped, the others are separated
+            let group = match *declaration {
+                ast::Declaration::ExternFunction(_) => Some("extern"),
+                ast::Declaration::Global(_) => Some("global"),
+                _ => None,
+            };
+            if let Some(previous_group) = previous_group {
+                if group.is_none() || group != previous_group {
+                    // the comment ending the previous declaration comes before the separation
+                    self.trailing_comments();
+                    self.blank_line();
+                }
+            }
+            previous_group = Some(group);
+
+            match *declaration {
+                ast::Declaration::Struct(ref s) => self.struct_declaration(s),
+                ast::Declaration::Enum(ref e) => self.enum_declaration(e),
+                ast::Declaration::ExternFunction(ref exfunc) => self.extern_function(exfunc),
+                ast::Declaration::Function(ref func) => self.function("fn", func),
+                ast::Declaration::Test(ref func) => self.function("test fn", func),
+                ast::Declaration::Global(ref global) => self.global(global),
+            }
+        }
+        self.comments_before(usize::MAX);
+    }
+
+    fn struct_declaration(&mut self, s: &ast::Struct) {
+        self.before(s.span);
+        if s.fields.is_empty() {
+            self.comments_before(s.span.end);
+            self.line(&format!("struct {} {{}}", s.name));
+            self.after(s.span);
+            return;
+        }
+
+        self.line(&format!("struct {} {{", s.name));
+        self.last_end = s.span.start;
+        self.indent += 1;
+        for (name, ty) in &s.fields {
+            self.before(name.span);
+            self.line(&format!("{}: {},", name.inner, self.ty(ty)));
+            self.after(ty.span);
+        }
+        self.comments_before(s.span.end - 1);
+        self.indent -= 1;
+        self.line("}");
+        self.after(s.span);
+    }
+
+    fn enum_declaration(&mut self, e: &ast::Enum) {
+        self.before(e.span);
+        if e.variants.is_empty() {
+            self.comments_before(e.span.end);
+            self.line(&format!("enum {} {{}}", e.name));
+            self.after(e.span);
+            return;
+        }
+
+        self.line(&format!("enum {} {{", e.name));
+        self.last_end = e.span.start;
+        self.indent += 1;
+        for variant in &e.variants {
+            self.before(variant.name.span);
+            let mut text = variant.name.inner.clone();
+            let mut end_span = variant.name.span;
+            if !variant.payload.is_empty() {
+                let payload: Vec<_> = variant.payload.iter().map(|ty| self.ty(ty)).collect();
+                text.push_str(&format!("({})", payload.join(", ")));
+                end_span = variant.payload[variant.payload.len() - 1].span;
+            }
+            if let Some(ref value) = variant.discriminant {
+                text.push_str(&format!(" = {}", value.inner));
+                end_span = value.span;
+            }
+            self.line(&format!("{},", text));
+            self.after(end_span);
+        }
+        self.comments_before(e.span.end - 1);
+        self.indent -= 1;
+        self.line("}");
+        self.after(e.span);
+    }
+
+    fn extern_function(&mut self, exfunc: &ast::ExternFunction) {
+        self.before(exfunc.span);
+        let mut parameters: Vec<_> = exfunc.parameters.iter().map(|ty| self.ty(ty)).collect();
+        if exfunc.is_vararg {
+            parameters.push("...".to_string());
+        }
+        let line = format!(
+            "extern fn {}({}){};",
+            exfunc.name,
+            parameters.join(", "),
+            self.return_ty(&exfunc.return_ty)
+        );
+        self.line(&line);
+        self.after(exfunc.span);
+    }
+
+    fn global(&mut self, global: &ast::Global) {
+        self.before(global.span);
+        let ty = global
+            .ty
+            .as_ref()
+            .map_or(String::new(), |ty| format!(": {}", self.ty(ty)));
+        let line = format!(
+            "{} {}{} = {};",
+            if global.is_const { "const" } else { "let" },
+            global.name,
+            ty,
+            self.expression(&global.value)
+        );
+        self.line(&line);
+        self.after(global.span);
+    }
+
+    fn function(&mut self, keyword: &str, func: &ast::Function) {
+        self.before(func.span);
+        let parameters: Vec<_> = func.parameters
+            .iter()
+            .map(|(name, ty)| format!("{}: {}", name, self.ty(ty)))
+            .collect();
+        let line = format!(
+            "{} {}({}){} {{",
+            keyword,
+            func.name,
+            parameters.join(", "),
+            self.return_ty(&func.return_ty)
+        );
+        self.line(&line);
+        self.block_body(&func.body.inner, func.body.span);
+        self.line("}");
+    }
+
+    // the opening `{` is already written, the closing one is left to the caller
+    fn block_body(&mut self, block: &ast::BlockStatement, span: Span) {
+        self.last_end = self.last_end.max(span.start + 1);
+        self.indent += 1;
+        for statement in &block.statements {
+            if let ast::Statement::Empty = statement.inner {
+                continue;
+            }
+            self.statement(statement);
+        }
+        self.comments_before(span.end - 1);
+        self.indent -= 1;
+        self.after(span);
+    }
+
+    // a statement body, return true if it was a block waiting for its closing `}`
+    fn body(&mut self, header: &str, body: &Spanned<ast::Statement>) -> bool {
+        if let ast::Statement::Block(ref block) = body.inner {
+            self.line(&format!("{} {{", header));
+            self.block_body(block, body.span);
+            true
+        } else {
+            self.line(header);
+            self.indent += 1;
+            self.statement(body);
+            self.indent -= 1;
+            false
+        }
+    }
+
+    fn statement(&mut self, statement: &Spanned<ast::Statement>) {
+        self.before(statement.span);
+        match statement.inner {
+            ast::Statement::Block(ref block) => {
+                self.line("{");
+                self.block_body(block, statement.span);
+                self.line("}");
+            }
+            ast::Statement::If(ref if_stmt) => self.if_statement(if_stmt),
+            ast::Statement::While(ref while_stmt) => {
+                let header = format!("while ({})", self.expression(&while_stmt.condition));
+                if self.body(&header, &while_stmt.body) {
+                    self.line("}");
+                }
+            }
+            ast::Statement::For(ref for_stmt) => {
+                let step = for_stmt
+                    .step
+                    .as_ref()
+                    .map_or(String::new(), |step| format!(" {}", self.expression(step)));
+                let header = format!(
+                    "for ({} {};{})",
+                    self.simple_statement(&for_stmt.init),
+                    self.expression(&for_stmt.condition),
+                    step
+                );
+                if self.body(&header, &for_stmt.body) {
+                    self.line("}");
+                }
+            }
+            ast::Statement::ForEach(ref for_each) => {
+                let header = format!(
+                    "for ({} {} : {})",
+                    self.ty(&for_each.ty),
+                    for_each.name.inner,
+                    self.expression(&for_each.array)
+                );
+                if self.body(&header, &for_each.body) {
+                    self.line("}");
+                }
+            }
+            _ => {
+                let line = self.simple_statement(statement);
+                self.line(&line);
+            }
+        }
+        self.after(statement.span);
+    }
+
+    fn if_statement(&mut self, if_stmt: &ast::IfStatement) {
+        let mut if_stmt = if_stmt;
+        let mut prefix = "";
+        loop {
+            let header = format!("{}if ({})", prefix, self.expression(&if_stmt.condition));
+            let braced = self.body(&header, &if_stmt.body);
+
+            let else_stmt = if let Some(ref else_stmt) = if_stmt.else_clause {
+                else_stmt
+            } else {
+                if braced {
+                    self.line("}");
+                }
+                return;
+            };
+
+            let else_prefix = if braced { "} else " } else { "else " };
+            if let ast::Statement::If(ref next) = else_stmt.inner {
+                // an else if chain stays flat
+                prefix = else_prefix;
+                if_stmt = next;
+            } else {
+                if self.body(else_prefix.trim_end(), else_stmt) {
+                    self.line("}");
+                }
+                return;
+            }
+        }
+    }
+
+    // the statements written on one line, with their `;`
+    fn simple_statement(&self, statement: &Spanned<ast::Statement>) -> String {
+        match statement.inner {
+            ast::Statement::Empty => ";".to_string(),
+            ast::Statement::Let(ref let_stmt) => {
+                let ty = let_stmt
+                    .ty
+                    .as_ref()
+                    .map_or(String::new(), |ty| format!(": {}", self.ty(ty)));
+                format!(
+                    "let {}{} = {};",
+                    let_stmt.name,
+                    ty,
+                    self.expression(&let_stmt.value)
+                )
+            }
+            ast::Statement::Return(None) => "return;".to_string(),
+            ast::Statement::Return(Some(ref expr)) => format!("return {};", self.expression(expr)),
+            ast::Statement::Expression(ref expr) => format!("{};", self.expression(expr)),
+            ast::Statement::Break => "break;".to_string(),
+            ast::Statement::Continue => "continue;".to_string(),
+            _ => panic!("Not a simple statement"),
+        }
+    }
+
+    fn expression(&self, expr: &Spanned<ast::Expression>) -> String {
+        match expr.inner {
+            // the literals are kept as written
+            ast::Expression::Literal(_) => self.input[expr.span.start..expr.span.end].to_string(),
+            ast::Expression::Identifier(ref id) => id.clone(),
+            ast::Expression::Nullptr => "nullptr".to_string(),
+            ast::Expression::EnumVariant {
+                ref enum_name,
+                ref variant,
+                ref payload,
+            } => {
+                if payload.is_empty() {
+                    format!("{}::{}", enum_name, variant.inner)
+                } else {
+                    let payload: Vec<_> = payload.iter().map(|e| self.expression(e)).collect();
+                    format!("{}::{}({})", enum_name, variant.inner, payload.join(", "))
+                }
+            }
+            ast::Expression::Match {
+                ref scrutinee,
+                ref arms,
+            } => {
+                // one arm per line, the nested lines are indented once more
+                let mut text = format!("match ({}) {{\n", self.expression(scrutinee));
+                for arm in arms {
+                    let pattern = match arm.pattern.inner {
+                        ast::Pattern::Wildcard => "_".to_string(),
+                        ast::Pattern::Variant {
+                            ref enum_name,
+                            ref variant,
+                            ref bindings,
+                        } if bindings.is_empty() => format!("{}::{}", enum_name, variant.inner),
+                        ast::Pattern::Variant {
+                            ref enum_name,
+                            ref variant,
+                            ref bindings,
+                        } => {
+                            let bindings: Vec<_> = bindings.iter().map(|b| &*b.inner).collect();
+                            format!("{}::{}({})", enum_name, variant.inner, bindings.join(", "))
+                        }
+                    };
+                    let value = self.expression(&arm.value)
+                        .replace('\n', &format!("\n{}", INDENT));
+                    for _ in 0..self.indent + 1 {
+                        text.push_str(INDENT);
+                    }
+                    text.push_str(&format!("{} => {},\n", pattern, value));
+                }
+                for _ in 0..self.indent {
+                    text.push_str(INDENT);
+                }
+                text.push('}');
+                text
+            }
+            ast::Expression::Parenthesis(ref sub) => format!("({})", self.expression(sub)),
+            ast::Expression::Assign { ref lhs, ref rhs } => {
+                format!("{} = {}", self.expression(lhs), self.expression(rhs))
+            }
+            ast::Expression::CompoundAssign {
+                binop,
+                ref lhs,
+                ref rhs,
+            } => format!(
+                "{} {}= {}",
+                self.expression(lhs),
+                binop_str(binop),
+                self.expression(rhs)
+            ),
+            ast::Expression::BinaryOperator {
+                binop,
+                ref lhs,
+                ref rhs,
+            } => format!(
+                "{} {} {}",
+                self.expression(lhs),
+                binop_str(binop),
+                self.expression(rhs)
+            ),
+            ast::Expression::LazyOperator {
+                lazyop,
+                ref lhs,
+                ref rhs,
+            } => {
+                let op = match lazyop {
+                    ast::LazyOperatorKind::LogicalAnd => "&&",
+                    ast::LazyOperatorKind::LogicalOr => "||",
+                };
+                format!("{} {} {}", self.expression(lhs), op, self.expression(rhs))
+            }
+            ast::Expression::UnaryOperator { unop, ref sub } => {
+                let op = match unop {
+                    ast::UnaryOperatorKind::Minus => "-",
+                    ast::UnaryOperatorKind::LogicalNot => "!",
+                    ast::UnaryOperatorKind::BitNot => "~",
+                    ast::UnaryOperatorKind::PtrDeref => "*",
+                };
+                prefix_operator(op, self.expression(sub))
+            }
+            ast::Expression::LValueUnaryOperator {
+                lvalue_unop,
+                ref sub,
+            } => match lvalue_unop {
+                ast::LValueUnaryOperatorKind::Increment => format!("{}++", self.expression(sub)),
+                ast::LValueUnaryOperatorKind::Decrement => format!("{}--", self.expression(sub)),
+                ast::LValueUnaryOperatorKind::AddressOf => {
+                    prefix_operator("&", self.expression(sub))
+                }
+            },
+            ast::Expression::Cast { ref as_ty, ref sub } => {
+                format!("{} as {}", self.expression(sub), self.ty(as_ty))
+            }
+            ast::Expression::Subscript {
+                ref array,
+                ref index,
+            } => format!("{}[{}]", self.expression(array), self.expression(index)),
+            ast::Expression::Slice {
+                ref array,
+                ref begin,
+                ref end,
+            } => format!(
+                "{}[{}..{}]",
+                self.expression(array),
+                self.expression(begin),
+                self.expression(end)
+            ),
+            ast::Expression::FunctionCall {
+                ref function,
+                ref args,
+            } => format!("{}({})", self.expression(function), self.expressions(args)),
+            ast::Expression::TupleLiteral { ref values } if values.len() == 1 => {
+                format!("({},)", self.expression(&values[0]))
+            }
+            ast::Expression::TupleLiteral { ref values } => {
+                format!("({})", self.expressions(values))
+            }
+            ast::Expression::ArrayLiteral { ref values } => {
+                format!("[{}]", self.expressions(values))
+            }
+            ast::Expression::ArrayFillLiteral { ref value, size } => {
+                format!("[{}; {}]", self.expression(value), size)
+            }
+            ast::Expression::StructLiteral {
+                ref struct_name,
+                ref fields,
+            } => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("{}: {}", name.inner, self.expression(value))
+                    })
+                    .collect();
+                format!("{} {{ {} }}", struct_name, fields.join(", "))
+            }
+            ast::Expression::FieldAccess {
+                ref expr,
+                ref field,
+            } => match *field {
+                Field::Named(ref name) => format!("{}.{}", self.expression(expr), name),
+                Field::Index(index) => format!("{}.{}", self.expression(expr), index),
+            },
+            ast::Expression::New { ref ty, ref value } => match *value {
+                Some(ref value) => format!("new {}", self.expression(value)),
+                None => format!("new {}", self.ty(ty)),
+            },
+            ast::Expression::NewArray {
+                ref ty,
+                ref size,
+                ref rows,
+            } => {
+                if rows.is_empty() {
+                    format!("new [{}; {}]", self.ty(ty), self.expression(size))
+                } else {
+                    let sizes: String = Some(&**size)
+                        .into_iter()
+                        .chain(rows)
+                        .map(|size| format!("[{}]", self.expression(size)))
+                        .collect();
+                    format!("new {}{}", self.ty(ty), sizes)
+                }
+            }
+            ast::Expression::Delete(ref sub) => format!("delete {}", self.expression(sub)),
+            ast::Expression::SizeOf(ref ty) => format!("sizeof({})", self.ty(ty)),
+            ast::Expression::AlignOf(ref ty) => format!("alignof({})", self.ty(ty)),
+        }
+    }
+
+    fn expressions(&self, exprs: &[Spanned<ast::Expression>]) -> String {
+        let exprs: Vec<_> = exprs.iter().map(|expr| self.expression(expr)).collect();
+        exprs.join(", ")
+    }
+
+    fn return_ty(&self, ty: &Spanned<ast::Type>) -> String {
+        if let ast::Type::Void = ty.inner {
+            String::new()
+        } else {
+            format!(" -> {}", self.ty(ty))
+        }
+    }
+
+    fn ty(&self, ty: &Spanned<ast::Type>) -> String {
+        match ty.inner {
+            ast::Type::Void => "()".to_string(),
+            ast::Type::Identifier(ref id) => id.clone(),
+            ast::Type::Pointer(ref sub) => format!("*{}", self.ty(sub)),
+            ast::Type::Array(ref sub, size) => format!("[{}; {}]", self.ty(sub), size),
+            ast::Type::DynArray(ref sub) => format!("[]{}", self.ty(sub)),
+            ast::Type::Slice(ref sub) => format!("&[{}]", self.ty(sub)),
+            ast::Type::Function(ref func_ty) => {
+                let mut parameters: Vec<_> =
+                    func_ty.parameters_ty.iter().map(|ty| self.ty(ty)).collect();
+                if func_ty.is_vararg {
+                    parameters.push("...".to_string());
+                }
+                format!(
+                    "fn({}) -> {}",
+                    parameters.join(", "),
+                    self.ty(&func_ty.return_ty)
+                )
+            }
+            ast::Type::Tuple(ref types) => {
+                let types: Vec<_> = types.iter().map(|ty| self.ty(ty)).collect();
+                format!("({})", types.join(", "))
+            }
+        }
+    }
+}
+
+// `- -x` and `& &x` must not be glued into `--x` and `&&x`
+fn prefix_operator(op: &str, sub: String) -> String {
+    if sub.starts_with(op) {
+        format!("{} {}", op, sub)
+    } else {
+        format!("{}{}", op, sub)
+    }
+}
+
+fn binop_str(binop: ast::BinaryOperatorKind) -> &'static str {
+    match binop {
+        ast::BinaryOperatorKind::Plus => "+",
+        ast::BinaryOperatorKind::Minus => "-",
+        ast::BinaryOperatorKind::Multiply => "*",
+        ast::BinaryOperatorKind::Divide => "/",
+        ast::BinaryOperatorKind::Modulo => "%",
+        ast::BinaryOperatorKind::Equal => "==",
+        ast::BinaryOperatorKind::NotEqual => "!=",
+        ast::BinaryOperatorKind::Less => "<",
+        ast::BinaryOperatorKind::LessEqual => "<=",
+        ast::BinaryOperatorKind::Greater => ">",
+        ast::BinaryOperatorKind::GreaterEqual => ">=",
+        ast::BinaryOperatorKind::BitAnd => "&",
+        ast::BinaryOperatorKind::BitOr => "|",
+        ast::BinaryOperatorKind::BitXor => "^",
+        ast::BinaryOperatorKind::ShiftLeft => "<<",
+        ast::BinaryOperatorKind::ShiftRight => ">>",
+    }
+}
@@ -0,0 +1,224 @@
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use libc;
+
+use interpreter::{RuntimeError, RuntimeResult, Value};
+
+// printf and scanf are variadic: the format is split into chunks holding at most one
+// conversion, so each chunk can be forwarded to the libc with a single typed argument
+
+struct Chunk {
+    format: CString,
+    conversion: Option<Conversion>,
+}
+
+struct Conversion {
+    kind: u8,
+    long: bool,
+    assigned: bool,
+}
+
+fn split_format(format: &[u8]) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    let mut index = 0;
+
+    while index < format.len() {
+        let c = format[index];
+        current.push(c);
+        index += 1;
+
+        if c != b'%' {
+            continue;
+        }
+
+        if index < format.len() && format[index] == b'%' {
+            current.push(b'%');
+            index += 1;
+            continue;
+        }
+
+        let mut long = false;
+        let mut assigned = true;
+        while index < format.len() {
+            let c = format[index];
+            current.push(c);
+            index += 1;
+
+            match c {
+                b'*' => assigned = false,
+                b'l' | b'L' | b'q' | b'j' | b'z' | b't' => long = true,
+                b'd' | b'i' | b'o' | b'u' | b'x' | b'X' | b'e' | b'E' | b'f' | b'F' | b'g'
+                | b'G' | b'a' | b'A' | b'c' | b's' | b'p' | b'n' => {
+                    chunks.push(Chunk {
+                        format: CString::new(current.split_off(0)).unwrap(),
+                        conversion: Some(Conversion {
+                            kind: c,
+                            long,
+                            assigned,
+                        }),
+                    });
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(Chunk {
+            format: CString::new(current).unwrap(),
+            conversion: None,
+        });
+    }
+    chunks
+}
+
+fn format_of(value: &Value) -> RuntimeResult<Vec<u8>> {
+    if let Value::String(s) = *value {
+        Ok(unsafe { CStr::from_ptr(s) }.to_bytes().to_vec())
+    } else {
+        Err(RuntimeError::InvalidExternArgument("string".to_string()))
+    }
+}
+
+fn pointer_of(value: &Value) -> RuntimeResult<*mut libc::c_void> {
+    match *value {
+        Value::String(s) => Ok(s as *mut _),
+        Value::Pointer(p, _) => Ok(p as *mut _),
+        _ => Err(RuntimeError::InvalidExternArgument("pointer".to_string())),
+    }
+}
+
+// the argument of a chunk, with the C type the conversion expects
+enum FormatArg {
+    Nothing,
+    Double(f64),
+    Long(libc::c_long),
+    Int(libc::c_int),
+    Pointer(*mut libc::c_void),
+}
+
+fn format_arg<'a, I>(chunk: &Chunk, args: &mut I, function: &str) -> RuntimeResult<FormatArg>
+where
+    I: Iterator<Item = &'a Value>,
+{
+    let conv = match chunk.conversion {
+        Some(ref conv) => conv,
+        None => return Ok(FormatArg::Nothing),
+    };
+    let arg = args.next()
+        .ok_or_else(|| RuntimeError::MissingExternArgument(function.to_string()))?;
+    let arg = match (conv.kind, arg) {
+        (b'e', &Value::Double(d))
+        | (b'E', &Value::Double(d))
+        | (b'f', &Value::Double(d))
+        | (b'F', &Value::Double(d))
+        | (b'g', &Value::Double(d))
+        | (b'G', &Value::Double(d))
+        | (b'a', &Value::Double(d))
+        | (b'A', &Value::Double(d)) => FormatArg::Double(d),
+        (_, &Value::Int(i)) if conv.long => FormatArg::Long(i as libc::c_long),
+        (_, &Value::Int(i)) => FormatArg::Int(i as libc::c_int),
+        (_, &Value::SizedInt(i, _)) if conv.long => FormatArg::Long(i as libc::c_long),
+        (_, &Value::SizedInt(i, _)) => FormatArg::Int(i as libc::c_int),
+        (_, &Value::Boolean(b)) => FormatArg::Int(b as libc::c_int),
+        (_, &Value::Char(c)) => FormatArg::Int(libc::c_int::from(c)),
+        (_, arg) => FormatArg::Pointer(pointer_of(arg)?),
+    };
+    Ok(arg)
+}
+
+pub fn printf(args: &[Value]) -> RuntimeResult<Value> {
+    let format = format_of(&args[0])?;
+    let mut args = args[1..].iter();
+    let mut written = 0;
+
+    for chunk in split_format(&format) {
+        let format = chunk.format.as_ptr();
+        let res = unsafe {
+            match format_arg(&chunk, &mut args, "printf")? {
+                FormatArg::Nothing => libc::printf(format),
+                FormatArg::Double(d) => libc::printf(format, d),
+                FormatArg::Long(l) => libc::printf(format, l),
+                FormatArg::Int(i) => libc::printf(format, i),
+                FormatArg::Pointer(p) => libc::printf(format, p),
+            }
+        };
+
+        if res < 0 {
+            return Ok(Value::Int(res));
+        }
+        written += res;
+    }
+
+    Ok(Value::Int(written))
+}
+
+pub fn sprintf(args: &[Value]) -> RuntimeResult<Value> {
+    let dest = pointer_of(&args[0])? as *mut libc::c_char;
+    let format = format_of(&args[1])?;
+    let mut args = args[2..].iter();
+    let mut written = 0;
+
+    for chunk in split_format(&format) {
+        let format = chunk.format.as_ptr();
+        let res = unsafe {
+            // each chunk is written after the previous ones
+            let dest = dest.offset(written as isize);
+            match format_arg(&chunk, &mut args, "sprintf")? {
+                FormatArg::Nothing => libc::sprintf(dest, format),
+                FormatArg::Double(d) => libc::sprintf(dest, format, d),
+                FormatArg::Long(l) => libc::sprintf(dest, format, l),
+                FormatArg::Int(i) => libc::sprintf(dest, format, i),
+                FormatArg::Pointer(p) => libc::sprintf(dest, format, p),
+            }
+        };
+
+        if res < 0 {
+            return Ok(Value::Int(res));
+        }
+        written += res;
+    }
+
+    Ok(Value::Int(written))
+}
+
+pub fn malloc(args: &[Value]) -> *mut u8 {
+    let size = match args[0] {
+        Value::SizedInt(size, _) => size as usize,
+        Value::Int(size) => size as usize,
+        _ => 0,
+    };
+    unsafe { libc::malloc(size) as *mut u8 }
+}
+
+pub fn scanf(args: &[Value]) -> RuntimeResult<Value> {
+    let format = format_of(&args[0])?;
+    let mut args = args[1..].iter();
+    let mut assigned = 0;
+
+    for chunk in split_format(&format) {
+        let format = chunk.format.as_ptr();
+        let (res, expected) = unsafe {
+            match chunk.conversion {
+                Some(ref conv) if conv.assigned => {
+                    let arg = args.next()
+                        .ok_or_else(|| RuntimeError::MissingExternArgument("scanf".to_string()))?;
+                    (libc::scanf(format, pointer_of(arg)?), 1)
+                }
+                _ => (libc::scanf(format, ptr::null_mut::<libc::c_void>()), 0),
+            }
+        };
+
+        if res == libc::EOF && assigned == 0 {
+            return Ok(Value::Int(libc::EOF));
+        } else if res < expected {
+            break;
+        }
+        assigned += res.max(0);
+    }
+
+    Ok(Value::Int(assigned))
+}
@@ -0,0 +1,203 @@
+use std::mem;
+use std::ptr;
+
+use ty;
+use interpreter::Value;
+use trans::tables::TypeTable;
+
+// mirrors the layout LLVM chooses for the types built in `backend::codegen_type`
+pub fn size_of(ty: ty::Type) -> usize {
+    size_align_of(ty).0
+}
+
+pub fn align_of(ty: ty::Type) -> usize {
+    size_align_of(ty).1
+}
+
+fn size_align_of(ty: ty::Type) -> (usize, usize) {
+    let ptr_size = mem::size_of::<usize>();
+    match *ty {
+        ty::TypeValue::Incomplete => panic!("Incomplete type in interpreter"),
+        ty::TypeValue::Error => panic!("Error type in interpreter"),
+        ty::TypeValue::Void => (1, 1),
+        // the int tag then a buffer fitting the payload of any variant
+        ty::TypeValue::Enum(ref e) if e.has_payload() => {
+            let (payload_size, payload_align) = payload_layout(e);
+            let align = payload_align.max(4);
+            (round_up(round_up(4, payload_align) + payload_size, align), align)
+        }
+        ty::TypeValue::Int | ty::TypeValue::Enum(_) => (4, 4),
+        ty::TypeValue::SizedInt(kind) => {
+            let size = kind.bits() as usize / 8;
+            (size, size)
+        }
+        ty::TypeValue::Double => (8, 8),
+        ty::TypeValue::Boolean | ty::TypeValue::Char => (1, 1),
+        ty::TypeValue::String
+        | ty::TypeValue::LValue(_, _)
+        | ty::TypeValue::Pointer(_)
+        | ty::TypeValue::FunctionPtr(_) => (ptr_size, ptr_size),
+        ty::TypeValue::Struct(ref s) => {
+            let fields: Vec<_> = s.fields.iter().map(|&(_, ty)| ty).collect();
+            aggregate_layout(&fields)
+        }
+        ty::TypeValue::Tuple(ref types) => aggregate_layout(types),
+        ty::TypeValue::Array(sub, size) => {
+            let (sub_size, sub_align) = size_align_of(sub);
+            (sub_size * size, sub_align)
+        }
+        // the int length then the pointer to the items
+        ty::TypeValue::DynArray(_) | ty::TypeValue::Slice(_) => {
+            (round_up(4, ptr_size) + ptr_size, ptr_size)
+        }
+    }
+}
+
+fn aggregate_layout(fields: &[ty::Type]) -> (usize, usize) {
+    let mut size = 0;
+    let mut align = 1;
+    for &field in fields {
+        let (field_size, field_align) = size_align_of(field);
+        size = round_up(size, field_align) + field_size;
+        align = align.max(field_align);
+    }
+    (round_up(size, align), align)
+}
+
+fn round_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+fn payload_layout(e: &ty::EnumTypeValue) -> (usize, usize) {
+    let (mut size, mut align) = (0, 1);
+    for variant in &e.variants {
+        let (variant_size, variant_align) = aggregate_layout(&variant.payload);
+        size = size.max(variant_size);
+        align = align.max(variant_align);
+    }
+    (round_up(size, align), align)
+}
+
+/// The offset of the payload in a value of a tagged union.
+pub fn payload_offset(e: &ty::EnumTypeValue) -> usize {
+    round_up(4, payload_layout(e).1)
+}
+
+// return the offset and the type of the field `index` of a struct, a tuple, a dynamic array,
+// a slice or the tag of a tagged union
+pub fn field_of(types: &TypeTable, ty: ty::Type, index: usize) -> (usize, ty::Type) {
+    let fields: Vec<_> = match *ty {
+        ty::TypeValue::Enum(_) => return (0, types.get_int_ty()),
+        ty::TypeValue::Struct(ref s) => s.fields.iter().map(|&(_, ty)| ty).collect(),
+        ty::TypeValue::Tuple(ref types) => types.clone(),
+        ty::TypeValue::DynArray(sub) | ty::TypeValue::Slice(sub) => {
+            vec![types.get_int_ty(), types.pointer_of(sub)]
+        }
+        _ => panic!("Field access on a non aggregate type"),
+    };
+
+    let mut offset = 0;
+    for (field_index, &field) in fields.iter().enumerate() {
+        offset = round_up(offset, align_of(field));
+        if field_index == index {
+            return (offset, field);
+        }
+        offset += size_of(field);
+    }
+    panic!("Field index out of range")
+}
+
+/// Owns the memory of the locals and temporaries of a function call.
+#[derive(Debug, Default)]
+pub struct Stack {
+    slots: Vec<Vec<u64>>,
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Stack { slots: Vec::new() }
+    }
+
+    pub fn alloc(&mut self, ty: ty::Type) -> *mut u8 {
+        let words = size_of(ty).div_ceil(8);
+        let mut slot = vec![0u64; words.max(1)];
+        let ptr = slot.as_mut_ptr() as *mut u8;
+        self.slots.push(slot);
+        ptr
+    }
+}
+
+pub unsafe fn load(ptr: *const u8, ty: ty::Type, functions: &[String]) -> Value {
+    match *ty {
+        ty::TypeValue::Enum(ref e) if e.has_payload() => {
+            let size = size_of(ty);
+            let mut bytes = vec![0; size];
+            ptr::copy_nonoverlapping(ptr, bytes.as_mut_ptr(), size);
+            Value::Aggregate(bytes, ty)
+        }
+        ty::TypeValue::Int | ty::TypeValue::Enum(_) => {
+            Value::Int(ptr::read_unaligned(ptr as *const i32))
+        }
+        ty::TypeValue::SizedInt(kind) => {
+            let value = match kind.bits() {
+                8 => i64::from(*ptr),
+                16 => i64::from(ptr::read_unaligned(ptr as *const u16)),
+                32 => i64::from(ptr::read_unaligned(ptr as *const u32)),
+                _ => ptr::read_unaligned(ptr as *const i64),
+            };
+            Value::SizedInt(kind.wrap(value), kind)
+        }
+        ty::TypeValue::Double => Value::Double(ptr::read_unaligned(ptr as *const f64)),
+        ty::TypeValue::Boolean => Value::Boolean(*ptr != 0),
+        ty::TypeValue::Char => Value::Char(*ptr),
+        ty::TypeValue::String => Value::String(ptr::read_unaligned(ptr as *const *const _)),
+        ty::TypeValue::LValue(sub, _) | ty::TypeValue::Pointer(sub) => {
+            Value::Pointer(ptr::read_unaligned(ptr as *const *mut u8), sub)
+        }
+        ty::TypeValue::FunctionPtr(_) => {
+            let index = ptr::read_unaligned(ptr as *const usize);
+            Value::Function(functions[index].clone(), ty)
+        }
+        ty::TypeValue::Struct(_)
+        | ty::TypeValue::Tuple(_)
+        | ty::TypeValue::Array(_, _)
+        | ty::TypeValue::DynArray(_)
+        | ty::TypeValue::Slice(_) => {
+            let size = size_of(ty);
+            let mut bytes = vec![0; size];
+            ptr::copy_nonoverlapping(ptr, bytes.as_mut_ptr(), size);
+            Value::Aggregate(bytes, ty)
+        }
+        ty::TypeValue::Void | ty::TypeValue::Incomplete | ty::TypeValue::Error => {
+            panic!("Load of a sizeless value")
+        }
+    }
+}
+
+pub unsafe fn store(ptr: *mut u8, value: &Value, functions: &[String]) {
+    match *value {
+        Value::Int(i) => ptr::write_unaligned(ptr as *mut i32, i),
+        Value::SizedInt(i, kind) => match kind.bits() {
+            8 => *ptr = i as u8,
+            16 => ptr::write_unaligned(ptr as *mut u16, i as u16),
+            32 => ptr::write_unaligned(ptr as *mut u32, i as u32),
+            _ => ptr::write_unaligned(ptr as *mut i64, i),
+        },
+        Value::Double(d) => ptr::write_unaligned(ptr as *mut f64, d),
+        Value::Boolean(b) => *ptr = b as u8,
+        Value::Char(c) => *ptr = c,
+        Value::String(s) => ptr::write_unaligned(ptr as *mut *const _, s),
+        Value::Pointer(p, _) => ptr::write_unaligned(ptr as *mut *mut u8, p),
+        Value::Function(ref name, _) => {
+            let index = functions
+                .iter()
+                .position(|f| f == name)
+                .expect("Unknown function");
+            ptr::write_unaligned(ptr as *mut usize, index)
+        }
+        Value::Aggregate(ref bytes, _) => {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len())
+        }
+        Value::Void => panic!("Store of a void value"),
+    }
+}
@@ -0,0 +1,696 @@
+use std::collections::hash_map::{Entry, HashMap};
+use std::ffi::CString;
+use std::fmt;
+
+use libc;
+
+use ir;
+use ty;
+use common;
+use interner::{Interner, InternerId};
+use trans::tables::TypeTable;
+
+mod builtins;
+mod memory;
+
+use self::memory::Stack;
+
+#[derive(Debug, Clone)]
+pub enum RuntimeError {
+    NoMain,
+    UnsupportedExtern(String),
+    MissingExternArgument(String),
+    InvalidExternArgument(String),
+    DivisionByZero,
+    IndexOutOfBounds(i32, usize), // the index and the line
+    NegativeLength(i32, usize),   // the length and the line
+}
+
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RuntimeError::NoMain => write!(f, "A main function must be defined"),
+            RuntimeError::UnsupportedExtern(ref name) => write!(
+                f,
+                "The extern function '{}' is not supported by the interpreter",
+                name
+            ),
+            RuntimeError::MissingExternArgument(ref name) => {
+                write!(f, "Missing argument in call to '{}'", name)
+            }
+            RuntimeError::InvalidExternArgument(ref expected) => {
+                write!(f, "Invalid argument in extern call ('{}' expected)", expected)
+            }
+            RuntimeError::DivisionByZero => write!(f, "Division by zero"),
+            // the same messages as the checks of the compiled programs
+            RuntimeError::IndexOutOfBounds(index, line) => {
+                write!(f, "index {} is out of bounds at line {}", index, line)
+            }
+            RuntimeError::NegativeLength(len, line) => {
+                write!(f, "negative array length {} at line {}", len, line)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Int(i32),
+    SizedInt(i64, ty::IntKind), // wrapped to the kind
+    Char(u8),
+    Double(f64),
+    Boolean(bool),
+    String(*const libc::c_char),
+    Pointer(*mut u8, ty::Type), // pointed type
+    Function(String, ty::Type),
+    Aggregate(Vec<u8>, ty::Type),
+    Void,
+}
+
+impl Value {
+    fn as_int(&self) -> i32 {
+        if let Value::Int(i) = *self {
+            i
+        } else {
+            panic!("Int value expected")
+        }
+    }
+
+    // `int` is the 32 bits signed kind, the chars are compared like `u8`
+    fn as_integer(&self) -> (i64, ty::IntKind) {
+        match *self {
+            Value::Int(i) => (i64::from(i), ty::IntKind::I32),
+            Value::SizedInt(i, kind) => (i, kind),
+            Value::Char(c) => (i64::from(c), ty::IntKind::U8),
+            _ => panic!("Integer value expected"),
+        }
+    }
+
+    fn integer(value: i64, kind: ty::IntKind) -> Value {
+        if kind == ty::IntKind::I32 {
+            Value::Int(value as i32)
+        } else {
+            Value::SizedInt(kind.wrap(value), kind)
+        }
+    }
+
+    fn as_double(&self) -> f64 {
+        if let Value::Double(d) = *self {
+            d
+        } else {
+            panic!("Double value expected")
+        }
+    }
+
+    fn as_boolean(&self) -> bool {
+        if let Value::Boolean(b) = *self {
+            b
+        } else {
+            panic!("Boolean value expected")
+        }
+    }
+
+    fn as_pointer(&self) -> (*mut u8, ty::Type) {
+        if let Value::Pointer(p, ty) = *self {
+            (p, ty)
+        } else {
+            panic!("Pointer value expected")
+        }
+    }
+
+    fn address(&self) -> *mut u8 {
+        match *self {
+            Value::Pointer(p, _) => p,
+            Value::String(s) => s as *mut _,
+            Value::Int(i) => i as usize as *mut _,
+            Value::SizedInt(i, _) => i as usize as *mut _,
+            _ => panic!("Value without an address"),
+        }
+    }
+}
+
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+pub fn interpret_program(
+    program: &ir::Program,
+    strings: &Interner<String>,
+    types: &TypeTable,
+) -> RuntimeResult<i32> {
+    let mut interpreter = Interpreter::new(program, strings, types);
+    if !interpreter.functions.contains_key("main") {
+        return Err(RuntimeError::NoMain);
+    }
+    interpreter.call_function("main", Vec::new()).map(|v| v.as_int())
+}
+
+struct Interpreter<'p, 's, 't> {
+    functions: HashMap<&'p str, &'p ir::Function>,
+    externs: HashMap<&'p str, &'p ir::ExternFunction>,
+    function_names: Vec<String>,
+    strings: &'s Interner<String>,
+    string_literals: HashMap<InternerId, CString>,
+    types: &'t TypeTable,
+    frames: Vec<Frame>,
+    globals: HashMap<&'p str, (*mut u8, ty::Type)>,
+    global_memory: Stack,
+}
+
+struct Frame {
+    locals: HashMap<ir::IdentifierId, (*mut u8, ty::Type)>,
+    // the slot of a temporary is reused each time its expression is evaluated, so a loop
+    // doesn't grow the frame
+    temporaries: HashMap<*const ir::Expression, *mut u8>,
+    stack: Stack,
+}
+
+impl<'p, 's, 't> Interpreter<'p, 's, 't> {
+    fn new(program: &'p ir::Program, strings: &'s Interner<String>, types: &'t TypeTable) -> Self {
+        let mut functions = HashMap::new();
+        let mut externs = HashMap::new();
+        let mut function_names = Vec::new();
+        let mut global_variables = Vec::new();
+
+        for decl in &program.declarations {
+            match *decl {
+                ir::Declaration::ExternFunction(ref exfunc) => {
+                    externs.insert(exfunc.name.as_str(), exfunc);
+                    function_names.push(exfunc.name.clone());
+                }
+                ir::Declaration::Function(ref func) => {
+                    functions.insert(func.name.as_str(), func);
+                    function_names.push(func.name.clone());
+                }
+                ir::Declaration::GlobalVariable(ref global) => global_variables.push(global),
+            }
+        }
+
+        let mut interpreter = Interpreter {
+            functions,
+            externs,
+            function_names,
+            strings,
+            string_literals: HashMap::new(),
+            types,
+            frames: Vec::new(),
+            globals: HashMap::new(),
+            global_memory: Stack::new(),
+        };
+
+        for global in global_variables {
+            let ptr = interpreter.global_memory.alloc(global.ty);
+            interpreter.init_constant(ptr, global.ty, &global.value);
+            interpreter
+                .globals
+                .insert(global.name.as_str(), (ptr, global.ty));
+        }
+        interpreter
+    }
+
+    fn init_constant(&mut self, ptr: *mut u8, ty: ty::Type, constant: &ir::Constant) {
+        match *constant {
+            ir::Constant::Literal(lit) => {
+                let value = self.eval_value(&ir::Value::Literal(lit));
+                unsafe { memory::store(ptr, &value, &self.function_names) };
+            }
+            ir::Constant::Null => {} // the memory of a global starts zeroed
+            ir::Constant::Aggregate(ref constants) => {
+                for (index, constant) in constants.iter().enumerate() {
+                    let (offset, sub_ty) = match *ty {
+                        ty::TypeValue::Array(sub, _) => (index * memory::size_of(sub), sub),
+                        _ => memory::field_of(self.types, ty, index),
+                    };
+                    self.init_constant(unsafe { ptr.add(offset) }, sub_ty, constant);
+                }
+            }
+        }
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn call_function(&mut self, name: &str, args: Vec<Value>) -> RuntimeResult<Value> {
+        if self.externs.contains_key(name) {
+            return self.call_extern_function(name, &args);
+        }
+
+        let function = self.functions[name];
+
+        let mut frame = Frame {
+            locals: HashMap::new(),
+            temporaries: HashMap::new(),
+            stack: Stack::new(),
+        };
+
+        for (&(ty, id), arg) in function.parameters.iter().zip(args) {
+            let ptr = frame.stack.alloc(ty);
+            unsafe { memory::store(ptr, &arg, &self.function_names) };
+            frame.locals.insert(id, (ptr, ty));
+        }
+
+        for decl in &function.var_declarations {
+            let ptr = frame.stack.alloc(decl.ty);
+            frame.locals.insert(decl.id, (ptr, decl.ty));
+        }
+
+        self.frames.push(frame);
+        let flow = self.exec_block(&function.body);
+        self.frames.pop();
+
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(Value::Void),
+        }
+    }
+
+    fn call_extern_function(&mut self, name: &str, args: &[Value]) -> RuntimeResult<Value> {
+        match name {
+            "printf" => builtins::printf(args),
+            "scanf" => builtins::scanf(args),
+            "sprintf" => builtins::sprintf(args),
+            "malloc" => Ok(Value::Pointer(
+                builtins::malloc(args),
+                self.types.get_void_ty(),
+            )),
+            _ => Err(RuntimeError::UnsupportedExtern(name.to_string())),
+        }
+    }
+
+    fn exec_block(&mut self, block: &[ir::Statement]) -> RuntimeResult<Flow> {
+        for stmt in block {
+            match self.exec_statement(stmt)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn exec_statement(&mut self, stmt: &ir::Statement) -> RuntimeResult<Flow> {
+        match *stmt {
+            ir::Statement::Block(ref block) => self.exec_block(block),
+            ir::Statement::If {
+                ref condition,
+                ref body,
+                ref else_clause,
+            } => {
+                if self.eval_expression(condition)?.as_boolean() {
+                    self.exec_block(body)
+                } else {
+                    self.exec_block(else_clause)
+                }
+            }
+            ir::Statement::For {
+                ref init,
+                ref condition,
+                ref step,
+                ref body,
+            } => {
+                if let Flow::Return(value) = self.exec_statement(init)? {
+                    return Ok(Flow::Return(value));
+                }
+
+                while self.eval_expression(condition)?.as_boolean() {
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+
+                    if let Some(ref step) = *step {
+                        self.eval_expression(step)?;
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+            ir::Statement::Return(ref expr) => {
+                let value = if let Some(ref expr) = *expr {
+                    self.eval_expression(expr)?
+                } else {
+                    Value::Void
+                };
+                Ok(Flow::Return(value))
+            }
+            ir::Statement::Expression(ref expr) => {
+                self.eval_expression(expr)?;
+                Ok(Flow::Normal)
+            }
+            ir::Statement::Break => Ok(Flow::Break),
+            ir::Statement::Continue => Ok(Flow::Continue),
+        }
+    }
+
+    fn eval_expression(&mut self, expr: &ir::Expression) -> RuntimeResult<Value> {
+        match *expr {
+            ir::Expression::Block(ref block) => {
+                self.exec_block(&block.stmts)?;
+                self.eval_expression(&block.final_expr)
+            }
+            ir::Expression::LValueToRValue(ref sub) => {
+                let (ptr, ty) = self.eval_expression(sub)?.as_pointer();
+                Ok(unsafe { memory::load(ptr, ty, &self.function_names) })
+            }
+            ir::Expression::RValueToLValue(ref sub) => {
+                let value = self.eval_expression(sub)?;
+                let ty = self.type_of(&value);
+                let frame = self.frame();
+                let ptr = match frame.temporaries.entry(&**sub) {
+                    Entry::Occupied(entry) => *entry.get(),
+                    Entry::Vacant(entry) => *entry.insert(frame.stack.alloc(ty)),
+                };
+                unsafe { memory::store(ptr, &value, &self.function_names) };
+                Ok(Value::Pointer(ptr, ty))
+            }
+            ir::Expression::Value(ref value) => Ok(self.eval_value(value)),
+            ir::Expression::Assign { ref lhs, ref rhs } => {
+                let (ptr, _) = self.eval_expression(lhs)?.as_pointer();
+                let value = self.eval_expression(rhs)?;
+                unsafe { memory::store(ptr, &value, &self.function_names) };
+                Ok(value)
+            }
+            ir::Expression::BinaryOperator {
+                binop,
+                ref lhs,
+                ref rhs,
+            } => {
+                let lhs = self.eval_expression(lhs)?;
+                let rhs = self.eval_expression(rhs)?;
+                eval_binop(binop, lhs, rhs)
+            }
+            ir::Expression::UnaryOperator { unop, ref sub } => {
+                let sub = self.eval_expression(sub)?;
+                Ok(match unop {
+                    ir::UnaryOperatorKind::IntMinus => {
+                        let (i, kind) = sub.as_integer();
+                        Value::integer(i.wrapping_neg(), kind)
+                    }
+                    ir::UnaryOperatorKind::DoubleMinus => Value::Double(-sub.as_double()),
+                    ir::UnaryOperatorKind::BooleanNot => Value::Boolean(!sub.as_boolean()),
+                    ir::UnaryOperatorKind::IntNot => {
+                        let (i, kind) = sub.as_integer();
+                        Value::integer(!i, kind)
+                    }
+                    ir::UnaryOperatorKind::PointerDeref => sub,
+                })
+            }
+            ir::Expression::LValueUnaryOperator {
+                lvalue_unop,
+                ref sub,
+            } => {
+                let sub = self.eval_expression(sub)?;
+                let (ptr, ty) = sub.as_pointer();
+                let delta = match lvalue_unop {
+                    ir::LValueUnaryOperatorKind::IntIncrement => 1,
+                    ir::LValueUnaryOperatorKind::IntDecrement => -1,
+                    ir::LValueUnaryOperatorKind::LValueToPtr => return Ok(sub),
+                };
+                unsafe {
+                    let (value, kind) = memory::load(ptr, ty, &self.function_names).as_integer();
+                    let value = Value::integer(value.wrapping_add(delta), kind);
+                    memory::store(ptr, &value, &self.function_names);
+                }
+                Ok(sub)
+            }
+            ir::Expression::Cast { kind, ref sub } => {
+                let sub = self.eval_expression(sub)?;
+                Ok(match kind {
+                    ir::CastKind::IntToDouble(kind) => {
+                        let (i, _) = sub.as_integer();
+                        if kind.is_signed() {
+                            Value::Double(i as f64)
+                        } else {
+                            Value::Double(i as u64 as f64)
+                        }
+                    }
+                    ir::CastKind::DoubleToInt(kind) => {
+                        let d = sub.as_double();
+                        if kind.is_signed() {
+                            Value::integer(d as i64, kind)
+                        } else {
+                            Value::integer(d as u64 as i64, kind)
+                        }
+                    }
+                    // the value is already sign or zero extended like its source type
+                    ir::CastKind::IntToInt(_, to) | ir::CastKind::CharToInt(to) => {
+                        Value::integer(sub.as_integer().0, to)
+                    }
+                    ir::CastKind::IntToChar(_) => Value::Char(sub.as_integer().0 as u8),
+                    ir::CastKind::BooleanToInt => Value::Int(sub.as_boolean() as i32),
+                    ir::CastKind::IntToBoolean => Value::Boolean(sub.as_int() & 1 != 0),
+                    ir::CastKind::PtrToInt(kind) => {
+                        Value::integer(sub.address() as usize as i64, kind)
+                    }
+                    ir::CastKind::IntToPtr(ptr_ty) => self.bitcast(sub, ptr_ty),
+                })
+            }
+            ir::Expression::BitCast { dest_ty, ref sub } => {
+                let sub = self.eval_expression(sub)?;
+                Ok(self.bitcast(sub, dest_ty))
+            }
+            ir::Expression::FunctionCall {
+                ref function,
+                ref args,
+            } => {
+                let function = self.eval_expression(function)?;
+                let mut arg_values = Vec::with_capacity(args.len());
+                for arg in args {
+                    arg_values.push(self.eval_expression(arg)?);
+                }
+
+                if let Value::Function(name, _) = function {
+                    self.call_function(&name, arg_values)
+                } else {
+                    panic!("Call of a non function value")
+                }
+            }
+            ir::Expression::FieldAccess { ref sub, index } => {
+                let (ptr, ty) = self.eval_expression(sub)?.as_pointer();
+                let (offset, field_ty) = memory::field_of(self.types, ty, index);
+                Ok(Value::Pointer(unsafe { ptr.add(offset) }, field_ty))
+            }
+            ir::Expression::VariantPayload {
+                ref sub,
+                payload_ty,
+            } => {
+                let (ptr, ty) = self.eval_expression(sub)?.as_pointer();
+                let offset = match *ty {
+                    ty::TypeValue::Enum(ref e) => memory::payload_offset(e),
+                    _ => panic!("Payload of a non enum value"),
+                };
+                Ok(Value::Pointer(unsafe { ptr.add(offset) }, payload_ty))
+            }
+            ir::Expression::Ternary {
+                ref condition,
+                ref true_expr,
+                ref false_expr,
+            } => {
+                if self.eval_expression(condition)?.as_boolean() {
+                    self.eval_expression(true_expr)
+                } else {
+                    self.eval_expression(false_expr)
+                }
+            }
+            ir::Expression::New(ty) => {
+                // like the backend, the memory of the value is zeroed
+                let ptr = unsafe { libc::calloc(1, memory::size_of(ty)) };
+                Ok(Value::Pointer(ptr as *mut u8, ty))
+            }
+            ir::Expression::NewArray { ty, ref size } => {
+                let size = self.eval_expression(size)?.as_int();
+                let ptr = unsafe { libc::calloc(size as usize, memory::size_of(ty)) };
+                Ok(Value::Pointer(ptr as *mut u8, ty))
+            }
+            ir::Expression::SizeOf(ty) => Ok(Value::SizedInt(
+                memory::size_of(ty) as i64,
+                ty::IntKind::U64,
+            )),
+            ir::Expression::AlignOf(ty) => Ok(Value::SizedInt(
+                memory::align_of(ty) as i64,
+                ty::IntKind::U64,
+            )),
+            ir::Expression::Delete(ref sub) => {
+                let ptr = self.eval_expression(sub)?.address();
+                unsafe { libc::free(ptr as *mut _) };
+                Ok(Value::Void)
+            }
+            ir::Expression::BoundsCheck {
+                ref index,
+                ref len,
+                line,
+            } => {
+                let index = self.eval_expression(index)?.as_int();
+                let len = self.eval_expression(len)?.as_int();
+                if 0 <= index && index < len {
+                    Ok(Value::Int(index))
+                } else {
+                    Err(RuntimeError::IndexOutOfBounds(index, line))
+                }
+            }
+            ir::Expression::LengthCheck { ref len, line } => {
+                let len = self.eval_expression(len)?.as_int();
+                if len >= 0 {
+                    Ok(Value::Int(len))
+                } else {
+                    Err(RuntimeError::NegativeLength(len, line))
+                }
+            }
+        }
+    }
+
+    fn eval_value(&mut self, value: &ir::Value) -> Value {
+        match *value {
+            ir::Value::Literal(common::Literal::IntLiteral(i)) => Value::Int(i as i32),
+            ir::Value::Literal(common::Literal::SizedIntLiteral(i, kind)) => {
+                Value::integer(i, kind)
+            }
+            ir::Value::Literal(common::Literal::DoubleLiteral(d)) => Value::Double(d),
+            ir::Value::Literal(common::Literal::BooleanLiteral(b)) => Value::Boolean(b),
+            ir::Value::Literal(common::Literal::CharLiteral(c)) => Value::Char(c),
+            ir::Value::Literal(common::Literal::StringLiteral(id)) => {
+                let strings = self.strings;
+                let s = self.string_literals
+                    .entry(id)
+                    .or_insert_with(|| CString::new(strings.get_ref(id).clone()).unwrap());
+                Value::String(s.as_ptr())
+            }
+            ir::Value::Local(id) => {
+                let (ptr, ty) = self.frame().locals[&id];
+                Value::Pointer(ptr, ty)
+            }
+            ir::Value::Global(ref name) => {
+                if let Some(&(ptr, ty)) = self.globals.get(name.as_str()) {
+                    return Value::Pointer(ptr, ty);
+                }
+                let func_ty = if let Some(exfunc) = self.externs.get(name.as_str()) {
+                    exfunc.ty.clone()
+                } else {
+                    self.functions[name.as_str()].get_type()
+                };
+                Value::Function(name.clone(), self.types.function_of(func_ty))
+            }
+        }
+    }
+
+    fn bitcast(&self, value: Value, dest_ty: ty::Type) -> Value {
+        match *dest_ty {
+            ty::TypeValue::Pointer(sub) | ty::TypeValue::LValue(sub, _) => {
+                Value::Pointer(value.address(), sub)
+            }
+            ty::TypeValue::String => Value::String(value.address() as *const _),
+            _ => value,
+        }
+    }
+
+    fn type_of(&self, value: &Value) -> ty::Type {
+        match *value {
+            Value::Int(_) => self.types.get_int_ty(),
+            Value::SizedInt(_, kind) => self.types.get_integer_ty(kind),
+            Value::Char(_) => self.types.get_char_ty(),
+            Value::Double(_) => self.types.get_double_ty(),
+            Value::Boolean(_) => self.types.get_boolean_ty(),
+            Value::String(_) => self.types.get_string_ty(),
+            Value::Pointer(_, sub) => self.types.pointer_of(sub),
+            Value::Function(_, ty) | Value::Aggregate(_, ty) => ty,
+            Value::Void => self.types.get_void_ty(),
+        }
+    }
+}
+
+fn eval_binop(binop: ir::BinaryOperatorKind, lhs: Value, rhs: Value) -> RuntimeResult<Value> {
+    use ir::BinaryOperatorKind as bok;
+
+    // LLVM float comparisons used by the backend are unordered ones
+    fn unordered(a: f64, b: f64) -> bool {
+        a.is_nan() || b.is_nan()
+    }
+
+    let value = match binop {
+        bok::IntPlus
+        | bok::IntMinus
+        | bok::IntMultiply
+        | bok::IntDivide
+        | bok::UIntDivide
+        | bok::IntModulo
+        | bok::UIntModulo
+        | bok::IntAnd
+        | bok::IntOr
+        | bok::IntXor
+        | bok::IntShiftLeft
+        | bok::IntShiftRight
+        | bok::UIntShiftRight
+        | bok::IntEqual
+        | bok::IntNotEqual
+        | bok::IntLess
+        | bok::UIntLess
+        | bok::IntLessEqual
+        | bok::UIntLessEqual
+        | bok::IntGreater
+        | bok::UIntGreater
+        | bok::IntGreaterEqual
+        | bok::UIntGreaterEqual => return eval_int_binop(binop, &lhs, &rhs),
+        bok::DoublePlus => Value::Double(lhs.as_double() + rhs.as_double()),
+        bok::DoubleMinus => Value::Double(lhs.as_double() - rhs.as_double()),
+        bok::DoubleMultiply => Value::Double(lhs.as_double() * rhs.as_double()),
+        bok::DoubleDivide => Value::Double(lhs.as_double() / rhs.as_double()),
+        bok::BooleanEqual => Value::Boolean(lhs.as_boolean() == rhs.as_boolean()),
+        bok::BooleanNotEqual => Value::Boolean(lhs.as_boolean() != rhs.as_boolean()),
+        bok::DoubleEqual
+        | bok::DoubleNotEqual
+        | bok::DoubleLess
+        | bok::DoubleLessEqual
+        | bok::DoubleGreater
+        | bok::DoubleGreaterEqual => {
+            let (a, b) = (lhs.as_double(), rhs.as_double());
+            let res = match binop {
+                bok::DoubleEqual => a == b,
+                bok::DoubleNotEqual => a != b,
+                bok::DoubleLess => a < b,
+                bok::DoubleLessEqual => a <= b,
+                bok::DoubleGreater => a > b,
+                _ => a >= b,
+            };
+            Value::Boolean(res || unordered(a, b))
+        }
+        bok::PtrPlusOffset | bok::PtrMinusOffset => {
+            let (ptr, ty) = lhs.as_pointer();
+            let mut offset = rhs.as_int() as isize * memory::size_of(ty) as isize;
+            if let bok::PtrMinusOffset = binop {
+                offset = -offset;
+            }
+            Value::Pointer(ptr.wrapping_offset(offset), ty)
+        }
+        bok::PtrEqual => Value::Boolean(lhs.address() == rhs.address()),
+        bok::PtrNotEqual => Value::Boolean(lhs.address() != rhs.address()),
+        bok::PtrDiff => {
+            let (a, ty) = lhs.as_pointer();
+            let (b, _) = rhs.as_pointer();
+            let diff = (a as isize - b as isize) / memory::size_of(ty) as isize;
+            Value::Int(diff as i32)
+        }
+        // the string operators are calls to the runtime after the translation
+        bok::StringConcat
+        | bok::StringEqual
+        | bok::StringNotEqual
+        | bok::StringLess
+        | bok::StringLessEqual
+        | bok::StringGreater
+        | bok::StringGreaterEqual => unreachable!(),
+    };
+    Ok(value)
+}
+
+fn eval_int_binop(binop: ir::BinaryOperatorKind, lhs: &Value, rhs: &Value) -> RuntimeResult<Value> {
+    let ((a, kind), (b, _)) = (lhs.as_integer(), rhs.as_integer());
+    match binop.eval_int(a, b, kind) {
+        Some(ir::IntResult::Integer(value)) => Ok(Value::integer(value, kind)),
+        Some(ir::IntResult::Boolean(b)) => Ok(Value::Boolean(b)),
+        None => Err(RuntimeError::DivisionByZero),
+    }
+}
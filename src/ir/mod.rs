@@ -16,6 +16,7 @@ pub struct Program {
 pub enum Declaration {
     ExternFunction(ExternFunction),
     Function(Function),
+    GlobalVariable(GlobalVariable),
 }
 
 #[derive(Debug, Clone)]
@@ -25,6 +26,23 @@ pub struct ExternFunction {
     pub span: Span,
 }
 
+#[derive(Debug, Clone)]
+pub struct GlobalVariable {
+    pub ty: Type,
+    pub name: String,
+    pub value: Constant,
+    pub is_const: bool,
+    pub span: Span,
+}
+
+/// The value of a global, computed before the program runs.
+#[derive(Debug, Clone)]
+pub enum Constant {
+    Literal(Literal),
+    Null,
+    Aggregate(Vec<Constant>), // the fields of a struct or a tuple, or the items of an array
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
     pub return_ty: Type,
@@ -114,11 +132,32 @@ pub enum Expression {
         sub: Box<Expression>,
         index: usize,
     },
+    VariantPayload {
+        sub: Box<Expression>, // a pointer to a tagged union, the result points to its payload
+        payload_ty: Type,     // the tuple of the fields of the variant
+    },
     Ternary {
         condition: Box<Expression>,
         true_expr: Box<Expression>,
         false_expr: Box<Expression>,
     },
+    New(Type),            // a zeroed value on the heap
+    NewArray {
+        ty: Type, // the type of the items, the expression is a pointer to the first one
+        size: Box<Expression>,
+    },
+    Delete(Box<Expression>),
+    SizeOf(Type), // a `u64`, from the layout of the target
+    AlignOf(Type),
+    BoundsCheck {
+        index: Box<Expression>, // the value of the expression, if it is in `0..len`
+        len: Box<Expression>,
+        line: usize, // named by the runtime error
+    },
+    LengthCheck {
+        len: Box<Expression>, // the value of the expression, if it isn't negative
+        line: usize,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -143,8 +182,16 @@ pub enum BinaryOperatorKind {
     IntMultiply,
     DoubleMultiply,
     IntDivide,
+    UIntDivide,
     DoubleDivide,
     IntModulo,
+    UIntModulo,
+    IntAnd,
+    IntOr,
+    IntXor,
+    IntShiftLeft,
+    IntShiftRight,  // arithmetic, the sign is kept
+    UIntShiftRight, // logical, zeros are shifted in
     IntEqual,
     DoubleEqual,
     BooleanEqual,
@@ -152,17 +199,95 @@ pub enum BinaryOperatorKind {
     DoubleNotEqual,
     BooleanNotEqual,
     IntLess,
+    UIntLess,
     DoubleLess,
     IntLessEqual,
+    UIntLessEqual,
     DoubleLessEqual,
     IntGreater,
+    UIntGreater,
     DoubleGreater,
     IntGreaterEqual,
+    UIntGreaterEqual,
     DoubleGreaterEqual,
 
+    StringConcat,
+    StringEqual,
+    StringNotEqual,
+    StringLess,
+    StringLessEqual,
+    StringGreater,
+    StringGreaterEqual,
+
     PtrPlusOffset,
     PtrMinusOffset,
     PtrDiff,
+    PtrEqual,
+    PtrNotEqual,
+}
+
+impl BinaryOperatorKind {
+    /// The integer comparison of the `strcmp` like result of the runtime with 0, for the
+    /// string comparisons.
+    pub fn string_comparison(self) -> Option<BinaryOperatorKind> {
+        match self {
+            BinaryOperatorKind::StringEqual => Some(BinaryOperatorKind::IntEqual),
+            BinaryOperatorKind::StringNotEqual => Some(BinaryOperatorKind::IntNotEqual),
+            BinaryOperatorKind::StringLess => Some(BinaryOperatorKind::IntLess),
+            BinaryOperatorKind::StringLessEqual => Some(BinaryOperatorKind::IntLessEqual),
+            BinaryOperatorKind::StringGreater => Some(BinaryOperatorKind::IntGreater),
+            BinaryOperatorKind::StringGreaterEqual => Some(BinaryOperatorKind::IntGreaterEqual),
+            _ => None,
+        }
+    }
+
+    /// Compute an integer operator, shared by the constant folding and the interpreter so
+    /// that they agree. The integers of every width are computed on 64 bits, the caller wraps
+    /// them to their kind: the unsigned ones are zero extended so only the 64 bits ones need
+    /// unsigned operations. None for a division by zero.
+    pub fn eval_int(self, a: i64, b: i64, kind: IntKind) -> Option<IntResult> {
+        use self::BinaryOperatorKind as bok;
+
+        // like x86, the shift amount is masked
+        let shift = b as u32 & (kind.bits() - 1);
+        let value = match self {
+            bok::IntPlus => a.wrapping_add(b),
+            bok::IntMinus => a.wrapping_sub(b),
+            bok::IntMultiply => a.wrapping_mul(b),
+            bok::IntDivide | bok::UIntDivide | bok::IntModulo | bok::UIntModulo if b == 0 => {
+                return None
+            }
+            bok::IntDivide => a.wrapping_div(b),
+            bok::UIntDivide => (a as u64 / b as u64) as i64,
+            bok::IntModulo => a.wrapping_rem(b),
+            bok::UIntModulo => (a as u64 % b as u64) as i64,
+            bok::IntAnd => a & b,
+            bok::IntOr => a | b,
+            bok::IntXor => a ^ b,
+            bok::IntShiftLeft => a << shift,
+            bok::IntShiftRight => a >> shift,
+            bok::UIntShiftRight => (a as u64 >> shift) as i64,
+            bok::IntEqual => return Some(IntResult::Boolean(a == b)),
+            bok::IntNotEqual => return Some(IntResult::Boolean(a != b)),
+            bok::IntLess => return Some(IntResult::Boolean(a < b)),
+            bok::UIntLess => return Some(IntResult::Boolean((a as u64) < b as u64)),
+            bok::IntLessEqual => return Some(IntResult::Boolean(a <= b)),
+            bok::UIntLessEqual => return Some(IntResult::Boolean(a as u64 <= b as u64)),
+            bok::IntGreater => return Some(IntResult::Boolean(a > b)),
+            bok::UIntGreater => return Some(IntResult::Boolean(a as u64 > b as u64)),
+            bok::IntGreaterEqual => return Some(IntResult::Boolean(a >= b)),
+            bok::UIntGreaterEqual => return Some(IntResult::Boolean(a as u64 >= b as u64)),
+            _ => unreachable!("{:?} is not an integer operator", self),
+        };
+        Some(IntResult::Integer(value))
+    }
+}
+
+/// The value of an integer operator, a comparison gives a boolean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntResult {
+    Integer(i64),
+    Boolean(bool),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -170,6 +295,7 @@ pub enum UnaryOperatorKind {
     IntMinus,
     DoubleMinus,
     BooleanNot,
+    IntNot,
     PointerDeref,
 }
 
@@ -182,10 +308,13 @@ pub enum LValueUnaryOperatorKind {
 
 #[derive(Debug, Clone, Copy)]
 pub enum CastKind {
-    IntToDouble,
-    DoubleToInt,
+    IntToDouble(IntKind),
+    DoubleToInt(IntKind),
+    IntToInt(IntKind, IntKind), // from, to
+    CharToInt(IntKind),
+    IntToChar(IntKind),
     BooleanToInt,
     IntToBoolean,
-    PtrToInt,
+    PtrToInt(IntKind),
     IntToPtr(Type),
 }
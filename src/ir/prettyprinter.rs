@@ -51,9 +51,24 @@ impl<'w, W: Write + 'w> PrettyPrinter<'w, W> {
         match *decl {
             ir::Declaration::ExternFunction(ref exfunc) => self.pp_ex_function(exfunc),
             ir::Declaration::Function(ref func) => self.pp_function(func),
+            ir::Declaration::GlobalVariable(ref global) => self.pp_global(global),
         }
     }
 
+    pub fn pp_global(&mut self, global: &ir::GlobalVariable) -> io::Result<()> {
+        let ty_str = self.ty_to_string(global.ty);
+        writeln_pp!(
+            self,
+            "{} {}: {} = {}; // {:?}",
+            if global.is_const { "const" } else { "let" },
+            global.name,
+            ty_str,
+            constant_to_string(&global.value),
+            global.span
+        )?;
+        writeln_pp!(self)
+    }
+
     fn pp_func_header(
         &mut self,
         ret_ty: ty::Type,
@@ -233,7 +248,38 @@ impl<'w, W: Write + 'w> PrettyPrinter<'w, W> {
                 let sub = self.pp_expression_percent(sub)?;
                 format!("get_field #{} of {}", index, sub)
             }
+            ir::Expression::VariantPayload {
+                ref sub,
+                payload_ty,
+            } => {
+                let sub = self.pp_expression_percent(sub)?;
+                format!("payload {} of {}", self.ty_to_string(payload_ty), sub)
+            }
             ir::Expression::Ternary { .. } => unimplemented!(),
+            ir::Expression::New(ty) => format!("new {}", self.ty_to_string(ty)),
+            ir::Expression::NewArray { ty, ref size } => {
+                let size = self.pp_expression_percent(size)?;
+                format!("new [{}; {}]", self.ty_to_string(ty), size)
+            }
+            ir::Expression::Delete(ref sub) => {
+                let sub = self.pp_expression_percent(sub)?;
+                format!("delete {}", sub)
+            }
+            ir::Expression::SizeOf(ty) => format!("sizeof {}", self.ty_to_string(ty)),
+            ir::Expression::AlignOf(ty) => format!("alignof {}", self.ty_to_string(ty)),
+            ir::Expression::BoundsCheck {
+                ref index,
+                ref len,
+                line,
+            } => {
+                let index = self.pp_expression_percent(index)?;
+                let len = self.pp_expression_percent(len)?;
+                format!("bounds_check {} < {} at line {}", index, len, line)
+            }
+            ir::Expression::LengthCheck { ref len, line } => {
+                let len = self.pp_expression_percent(len)?;
+                format!("length_check {} at line {}", len, line)
+            }
         };
 
         let id = self.new_expr();
@@ -264,16 +310,20 @@ impl<'w, W: Write + 'w> PrettyPrinter<'w, W> {
     fn ty_to_string(&self, ty: ty::Type) -> String {
         match *ty {
             ty::TypeValue::Incomplete => "incomplete".to_string(),
+            ty::TypeValue::Error => "{error}".to_string(),
             ty::TypeValue::Int => "int".to_string(),
+            ty::TypeValue::SizedInt(kind) => kind.name().to_string(),
             ty::TypeValue::Double => "double".to_string(),
             ty::TypeValue::Boolean => "boolean".to_string(),
             ty::TypeValue::String => "string".to_string(),
+            ty::TypeValue::Char => "char".to_string(),
             ty::TypeValue::Void => "void".to_string(),
             ty::TypeValue::LValue(sub, ass) => {
                 format!("&{} assignable: {}", self.ty_to_string(sub), ass)
             }
             ty::TypeValue::Pointer(sub) => format!("*{}", self.ty_to_string(sub)),
             ty::TypeValue::Struct(ref s) => format!("struct {}", s.name),
+            ty::TypeValue::Enum(ref e) => format!("enum {}", e.name),
             ty::TypeValue::Tuple(ref types) => format!(
                 "({})",
                 types
@@ -283,6 +333,8 @@ impl<'w, W: Write + 'w> PrettyPrinter<'w, W> {
                     .join(", ")
             ),
             ty::TypeValue::Array(sub, size) => format!("[{}; {}]", self.ty_to_string(sub), size),
+            ty::TypeValue::DynArray(sub) => format!("[]{}", self.ty_to_string(sub)),
+            ty::TypeValue::Slice(sub) => format!("&[{}]", self.ty_to_string(sub)),
             ty::TypeValue::FunctionPtr(ref func_ty) => {
                 let params: Vec<_> = func_ty
                     .parameters_ty
@@ -308,11 +360,27 @@ fn value_to_string(value: &ir::Value) -> String {
     }
 }
 
+fn constant_to_string(constant: &ir::Constant) -> String {
+    match *constant {
+        ir::Constant::Literal(ref lit) => lit_to_string(lit),
+        ir::Constant::Null => "null".to_string(),
+        ir::Constant::Aggregate(ref constants) => {
+            let constants: Vec<_> = constants.iter().map(constant_to_string).collect();
+            format!("{{{}}}", constants.join(", "))
+        }
+    }
+}
+
 fn lit_to_string(lit: &ir::Literal) -> String {
     match *lit {
         ir::Literal::IntLiteral(i) => i.to_string(),
+        ir::Literal::SizedIntLiteral(i, kind) if kind.is_signed() => {
+            format!("{}{}", i, kind.name())
+        }
+        ir::Literal::SizedIntLiteral(i, kind) => format!("{}{}", i as u64, kind.name()),
         ir::Literal::DoubleLiteral(d) => d.to_string(),
         ir::Literal::BooleanLiteral(b) => b.to_string(),
+        ir::Literal::CharLiteral(c) => format!("{:?}", c as char),
         ir::Literal::StringLiteral(id) => format!("{:?}", id),
     }
 }
@@ -0,0 +1,351 @@
+use std::fmt::{self, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A JSON value, enough for the diagnostics and the messages of the language server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        if let Json::Object(ref members) = *self {
+            members.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+        } else {
+            None
+        }
+    }
+
+    // follow a path of keys through nested objects
+    pub fn path(&self, keys: &[&str]) -> Option<&Json> {
+        keys.iter().try_fold(self, |json, key| json.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Json::String(ref s) = *self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Json::Number(n) if n >= 0.0 => Some(n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        if let Json::Array(ref values) = *self {
+            Some(values)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(s: &'a str) -> Json {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(values: Vec<Json>) -> Json {
+        Json::Array(values)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(ref s) => write!(f, "{}", quote(s)),
+            Json::Array(ref values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(ref members) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", quote(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Quote and escape a string for JSON.
+pub fn quote(s: &str) -> String {
+    let mut output = String::with_capacity(s.len() + 2);
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Parse a JSON document, None if it is malformed.
+pub fn parse(input: &str) -> Option<Json> {
+    let mut chars = input.chars().peekable();
+    let value = parse_value(&mut chars)?;
+    skip_whitespaces(&mut chars);
+    if chars.peek().is_none() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+type Input<'a> = Peekable<Chars<'a>>;
+
+fn skip_whitespaces(chars: &mut Input) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn expect_word(chars: &mut Input, word: &str, value: Json) -> Option<Json> {
+    for expected in word.chars() {
+        if chars.next() != Some(expected) {
+            return None;
+        }
+    }
+    Some(value)
+}
+
+fn parse_value(chars: &mut Input) -> Option<Json> {
+    skip_whitespaces(chars);
+    match *chars.peek()? {
+        'n' => expect_word(chars, "null", Json::Null),
+        't' => expect_word(chars, "true", Json::Bool(true)),
+        'f' => expect_word(chars, "false", Json::Bool(false)),
+        '"' => parse_string(chars).map(Json::String),
+        '[' => {
+            chars.next();
+            let mut values = Vec::new();
+            skip_whitespaces(chars);
+            if chars.peek() == Some(&']') {
+                chars.next();
+                return Some(Json::Array(values));
+            }
+            loop {
+                values.push(parse_value(chars)?);
+                skip_whitespaces(chars);
+                match chars.next()? {
+                    ',' => {}
+                    ']' => return Some(Json::Array(values)),
+                    _ => return None,
+                }
+            }
+        }
+        '{' => {
+            chars.next();
+            let mut members = Vec::new();
+            skip_whitespaces(chars);
+            if chars.peek() == Some(&'}') {
+                chars.next();
+                return Some(Json::Object(members));
+            }
+            loop {
+                skip_whitespaces(chars);
+                let key = parse_string(chars)?;
+                skip_whitespaces(chars);
+                if chars.next()? != ':' {
+                    return None;
+                }
+                members.push((key, parse_value(chars)?));
+                skip_whitespaces(chars);
+                match chars.next()? {
+                    ',' => {}
+                    '}' => return Some(Json::Object(members)),
+                    _ => return None,
+                }
+            }
+        }
+        _ => parse_number(chars),
+    }
+}
+
+fn parse_number(chars: &mut Input) -> Option<Json> {
+    let mut number = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E' {
+            number.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    number.parse().ok().map(Json::Number)
+}
+
+fn parse_string(chars: &mut Input) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+
+    let mut s = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(s),
+            '\\' => match chars.next()? {
+                'n' => s.push('\n'),
+                'r' => s.push('\r'),
+                't' => s.push('\t'),
+                'b' => s.push('\u{8}'),
+                'f' => s.push('\u{c}'),
+                'u' => {
+                    let unit = parse_hex4(chars)?;
+                    // characters outside the BMP come as a surrogate pair
+                    let c = if (0xD800..0xDC00).contains(&unit) {
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        let low = parse_hex4(chars)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return None;
+                        }
+                        ::std::char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00))
+                    } else {
+                        ::std::char::from_u32(unit)
+                    };
+                    s.push(c.unwrap_or('\u{FFFD}'));
+                }
+                c => s.push(c),
+            },
+            c => s.push(c),
+        }
+    }
+}
+
+fn parse_hex4(chars: &mut Input) -> Option<u32> {
+    let mut value = 0;
+    for _ in 0..4 {
+        value = value * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, quote, Json};
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(parse(" null "), Some(Json::Null));
+        assert_eq!(parse("true"), Some(Json::Bool(true)));
+        assert_eq!(parse("-1.5e2"), Some(Json::Number(-150.0)));
+        assert_eq!(parse("[]"), Some(Json::Array(Vec::new())));
+        assert_eq!(
+            parse("{\"a\": [1, {\"b\": false}], \"c\": \"d\"}"),
+            Some(Json::object(vec![
+                (
+                    "a",
+                    vec![1.into(), Json::object(vec![("b", false.into())])].into(),
+                ),
+                ("c", "d".into()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("nul"), None);
+        assert_eq!(parse("[1, 2"), None);
+        assert_eq!(parse("{\"a\" 1}"), None);
+        assert_eq!(parse("1 2"), None);
+        assert_eq!(parse("\"\\ud800\""), None);
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            parse(r#""a\"\\\n\t\u00e9\ud83d\ude00""#),
+            Some("a\"\\\n\té\u{1F600}".into())
+        );
+        assert_eq!(quote("a\"\\\n\u{1}é"), r#""a\"\\\n\u0001é""#);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let value = Json::object(vec![
+            ("id", 3.into()),
+            ("ratio", Json::Number(0.5)),
+            ("text", "line\n\"quoted\"".into()),
+            ("items", vec![Json::Null, true.into()].into()),
+        ]);
+        let text = value.to_string();
+        assert_eq!(
+            text,
+            r#"{"id":3,"ratio":0.5,"text":"line\n\"quoted\"","items":[null,true]}"#
+        );
+        assert_eq!(parse(&text), Some(value));
+    }
+
+    #[test]
+    fn object_access() {
+        let value = parse(r#"{"a": {"b": {"c": 7}}, "s": "x", "l": [1]}"#).unwrap();
+        assert_eq!(value.path(&["a", "b", "c"]).and_then(Json::as_usize), Some(7));
+        assert_eq!(value.path(&["a", "x"]), None);
+        assert_eq!(value.get("s").and_then(Json::as_str), Some("x"));
+        assert_eq!(value.get("l").and_then(Json::as_array).map(|l| l.len()), Some(1));
+    }
+}
@@ -1,6 +1,9 @@
+use std::str;
+
 use regex::Regex;
 use errors::LexingError;
 use codemap::{Span, Spanned};
+use ty::IntKind;
 
 mod token;
 pub use self::token::Token;
@@ -10,37 +13,53 @@ lazy_static! {
     static ref LINE_COMMENT: Regex = Regex::new(r"^//.*").unwrap();
     static ref LINE_PP_COMMENT: Regex = Regex::new(r"^#.*").unwrap();
     static ref BLOCK_COMMENT: Regex = Regex::new(r"^/\*(.|[\r\n])*?\*/").unwrap();
-    static ref SKIPABLE: Vec<&'static Regex> = vec![
-        &WHITESPACES, &LINE_COMMENT, &LINE_PP_COMMENT, &BLOCK_COMMENT
+    static ref COMMENTS: Vec<&'static Regex> = vec![
+        &LINE_COMMENT, &LINE_PP_COMMENT, &BLOCK_COMMENT
     ];
 
     static ref IDENTIFIER_REGEX: Regex = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*").unwrap();
-    static ref INTEGER_REGEX: Regex = Regex::new(r"^[0-9]+").unwrap();
+    static ref INTEGER_REGEX: Regex = Regex::new(r"^[0-9]+(i8|i16|i64|u8|u32|u64)?").unwrap();
     static ref DOUBLE_REGEX: Regex = Regex::new(r"^[0-9]*\.[0-9]+([eE][+-]?[0-9]+)?").unwrap();
-    static ref STRING_REGEX: Regex = Regex::new(r##"^"(([^"]|\\")*[^\\])?""##).unwrap();
+    static ref CHAR_REGEX: Regex = Regex::new(r"^'(\\x[0-9a-fA-F]{2}|\\.|[^'\\\n])'").unwrap();
+    static ref INVALID_CHAR_REGEX: Regex = Regex::new(r"^'[^'\n]*'?").unwrap();
+    static ref STRING_REGEX: Regex = Regex::new(r##"^"([^"\\]|\\.)*""##).unwrap();
 }
 
 pub struct Lexer<'input> {
     input: &'input str,
     pos: usize,
     buffer: Option<Spanned<Token<'input>>>,
+    pub comments: Vec<Span>, // the skipped comments, for the formatter
 }
 
 pub type LexingResult<T> = Result<T, Spanned<LexingError>>;
 
+/// A position of the lexer, to come back to after looking ahead.
+pub struct Checkpoint<'input> {
+    pos: usize,
+    buffer: Option<Spanned<Token<'input>>>,
+    comments: usize,
+}
+
 impl<'input> Lexer<'input> {
     pub fn new(input: &'input str) -> Self {
         Lexer {
             input,
             pos: 0,
             buffer: None,
+            comments: Vec::new(),
         }
     }
 
     pub fn skip_whitespaces(&mut self) {
         'main_loop: loop {
-            for regex in SKIPABLE.iter() {
+            if let Some(m) = WHITESPACES.find(&self.input[self.pos..]) {
+                self.pos += m.end();
+                continue 'main_loop;
+            }
+            for regex in COMMENTS.iter() {
                 if let Some(m) = regex.find(&self.input[self.pos..]) {
+                    self.comments.push(Span::new_with_len(self.pos, m.end()));
                     self.pos += m.end();
                     continue 'main_loop;
                 }
@@ -58,6 +77,20 @@ impl<'input> Lexer<'input> {
         })
     }
 
+    pub fn checkpoint(&self) -> Checkpoint<'input> {
+        Checkpoint {
+            pos: self.pos,
+            buffer: self.buffer.clone(),
+            comments: self.comments.len(),
+        }
+    }
+
+    pub fn rewind(&mut self, checkpoint: Checkpoint<'input>) {
+        self.pos = checkpoint.pos;
+        self.buffer = checkpoint.buffer;
+        self.comments.truncate(checkpoint.comments);
+    }
+
     pub fn peek_token(&mut self) -> LexingResult<&Spanned<Token<'input>>> {
         if self.buffer.is_none() {
             self.buffer = Some(self.next_token()?);
@@ -96,6 +129,7 @@ impl<'input> Lexer<'input> {
         }
 
         match_literal!(self; "..." => Token::DotDotDot);
+        match_literal!(self; ".." => Token::DotDot);
         match_literal!(self; "(" => Token::LeftParenthesis);
         match_literal!(self; ")" => Token::RightParenthesis);
         match_literal!(self; "{" => Token::LeftBracket);
@@ -103,20 +137,37 @@ impl<'input> Lexer<'input> {
         match_literal!(self; "[" => Token::LeftSquare);
         match_literal!(self; "]" => Token::RightSquare);
         match_literal!(self; ";" => Token::SemiColon);
+        match_literal!(self; "::" => Token::ColonColon);
         match_literal!(self; ":" => Token::Colon);
         match_literal!(self; "," => Token::Comma);
         match_literal!(self; "." => Token::Dot);
         match_literal!(self; "->" => Token::Arrow);
 
+        match_literal!(self; "=>" => Token::FatArrow);
         match_literal!(self; "==" => Token::EqualEqual);
         match_literal!(self; "!=" => Token::BangEqual);
         match_literal!(self; "++" => Token::PlusPlus);
         match_literal!(self; "--" => Token::MinusMinus);
+        match_literal!(self; "<<=" => Token::LessLessEqual);
+        match_literal!(self; ">>=" => Token::GreaterGreaterEqual);
+        match_literal!(self; "<<" => Token::LessLess);
+        match_literal!(self; ">>" => Token::GreaterGreater);
         match_literal!(self; "<=" => Token::LessEqual);
         match_literal!(self; ">=" => Token::GreaterEqual);
         match_literal!(self; "||" => Token::PipePipe);
         match_literal!(self; "&&" => Token::AmpAmp);
+        match_literal!(self; "+=" => Token::PlusEqual);
+        match_literal!(self; "-=" => Token::MinusEqual);
+        match_literal!(self; "*=" => Token::StarEqual);
+        match_literal!(self; "/=" => Token::SlashEqual);
+        match_literal!(self; "%=" => Token::PercentEqual);
+        match_literal!(self; "&=" => Token::AmpEqual);
+        match_literal!(self; "|=" => Token::PipeEqual);
+        match_literal!(self; "^=" => Token::CaretEqual);
         match_literal!(self; "&" => Token::Amp);
+        match_literal!(self; "|" => Token::Pipe);
+        match_literal!(self; "^" => Token::Caret);
+        match_literal!(self; "~" => Token::Tilde);
 
         match_literal!(self; "=" => Token::Equal);
         match_literal!(self; "+" => Token::Plus);
@@ -143,10 +194,17 @@ impl<'input> Lexer<'input> {
                 "continue" => Token::ContinueKeyword,
                 "break" => Token::BreakKeyword,
                 "struct" => Token::StructKeyword,
+                "enum" => Token::EnumKeyword,
+                "match" => Token::MatchKeyword,
                 "as" => Token::AsKeyword,
                 "fn" => Token::FnKeyword,
                 "let" => Token::LetKeyword,
+                "const" => Token::ConstKeyword,
+                "new" => Token::NewKeyword,
+                "delete" => Token::DeleteKeyword,
                 "nullptr" => Token::NullptrKeyword,
+                "sizeof" => Token::SizeofKeyword,
+                "alignof" => Token::AlignofKeyword,
                 s => {
                     if s.starts_with("___") {
                         return Err(Spanned::new(
@@ -174,26 +232,108 @@ impl<'input> Lexer<'input> {
         }
         if let Some(s) = self.match_regex(&INTEGER_REGEX) {
             let len = s.len();
-            let number = if let Ok(n) = s.parse() {
-                n
-            } else {
-                return Err(Spanned::new(
-                    LexingError::UnparsableNumber,
-                    Span::new_with_len(start_pos, len),
-                ));
+            // the suffix gives the type of the literal, `int` without one
+            let digits_len = s.find(|c: char| !c.is_digit(10)).unwrap_or(len);
+            let (digits, suffix) = s.split_at(digits_len);
+            let token = match (IntKind::from_name(suffix), digits.parse(), digits.parse()) {
+                (None, Ok(n), _) => Token::IntegerLiteral(n),
+                (Some(kind), _, Ok(n)) => Token::SizedIntegerLiteral(n, kind),
+                _ => {
+                    return Err(Spanned::new(
+                        LexingError::UnparsableNumber,
+                        Span::new_with_len(start_pos, len),
+                    ))
+                }
             };
-            let token = Token::IntegerLiteral(number);
             return Ok(Spanned::new(token, Span::new_with_len(start_pos, len)));
         }
+        if self.input[self.pos..].starts_with('\'') {
+            let (len, c) = match self.match_regex(&CHAR_REGEX) {
+                Some(s) => (s.len(), convert_escape_char(&s[1..s.len() - 1])),
+                // skip the literal so that lexing can resume after the error
+                None => (self.match_regex(&INVALID_CHAR_REGEX).unwrap().len(), None),
+            };
+            let span = Span::new_with_len(start_pos, len);
+            return match c {
+                Some(c) => Ok(Spanned::new(Token::CharLiteral(c), span)),
+                None => Err(Spanned::new(LexingError::InvalidCharLiteral, span)),
+            };
+        }
         if let Some(s) = self.match_regex(&STRING_REGEX) {
-            let len = s.len();
-            let token = Token::StringLiteral(s);
-            return Ok(Spanned::new(token, Span::new_with_len(start_pos, len)));
+            let span = Span::new_with_len(start_pos, s.len());
+            return match convert_escape_string(&s[1..s.len() - 1]) {
+                Some(s) => Ok(Spanned::new(Token::StringLiteral(s), span)),
+                None => Err(Spanned::new(LexingError::InvalidStringEscape, span)),
+            };
+        }
+
+        // skip the character so that lexing can resume after the error
+        let c = (&self.input[self.pos..]).chars().next().unwrap();
+        self.pos += c.len_utf8();
+        Err(Spanned::new(LexingError::UnknownChar(c), Span::new_one(start_pos)))
+    }
+}
+
+// the byte of the escape starting `s`, after its `\\`, and the length of the escape
+fn decode_escape(s: &[u8]) -> Option<(u8, usize)> {
+    let byte = match *s.first()? {
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'b' => 0x08,
+        b'f' => 0x0c,
+        b'0' => 0,
+        c @ b'\\' | c @ b'\'' | c @ b'"' => c,
+        b'x' => {
+            let digits = s.get(1..3)?;
+            if !digits.iter().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let digits = str::from_utf8(digits).ok()?;
+            return u8::from_str_radix(digits, 16).ok().map(|c| (c, 3));
         }
+        _ => return None,
+    };
+    Some((byte, 1))
+}
+
+// the byte of the content of a char literal, None if it isn't ASCII or an escape
+fn convert_escape_char(s: &str) -> Option<u8> {
+    match s.as_bytes() {
+        [b'\\', escape @ ..] => match decode_escape(escape)? {
+            (c, len) if len == escape.len() => Some(c),
+            _ => None,
+        },
+        [c] if c.is_ascii() => Some(*c),
+        _ => None,
+    }
+}
 
-        Err(Spanned::new(
-            LexingError::UnknownChar((&self.input[self.pos..]).chars().next().unwrap()),
-            Span::new_one(self.pos),
-        ))
+// the content of a string literal, None if an escape is unknown or isn't an ASCII byte
+fn convert_escape_string(s: &str) -> Option<String> {
+    let mut output = String::new();
+    let mut ended = false;
+    let mut chars = s.char_indices();
+    while let Some((index, c)) = chars.next() {
+        let c = if c == '\\' {
+            let (c, len) = decode_escape(&s.as_bytes()[index + 1..])?;
+            if !c.is_ascii() {
+                return None;
+            }
+            // the escapes are ASCII, one char per byte
+            for _ in 0..len {
+                chars.next();
+            }
+            c as char
+        } else {
+            c
+        };
+
+        // the strings of the runtime are C strings, they stop at the first nul byte
+        ended = ended || c == '\0';
+        if !ended {
+            output.push(c);
+        }
     }
+    Some(output)
 }
@@ -1,3 +1,5 @@
+use ty::IntKind;
+
 /// Represents a Token from a Javalette program.
 ///
 /// Usually constructed by a `Lexer` and used by the `Parser` to build the `AST`.
@@ -16,9 +18,21 @@ pub enum Token<'input> {
     SemiColon,
     Comma,
     Colon,
+    ColonColon,
     DotDotDot,
+    DotDot,
     // operators
     Equal,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    AmpEqual,
+    PipeEqual,
+    CaretEqual,
+    LessLessEqual,
+    GreaterGreaterEqual,
     Plus,
     Minus,
     Star,
@@ -33,13 +47,21 @@ pub enum Token<'input> {
     Greater,
     GreaterEqual,
     PipePipe,
+    Pipe,
     Amp,
     AmpAmp,
+    Caret,
+    LessLess,
+    GreaterGreater,
+    Tilde,
     Bang,
     Arrow,
+    FatArrow,
     //keywords
     ExternKeyword,
     StructKeyword,
+    EnumKeyword,
+    MatchKeyword,
     WhileKeyword,
     ForKeyword,
     IfKeyword,
@@ -50,7 +72,12 @@ pub enum Token<'input> {
     AsKeyword,
     FnKeyword,
     LetKeyword,
+    ConstKeyword,
     NullptrKeyword,
+    NewKeyword,
+    DeleteKeyword,
+    SizeofKeyword,
+    AlignofKeyword,
 
     /*IntKeyword,
     DoubleKeyword,
@@ -59,7 +86,9 @@ pub enum Token<'input> {
     StringKeyword,*/
     Identifier(&'input str),
     IntegerLiteral(i64),
+    SizedIntegerLiteral(u64, IntKind),
+    CharLiteral(u8),
     DoubleLiteral(f64),
     BooleanLiteral(bool),
-    StringLiteral(&'input str),
+    StringLiteral(String), // with its escapes decoded
 }
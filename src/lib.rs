@@ -9,8 +9,12 @@ extern crate typed_arena;
 
 pub mod context;
 pub mod errors;
+pub mod explanations;
+pub mod json;
+pub mod formatter;
 pub mod interner;
 pub mod codemap;
+pub mod diagnostics;
 pub mod lexer;
 pub mod ast;
 pub mod parser;
@@ -19,3 +23,8 @@ pub mod ty;
 pub mod ir;
 pub mod trans;
 pub mod backend;
+pub mod interpreter;
+pub mod repl;
+pub mod lsp;
+pub mod testsuite;
+pub mod test_harness;
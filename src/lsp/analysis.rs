@@ -0,0 +1,50 @@
+use codemap::Spanned;
+use errors::{self, UserError};
+use interner::Interner;
+use lexer::Lexer;
+use parser;
+use trans::{self, Warnings};
+use trans::tables::{SourceIndex, Tables};
+
+/// Everything the server knows about one version of a document.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub errors: Vec<Spanned<UserError>>,
+    pub warnings: Warnings,
+    pub index: SourceIndex,
+}
+
+/// Run the parser and the translation on a document, like the check backend does.
+pub fn analyze(input: &str) -> Analysis {
+    let mut strings = Interner::new();
+    let mut tables = Tables::default();
+
+    let runtime = trans::translate_runtime(&mut strings, &mut tables);
+
+    // the runtime is left out of the index, it has no location in the document
+    tables.index = Some(SourceIndex::default());
+
+    let mut analysis = Analysis::default();
+    let (ast, parse_errors) = parser::parse_partial_program(Lexer::new(input), &mut strings);
+
+    // a document that doesn't parse is still translated to index what is left of it, but
+    // the other errors and the warnings could come from the code the parser dropped
+    let mut warnings = Vec::new();
+    let program = trans::translate_program(&mut tables, ast, Some(runtime), &mut warnings);
+    if !parse_errors.is_empty() {
+        analysis.errors = errors::into_user_errors(parse_errors);
+    } else {
+        analysis.warnings = warnings;
+        match program {
+            Ok(program) => {
+                if let Err(err) = trans::check_if_main_declaration(&tables, &program) {
+                    analysis.errors.push(err.into());
+                }
+            }
+            Err(errors) => analysis.errors = errors::into_user_errors(errors),
+        }
+    }
+
+    analysis.index = tables.index.unwrap_or_default();
+    analysis
+}
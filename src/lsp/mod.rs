@@ -0,0 +1,433 @@
+// a language server speaking LSP over stdio, built on the parser and the translation passes
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use codemap::{CodeMap, SourceLocation, Span};
+use common::Field;
+use diagnostics::{LintLevel, LintLevels};
+use json::{self, Json};
+use ty;
+
+pub mod analysis;
+
+use self::analysis::Analysis;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// LSP constants
+const TEXT_DOCUMENT_SYNC_FULL: usize = 1;
+const SEVERITY_ERROR: usize = 1;
+const SEVERITY_WARNING: usize = 2;
+const COMPLETION_KIND_FIELD: usize = 5;
+
+// stands for the field being completed, so that `expr.` can be parsed
+const COMPLETION_PLACEHOLDER: &str = "__complete";
+
+type RequestResult = Result<Json, (i64, String)>;
+
+struct Document {
+    text: String,
+    analysis: Analysis,
+}
+
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server {
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Serve the client until it sends `exit`, and return the exit code of the process.
+    pub fn run(&mut self) -> i32 {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+
+        while let Some(body) = read_message(&mut input) {
+            if let Some(message) = json::parse(&body) {
+                if let Some(code) = self.handle_message(&message) {
+                    return code;
+                }
+            } else {
+                send_response(Json::Null, Err((PARSE_ERROR, "invalid JSON".to_string())));
+            }
+        }
+        1 // the client went away without exit
+    }
+
+    fn handle_message(&mut self, message: &Json) -> Option<i32> {
+        // the responses of the client are ignored, the server never sends requests
+        let method = message.get("method").and_then(Json::as_str)?;
+        let params = message.get("params").unwrap_or(&Json::Null);
+
+        if let Some(id) = message.get("id") {
+            let result = self.handle_request(method, params);
+            send_response(id.clone(), result);
+        } else if method == "exit" {
+            return Some(if self.shutdown { 0 } else { 1 });
+        } else {
+            self.handle_notification(method, params);
+        }
+        None
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Json) -> RequestResult {
+        match method {
+            "initialize" => Ok(Json::object(vec![
+                (
+                    "capabilities",
+                    Json::object(vec![
+                        ("textDocumentSync", TEXT_DOCUMENT_SYNC_FULL.into()),
+                        ("hoverProvider", true.into()),
+                        ("definitionProvider", true.into()),
+                        (
+                            "completionProvider",
+                            Json::object(vec![("triggerCharacters", vec![".".into()].into())]),
+                        ),
+                    ]),
+                ),
+                (
+                    "serverInfo",
+                    Json::object(vec![
+                        ("name", "yalc".into()),
+                        ("version", env!("CARGO_PKG_VERSION").into()),
+                    ]),
+                ),
+            ])),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/hover" => {
+                let (uri, offset) = self.document_position(params)?;
+                Ok(self.hover(uri, offset))
+            }
+            "textDocument/definition" => {
+                let (uri, offset) = self.document_position(params)?;
+                Ok(self.definition(uri, offset))
+            }
+            "textDocument/completion" => {
+                let (uri, offset) = self.document_position(params)?;
+                Ok(self.completion(uri, offset))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Json) {
+        let uri = match params.path(&["textDocument", "uri"]).and_then(Json::as_str) {
+            Some(uri) => uri.to_string(),
+            None => return,
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                if let Some(text) = params.path(&["textDocument", "text"]).and_then(Json::as_str) {
+                    self.update_document(uri, text.to_string());
+                }
+            }
+            "textDocument/didChange" => {
+                // the sync is full, the last change holds the whole text
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                if let Some(text) = text {
+                    self.update_document(uri, text.to_string());
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                send_diagnostics(&uri, Vec::new());
+            }
+            _ => {}
+        }
+    }
+
+    fn update_document(&mut self, uri: String, text: String) {
+        let analysis = analysis::analyze(&text);
+        let document = Document { text, analysis };
+        send_diagnostics(&uri, document_diagnostics(&uri, &document));
+        self.documents.insert(uri, document);
+    }
+
+    fn document_position<'a>(&self, params: &'a Json) -> Result<(&'a str, usize), (i64, String)> {
+        let uri = params
+            .path(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .ok_or_else(|| (INVALID_PARAMS, "missing document".to_string()))?;
+        let document = self.documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown document '{}'", uri)))?;
+        let position = params
+            .get("position")
+            .ok_or_else(|| (INVALID_PARAMS, "missing position".to_string()))?;
+
+        let codemap = CodeMap::new(uri, &document.text);
+        let offset = position_to_offset(&codemap, position)
+            .ok_or_else(|| (INVALID_PARAMS, "invalid position".to_string()))?;
+        Ok((uri, offset))
+    }
+
+    fn hover(&self, uri: &str, offset: usize) -> Json {
+        let document = &self.documents[uri];
+        let codemap = CodeMap::new(uri, &document.text);
+        match document.analysis.index.expression_at(offset) {
+            Some((span, ty)) => Json::object(vec![
+                (
+                    "contents",
+                    Json::object(vec![
+                        ("kind", "plaintext".into()),
+                        ("value", ty.to_string().into()),
+                    ]),
+                ),
+                ("range", span_to_range(&codemap, span)),
+            ]),
+            None => Json::Null,
+        }
+    }
+
+    fn definition(&self, uri: &str, offset: usize) -> Json {
+        let document = &self.documents[uri];
+        let codemap = CodeMap::new(uri, &document.text);
+        match document.analysis.index.definition_at(offset) {
+            Some((_, definition)) => Json::object(vec![
+                ("uri", uri.into()),
+                ("range", span_to_range(&codemap, definition)),
+            ]),
+            None => Json::Null,
+        }
+    }
+
+    fn completion(&self, uri: &str, offset: usize) -> Json {
+        let document = &self.documents[uri];
+        let text = &document.text;
+
+        // only fields are completed, the cursor must be after `expr.` and part of a name
+        let word_start = text[..offset]
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| c.is_alphanumeric() || c == '_')
+            .last()
+            .map_or(offset, |(index, _)| index);
+        if !text[..word_start].ends_with('.') {
+            return Json::Array(Vec::new());
+        }
+        let dot = word_start - 1;
+
+        // the text being typed rarely parses, so the name is replaced by a placeholder,
+        // followed by a `;` if the statement is not finished either
+        let mut ty = document.analysis.index.expression_ending_at(dot);
+        for suffix in &["", ";"] {
+            if ty.is_some() {
+                break;
+            }
+            let patched = format!(
+                "{}{}{}{}",
+                &text[..word_start],
+                COMPLETION_PLACEHOLDER,
+                suffix,
+                &text[offset..]
+            );
+            ty = analysis::analyze(&patched).index.expression_ending_at(dot);
+        }
+
+        ty.map_or_else(Vec::new, field_completions).into()
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server::new()
+    }
+}
+
+fn field_completions(ty: ty::Type) -> Vec<Json> {
+    let ty = if let ty::TypeValue::LValue(sub, _) = *ty {
+        sub
+    } else {
+        ty
+    };
+
+    let candidates = match *ty {
+        ty::TypeValue::Struct(st) => st.fields
+            .iter()
+            .map(|&(ref name, _)| Field::Named(name.clone()))
+            .collect(),
+        ty::TypeValue::Tuple(ref types) => (0..types.len()).map(Field::Index).collect(),
+        ty::TypeValue::Array(_, _)
+        | ty::TypeValue::DynArray(_)
+        | ty::TypeValue::Slice(_)
+        | ty::TypeValue::String => vec![Field::Named("len".to_string())],
+        _ => Vec::new(),
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|field| {
+            let detail = match ty.has_field(&field)? {
+                ty::FieldInfo::StructField(_, ty) | ty::FieldInfo::TupleField(_, ty) => {
+                    ty.to_string()
+                }
+                ty::FieldInfo::ArrayLen(_)
+                | ty::FieldInfo::RuntimeLen
+                | ty::FieldInfo::StringLen => "int".to_string(),
+            };
+            Some(Json::object(vec![
+                ("label", field.to_string().into()),
+                ("kind", COMPLETION_KIND_FIELD.into()),
+                ("detail", detail.into()),
+            ]))
+        })
+        .collect()
+}
+
+fn document_diagnostics(uri: &str, document: &Document) -> Vec<Json> {
+    let codemap = CodeMap::new(uri, &document.text);
+    let levels = LintLevels::default();
+    let mut diagnostics = Vec::new();
+
+    let diagnostic = |span, severity, code: &str, message: String| {
+        Json::object(vec![
+            ("range", span_to_range(&codemap, span)),
+            ("severity", severity),
+            ("code", code.into()),
+            ("source", "yalc".into()),
+            ("message", message.into()),
+        ])
+    };
+
+    for warning in &document.analysis.warnings {
+        let severity = match levels.level(warning.inner.lint()) {
+            LintLevel::Allow => continue,
+            LintLevel::Warn => SEVERITY_WARNING,
+            LintLevel::Deny => SEVERITY_ERROR,
+        };
+        diagnostics.push(diagnostic(
+            warning.span,
+            severity.into(),
+            warning.inner.code(),
+            warning.inner.to_string(),
+        ));
+    }
+
+    for error in &document.analysis.errors {
+        diagnostics.push(diagnostic(
+            error.span,
+            SEVERITY_ERROR.into(),
+            error.inner.code(),
+            error.inner.to_string(),
+        ));
+    }
+    diagnostics
+}
+
+// LSP positions are 0-based lines and columns counted in UTF-16 code units
+fn position_to_offset(codemap: &CodeMap, position: &Json) -> Option<usize> {
+    let line = position.get("line")?.as_usize()? + 1;
+    let character = position.get("character")?.as_usize()?;
+
+    let mut units = 0;
+    let mut column = 0;
+    for c in codemap.line(line).chars() {
+        if units >= character {
+            break;
+        }
+        units += c.len_utf16();
+        column += c.len_utf8();
+    }
+    Some(codemap.sourceloc_to_bytepos(SourceLocation { line, column }))
+}
+
+fn offset_to_position(codemap: &CodeMap, offset: usize) -> Json {
+    let loc = codemap.bytepos_to_sourceloc(offset);
+    let line = codemap.line(loc.line);
+    let before = line.get(..loc.column).unwrap_or(line);
+    Json::object(vec![
+        ("line", (loc.line - 1).into()),
+        ("character", before.encode_utf16().count().into()),
+    ])
+}
+
+fn span_to_range(codemap: &CodeMap, span: Span) -> Json {
+    // the errors without location are shown at the start of the document
+    let span = if span == Span::dummy() {
+        Span::new(0, 0)
+    } else {
+        span
+    };
+    Json::object(vec![
+        ("start", offset_to_position(codemap, span.start)),
+        ("end", offset_to_position(codemap, span.end)),
+    ])
+}
+
+fn read_message<R: BufRead>(input: &mut R) -> Option<String> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        let mut parts = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    String::from_utf8(body).ok()
+}
+
+fn send_response(id: Json, result: RequestResult) {
+    let outcome = match result {
+        Ok(result) => ("result", result),
+        Err((code, message)) => (
+            "error",
+            Json::object(vec![
+                ("code", Json::Number(code as f64)),
+                ("message", message.into()),
+            ]),
+        ),
+    };
+    send(&Json::object(vec![("jsonrpc", "2.0".into()), ("id", id), outcome]));
+}
+
+fn send_diagnostics(uri: &str, diagnostics: Vec<Json>) {
+    send(&Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            Json::object(vec![("uri", uri.into()), ("diagnostics", diagnostics.into())]),
+        ),
+    ]));
+}
+
+fn send(message: &Json) {
+    let body = message.to_string();
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+        .and_then(|_| output.flush())
+        .expect("can't write to stdout");
+}
@@ -12,7 +12,17 @@ struct Infos {
 
 fn info_of<'input>(binop: &Token<'input>) -> Option<Infos> {
     match *binop {
-        Token::Equal => Some(Infos {
+        Token::Equal
+        | Token::PlusEqual
+        | Token::MinusEqual
+        | Token::StarEqual
+        | Token::SlashEqual
+        | Token::PercentEqual
+        | Token::AmpEqual
+        | Token::PipeEqual
+        | Token::CaretEqual
+        | Token::LessLessEqual
+        | Token::GreaterGreaterEqual => Some(Infos {
             precedence: 10,
             right_assoc: true,
             relational: false,
@@ -27,6 +37,22 @@ fn info_of<'input>(binop: &Token<'input>) -> Option<Infos> {
             right_assoc: false,
             relational: false,
         }),
+        // like in C, the bitwise operators are below the comparisons
+        Token::Pipe => Some(Infos {
+            precedence: 33,
+            right_assoc: false,
+            relational: false,
+        }),
+        Token::Caret => Some(Infos {
+            precedence: 35,
+            right_assoc: false,
+            relational: false,
+        }),
+        Token::Amp => Some(Infos {
+            precedence: 37,
+            right_assoc: false,
+            relational: false,
+        }),
         Token::EqualEqual | Token::BangEqual => Some(Infos {
             precedence: 40,
             right_assoc: false,
@@ -37,6 +63,11 @@ fn info_of<'input>(binop: &Token<'input>) -> Option<Infos> {
             right_assoc: false,
             relational: true,
         }),
+        Token::LessLess | Token::GreaterGreater => Some(Infos {
+            precedence: 55,
+            right_assoc: false,
+            relational: false,
+        }),
         Token::Plus | Token::Minus => Some(Infos {
             precedence: 60,
             right_assoc: false,
@@ -51,6 +82,23 @@ fn info_of<'input>(binop: &Token<'input>) -> Option<Infos> {
     }
 }
 
+// the operator applied by a compound assignment like `+=`
+fn compound_binop(token: &Token) -> Option<ast::BinaryOperatorKind> {
+    match *token {
+        Token::PlusEqual => Some(ast::BinaryOperatorKind::Plus),
+        Token::MinusEqual => Some(ast::BinaryOperatorKind::Minus),
+        Token::StarEqual => Some(ast::BinaryOperatorKind::Multiply),
+        Token::SlashEqual => Some(ast::BinaryOperatorKind::Divide),
+        Token::PercentEqual => Some(ast::BinaryOperatorKind::Modulo),
+        Token::AmpEqual => Some(ast::BinaryOperatorKind::BitAnd),
+        Token::PipeEqual => Some(ast::BinaryOperatorKind::BitOr),
+        Token::CaretEqual => Some(ast::BinaryOperatorKind::BitXor),
+        Token::LessLessEqual => Some(ast::BinaryOperatorKind::ShiftLeft),
+        Token::GreaterGreaterEqual => Some(ast::BinaryOperatorKind::ShiftRight),
+        _ => None,
+    }
+}
+
 fn apply(
     binop: &Token,
     lhs: Spanned<ast::Expression>,
@@ -59,6 +107,10 @@ fn apply(
     let lhs = Box::new(lhs);
     let rhs = Box::new(rhs);
 
+    if let Some(binop) = compound_binop(binop) {
+        return ast::Expression::CompoundAssign { binop, lhs, rhs };
+    }
+
     match *binop {
         Token::Equal => ast::Expression::Assign { lhs, rhs },
         Token::PipePipe => ast::Expression::LazyOperator {
@@ -126,6 +178,31 @@ fn apply(
             lhs,
             rhs,
         },
+        Token::Amp => ast::Expression::BinaryOperator {
+            binop: ast::BinaryOperatorKind::BitAnd,
+            lhs,
+            rhs,
+        },
+        Token::Pipe => ast::Expression::BinaryOperator {
+            binop: ast::BinaryOperatorKind::BitOr,
+            lhs,
+            rhs,
+        },
+        Token::Caret => ast::Expression::BinaryOperator {
+            binop: ast::BinaryOperatorKind::BitXor,
+            lhs,
+            rhs,
+        },
+        Token::LessLess => ast::Expression::BinaryOperator {
+            binop: ast::BinaryOperatorKind::ShiftLeft,
+            lhs,
+            rhs,
+        },
+        Token::GreaterGreater => ast::Expression::BinaryOperator {
+            binop: ast::BinaryOperatorKind::ShiftRight,
+            lhs,
+            rhs,
+        },
         _ => panic!("Expected an operator token"),
     }
 }
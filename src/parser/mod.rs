@@ -11,31 +11,48 @@ use self::expression_parser::*;
 
 pub type ParsingResult<T> = Result<T, Spanned<ParsingError>>;
 
-pub fn parse_program(lexer: Lexer, strings: &mut Interner<String>) -> ParsingResult<ast::Program> {
-    let declarations = {
-        let mut parser = Parser::new(lexer, strings);
-
-        let mut declarations = Vec::new();
+pub fn parse_program(
+    lexer: Lexer,
+    strings: &mut Interner<String>,
+) -> Result<ast::Program, Vec<Spanned<ParsingError>>> {
+    let mut parser = Parser::new(lexer, strings);
+    let declarations = parser.parse_declarations();
+    parser.finish(ast::Program { declarations })
+}
 
-        while !parser.at_eof()? {
-            declarations.push(parser.parse_declaration()?);
-        }
-        declarations
-    };
+/// Parse a program as far as the recovery goes: the declarations that parsed are returned
+/// along with the errors, for the tools that work on broken code.
+pub fn parse_partial_program(
+    lexer: Lexer,
+    strings: &mut Interner<String>,
+) -> (ast::Program, Vec<Spanned<ParsingError>>) {
+    let mut parser = Parser::new(lexer, strings);
+    let declarations = parser.parse_declarations();
+    (ast::Program { declarations }, parser.errors)
+}
 
-    Ok(ast::Program { declarations })
+pub fn parse_repl_entry(
+    lexer: Lexer,
+    strings: &mut Interner<String>,
+) -> Result<ast::ReplEntry, Vec<Spanned<ParsingError>>> {
+    let mut parser = Parser::new(lexer, strings);
+    let entry = parser.parse_repl_entry();
+    parser.finish(entry)
 }
 
 struct Parser<'si, 'input> {
     pub lexer: Lexer<'input>,
     pub string_interner: &'si mut Interner<String>,
+    pub errors: Vec<Spanned<ParsingError>>,
 }
 
 macro_rules! accept {
     ($lexer:expr; $expect:pat => $ret:expr, $($expected:expr),*) => {
         {
-            let Spanned { inner, span } = $lexer.next_token()?;
+            // the token is left in place on error, so the recovery can synchronize on it
+            let Spanned { inner, span } = $lexer.peek_token()?.clone();
             if let $expect = inner {
+                $lexer.next_token()?;
                 ($ret, span)
             } else {
                 return_unexpected!(span, $($expected),*);
@@ -64,15 +81,141 @@ impl<'si, 'input> Parser<'si, 'input> {
         Parser {
             lexer,
             string_interner,
+            errors: Vec::new(),
         }
     }
 
-    fn at_eof(&mut self) -> ParsingResult<bool> {
-        if let Token::EOF = self.lexer.peek_token()?.inner {
-            Ok(true)
+    fn finish<T>(self, value: T) -> Result<T, Vec<Spanned<ParsingError>>> {
+        if self.errors.is_empty() {
+            Ok(value)
         } else {
-            Ok(false)
+            Err(self.errors)
+        }
+    }
+
+    // lexing errors are recorded here, the lexer resumes after the bad input
+    fn peek_recover(&mut self) -> Token<'input> {
+        loop {
+            match self.lexer.peek_token() {
+                Ok(token) => return token.inner.clone(),
+                Err(err) => self.errors.push(err.into()),
+            }
+        }
+    }
+
+    fn at_declaration_boundary(&mut self) -> bool {
+        match self.peek_recover() {
+            Token::EOF
+            | Token::StructKeyword
+            | Token::EnumKeyword
+            | Token::ExternKeyword
+            | Token::FnKeyword
+            | Token::ConstKeyword => true,
+            _ => false,
+        }
+    }
+
+    // skip tokens until the start of the next declaration
+    fn synchronize_declaration(&mut self) {
+        while !self.at_declaration_boundary() {
+            self.lexer.next_token().ok();
+        }
+    }
+
+    // skip tokens until the end of the current statement: after a `;` or a block, before
+    // the `}` closing the enclosing block, or at the start of the next declaration
+    fn synchronize_statement(&mut self) {
+        let mut depth = 0;
+        while !self.at_declaration_boundary() {
+            let done = match self.peek_recover() {
+                Token::RightBracket if depth == 0 => return,
+                Token::RightBracket => {
+                    depth -= 1;
+                    depth == 0
+                }
+                Token::LeftBracket => {
+                    depth += 1;
+                    false
+                }
+                Token::SemiColon => depth == 0,
+                _ => false,
+            };
+
+            self.lexer.next_token().ok();
+            if done {
+                return;
+            }
+        }
+    }
+
+    fn recover_statement(&mut self, error: Spanned<ParsingError>) {
+        self.errors.push(error);
+        self.synchronize_statement();
+    }
+
+    fn parse_declarations(&mut self) -> Vec<ast::Declaration> {
+        let mut declarations = Vec::new();
+        while self.peek_recover() != Token::EOF {
+            match self.parse_declaration() {
+                Ok(declaration) => declarations.push(declaration),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize_declaration();
+                }
+            }
+        }
+        declarations
+    }
+
+    fn parse_repl_entry(&mut self) -> ast::ReplEntry {
+        match self.peek_recover() {
+            Token::StructKeyword
+            | Token::EnumKeyword
+            | Token::ExternKeyword
+            | Token::FnKeyword
+            | Token::ConstKeyword => {
+                let declarations = self.parse_declarations();
+                return ast::ReplEntry::Declarations(ast::Program { declarations });
+            }
+            _ => {}
+        }
+
+        let mut statements = Vec::new();
+        while self.peek_recover() != Token::EOF {
+            let statement = match self.peek_recover() {
+                Token::SemiColon
+                | Token::IfKeyword
+                | Token::WhileKeyword
+                | Token::ForKeyword
+                | Token::ReturnKeyword
+                | Token::LeftBracket
+                | Token::BreakKeyword
+                | Token::ContinueKeyword
+                | Token::LetKeyword => self.parse_statement(),
+                _ => match self.parse_expression() {
+                    Ok(expr) => {
+                        // an expression ending the input is the value of the entry
+                        if self.peek_recover() == Token::EOF {
+                            return ast::ReplEntry::Statements(statements, Some(expr));
+                        }
+                        self.parse_expression_statement_end(expr)
+                    }
+                    Err(err) => Err(err),
+                },
+            };
+
+            match statement {
+                Ok(statement) => statements.push(statement),
+                Err(err) => {
+                    self.recover_statement(err);
+                    // no block is left open at the top of an entry, a `}` there is stray
+                    if self.peek_recover() == Token::RightBracket {
+                        self.lexer.next_token().ok();
+                    }
+                }
+            }
         }
+        ast::ReplEntry::Statements(statements, None)
     }
 
     fn parse_type(&mut self) -> ParsingResult<Spanned<ast::Type>> {
@@ -83,9 +226,29 @@ impl<'si, 'input> Parser<'si, 'input> {
                 let span = Span::merge(begin_span, ty.span);
                 Ok(Spanned::new(ast::Type::Pointer(Box::new(ty)), span))
             }
+            Token::Amp => {
+                let begin_span = self.lexer.next_token()?.span;
+                expect!(self.lexer; Token::LeftSquare, "[");
+                let ty = self.parse_type()?;
+                let end_span = expect!(self.lexer; Token::RightSquare, "]");
+                let span = Span::merge(begin_span, end_span);
+                Ok(Spanned::new(ast::Type::Slice(Box::new(ty)), span))
+            }
             Token::LeftSquare => {
                 let begin_span = self.lexer.next_token()?.span;
+                if let Token::RightSquare = self.lexer.peek_token()?.inner {
+                    self.lexer.next_token()?;
+                    let ty = self.parse_type()?;
+                    let span = Span::merge(begin_span, ty.span);
+                    return Ok(Spanned::new(ast::Type::DynArray(Box::new(ty)), span));
+                }
                 let ty = self.parse_type()?;
+                if let Token::RightSquare = self.lexer.peek_token()?.inner {
+                    // `[T]` is the Javalette spelling of `[]T`
+                    let end_span = self.lexer.next_token()?.span;
+                    let span = Span::merge(begin_span, end_span);
+                    return Ok(Spanned::new(ast::Type::DynArray(Box::new(ty)), span));
+                }
                 expect!(self.lexer; Token::SemiColon, ";");
                 let (size, _) =
                     accept!(self.lexer; Token::IntegerLiteral(lit) => lit as usize, "integer");
@@ -134,9 +297,18 @@ impl<'si, 'input> Parser<'si, 'input> {
         let span = self.lexer.peek_token()?.span;
         match self.lexer.peek_token()?.inner {
             Token::StructKeyword => self.parse_struct_declaration(),
+            Token::EnumKeyword => self.parse_enum_declaration(),
             Token::ExternKeyword => self.parse_extern_function_declaration(),
-            Token::FnKeyword => self.parse_function_declaration(),
-            _ => return_unexpected!(span, "struct", "typedef", "extern", "fn"),
+            Token::FnKeyword => Ok(ast::Declaration::Function(self.parse_function()?)),
+            Token::LetKeyword | Token::ConstKeyword => self.parse_global_declaration(),
+            // `test` is not a keyword, it can still name variables
+            Token::Identifier("test") => {
+                self.lexer.next_token()?;
+                Ok(ast::Declaration::Test(self.parse_function()?))
+            }
+            _ => return_unexpected!(
+                span, "struct", "enum", "extern", "fn", "test", "let", "const"
+            ),
         }
     }
 
@@ -153,6 +325,90 @@ impl<'si, 'input> Parser<'si, 'input> {
         Ok(ast::Declaration::Struct(ast::Struct { name, fields, span }))
     }
 
+    fn parse_global_declaration(&mut self) -> ParsingResult<ast::Declaration> {
+        let begin = self.lexer.next_token()?;
+        let is_const = begin.inner == Token::ConstKeyword;
+        let name = self.parse_identifier()?;
+
+        let ty = if let Token::Colon = self.lexer.peek_token()?.inner {
+            self.lexer.next_token()?;
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        expect!(self.lexer; Token::Equal, "=");
+        let value = self.parse_expression()?;
+
+        let end_span = expect!(self.lexer; Token::SemiColon, ";");
+        let span = Span::merge(begin.span, end_span);
+
+        Ok(ast::Declaration::Global(ast::Global {
+            name,
+            ty,
+            value,
+            is_const,
+            span,
+        }))
+    }
+
+    fn parse_enum_declaration(&mut self) -> ParsingResult<ast::Declaration> {
+        let begin_span = expect!(self.lexer; Token::EnumKeyword, "enum");
+        let name = self.parse_identifier()?;
+        expect!(self.lexer; Token::LeftBracket, "{");
+
+        let variants =
+            self.parse_comma_sep(&Token::RightBracket, Parser::parse_enum_variant, true)?;
+
+        let end_span = expect!(self.lexer; Token::RightBracket, "}");
+        let span = Span::merge(begin_span, end_span);
+
+        Ok(ast::Declaration::Enum(ast::Enum {
+            name,
+            variants,
+            span,
+        }))
+    }
+
+    fn parse_enum_variant(&mut self) -> ParsingResult<ast::EnumVariant> {
+        let (name, span) =
+            accept!(self.lexer; Token::Identifier(id) => id.to_string(), "identifier");
+        let name = Spanned::new(name, span);
+
+        let payload = if let Token::LeftParenthesis = self.lexer.peek_token()?.inner {
+            self.lexer.next_token()?;
+            let payload =
+                self.parse_comma_sep(&Token::RightParenthesis, Parser::parse_type, false)?;
+            expect!(self.lexer; Token::RightParenthesis, ")");
+            payload
+        } else {
+            Vec::new()
+        };
+
+        let discriminant = if let Token::Equal = self.lexer.peek_token()?.inner {
+            self.lexer.next_token()?;
+            // the discriminant is an integer literal, maybe negative
+            let negative = if let Token::Minus = self.lexer.peek_token()?.inner {
+                Some(self.lexer.next_token()?.span)
+            } else {
+                None
+            };
+            let (value, span) =
+                accept!(self.lexer; Token::IntegerLiteral(i) => i, "integer literal");
+            Some(match negative {
+                Some(minus_span) => Spanned::new(-value, Span::merge(minus_span, span)),
+                None => Spanned::new(value, span),
+            })
+        } else {
+            None
+        };
+        Ok(ast::EnumVariant {
+            name,
+            payload,
+            discriminant,
+        })
+    }
+
     fn parse_field(&mut self) -> ParsingResult<(Spanned<String>, Spanned<ast::Type>)> {
         let (name, span) =
             accept!(self.lexer; Token::Identifier(id) => id.to_string(), "identifier");
@@ -213,7 +469,7 @@ impl<'si, 'input> Parser<'si, 'input> {
         Ok((result, is_vararg))
     }
 
-    fn parse_function_declaration(&mut self) -> ParsingResult<ast::Declaration> {
+    fn parse_function(&mut self) -> ParsingResult<ast::Function> {
         let begin_span = expect!(self.lexer; Token::FnKeyword, "fn");
         let name = self.parse_identifier()?;
         expect!(self.lexer; Token::LeftParenthesis, "(");
@@ -231,15 +487,15 @@ impl<'si, 'input> Parser<'si, 'input> {
         };
 
         let span = Span::merge(begin_span, end_span);
-        let body = self.parse_block_statement()?.inner;
+        let body = self.parse_block_statement()?;
 
-        Ok(ast::Declaration::Function(ast::Function {
+        Ok(ast::Function {
             return_ty,
             name,
             parameters,
             body,
             span,
-        }))
+        })
     }
 
     fn parse_parameter(&mut self) -> ParsingResult<(String, Spanned<ast::Type>)> {
@@ -255,14 +511,24 @@ impl<'si, 'input> Parser<'si, 'input> {
     }
 
     fn parse_block_statement(&mut self) -> ParsingResult<Spanned<ast::BlockStatement>> {
-        let mut block = Vec::new();
+        let mut block: Vec<Spanned<ast::Statement>> = Vec::new();
         let begin_span = expect!(self.lexer; Token::LeftBracket, "{");
         loop {
-            if let Token::RightBracket = self.lexer.peek_token()?.inner {
+            if let Token::RightBracket = self.peek_recover() {
                 break;
             }
 
-            block.push(self.parse_statement()?);
+            // the closing `}` was lost while recovering from an error
+            if !self.errors.is_empty() && self.at_declaration_boundary() {
+                let end_span = block.last().map_or(begin_span, |stmt| stmt.span);
+                let span = Span::merge(begin_span, end_span);
+                return Ok(Spanned::new(ast::BlockStatement::from_vec(block), span));
+            }
+
+            match self.parse_statement() {
+                Ok(statement) => block.push(statement),
+                Err(err) => self.recover_statement(err),
+            }
         }
         let end_span = expect!(self.lexer; Token::RightBracket, "}");
         let span = Span::merge(begin_span, end_span);
@@ -304,12 +570,19 @@ impl<'si, 'input> Parser<'si, 'input> {
             Ok(Spanned::new(ast::Statement::Empty, span))
         } else {
             let expr = self.parse_expression()?;
-            let end_span = expect!(self.lexer; Token::SemiColon, ";");
-            let span = Span::merge(expr.span, end_span);
-            Ok(Spanned::new(ast::Statement::Expression(expr), span))
+            self.parse_expression_statement_end(expr)
         }
     }
 
+    fn parse_expression_statement_end(
+        &mut self,
+        expr: Spanned<ast::Expression>,
+    ) -> ParsingResult<Spanned<ast::Statement>> {
+        let end_span = expect!(self.lexer; Token::SemiColon, ";");
+        let span = Span::merge(expr.span, end_span);
+        Ok(Spanned::new(ast::Statement::Expression(expr), span))
+    }
+
     fn parse_if_statement(&mut self) -> ParsingResult<Spanned<ast::Statement>> {
         let begin_span = expect!(self.lexer; Token::IfKeyword, "if");
         expect!(self.lexer; Token::LeftParenthesis, "(");
@@ -360,6 +633,9 @@ impl<'si, 'input> Parser<'si, 'input> {
     fn parse_for_statement(&mut self) -> ParsingResult<Spanned<ast::Statement>> {
         let begin_span = expect!(self.lexer; Token::ForKeyword, "for");
         expect!(self.lexer; Token::LeftParenthesis, "(");
+        if self.at_for_each_header() {
+            return self.parse_for_each_end(begin_span);
+        }
 
         let init = if let Token::LetKeyword = self.lexer.peek_token()?.inner {
             self.parse_let_statement()?
@@ -392,6 +668,42 @@ impl<'si, 'input> Parser<'si, 'input> {
         ))
     }
 
+    // a for each loop starts with `T x :`, which needs to look past the type
+    fn at_for_each_header(&mut self) -> bool {
+        let checkpoint = self.lexer.checkpoint();
+        let is_header = self.parse_type().is_ok()
+            && self.parse_identifier().is_ok()
+            && match self.lexer.peek_token() {
+                Ok(token) => token.inner == Token::Colon,
+                Err(_) => false,
+            };
+        self.lexer.rewind(checkpoint);
+        is_header
+    }
+
+    fn parse_for_each_end(&mut self, begin_span: Span) -> ParsingResult<Spanned<ast::Statement>> {
+        let ty = self.parse_type()?;
+        let (name, name_span) =
+            accept!(self.lexer; Token::Identifier(id) => id.to_string(), "identifier");
+        let name = Spanned::new(name, name_span);
+        expect!(self.lexer; Token::Colon, ":");
+        let array = self.parse_expression()?;
+        expect!(self.lexer; Token::RightParenthesis, ")");
+        let body = Box::new(self.parse_statement()?);
+
+        let span = Span::merge(begin_span, body.span);
+
+        Ok(Spanned::new(
+            ast::Statement::ForEach(ast::ForEachStatement {
+                ty,
+                name,
+                array,
+                body,
+            }),
+            span,
+        ))
+    }
+
     fn parse_return_statement(&mut self) -> ParsingResult<Spanned<ast::Statement>> {
         let begin_span = expect!(self.lexer; Token::ReturnKeyword, "return");
         let expr = if let Token::SemiColon = self.lexer.peek_token()?.inner {
@@ -473,6 +785,16 @@ impl<'si, 'input> Parser<'si, 'input> {
                 };
                 Ok(Spanned::new(expr, span))
             }
+            Token::Tilde => {
+                let span = self.lexer.next_token()?.span;
+                let sub = self.parse_unop_expression()?;
+                let span = Span::merge(span, sub.span);
+                let expr = ast::Expression::UnaryOperator {
+                    unop: ast::UnaryOperatorKind::BitNot,
+                    sub: Box::new(sub),
+                };
+                Ok(Spanned::new(expr, span))
+            }
             Token::Amp => {
                 let span = self.lexer.next_token()?.span;
                 let sub = self.parse_unop_expression()?;
@@ -493,10 +815,71 @@ impl<'si, 'input> Parser<'si, 'input> {
                 };
                 Ok(Spanned::new(expr, span))
             }
+            Token::NewKeyword => self.parse_new_expression(),
+            Token::DeleteKeyword => {
+                let span = self.lexer.next_token()?.span;
+                let sub = self.parse_unop_expression()?;
+                let span = Span::merge(span, sub.span);
+                Ok(Spanned::new(ast::Expression::Delete(Box::new(sub)), span))
+            }
             _ => self.parse_mid_expression(),
         }
     }
 
+    fn parse_new_expression(&mut self) -> ParsingResult<Spanned<ast::Expression>> {
+        let begin_span = expect!(self.lexer; Token::NewKeyword, "new");
+        if let Token::LeftSquare = self.lexer.peek_token()?.inner {
+            // `new [T; n]` allocates a dynamic array of any int size
+            self.lexer.next_token()?;
+            let ty = self.parse_type()?;
+            expect!(self.lexer; Token::SemiColon, ";");
+            let size = self.parse_expression()?;
+            let end_span = expect!(self.lexer; Token::RightSquare, "]");
+            let span = Span::merge(begin_span, end_span);
+            let expr = ast::Expression::NewArray {
+                ty,
+                size: Box::new(size),
+                rows: Vec::new(),
+            };
+            return Ok(Spanned::new(expr, span));
+        }
+        let ty = self.parse_type()?;
+
+        if let Token::LeftSquare = self.lexer.peek_token()?.inner {
+            // `new T[n][m]` allocates an array of `m` items arrays
+            let mut sizes = Vec::new();
+            let mut end_span = ty.span;
+            while let Token::LeftSquare = self.lexer.peek_token()?.inner {
+                self.lexer.next_token()?;
+                sizes.push(self.parse_expression()?);
+                end_span = expect!(self.lexer; Token::RightSquare, "]");
+            }
+            let span = Span::merge(begin_span, end_span);
+            let size = sizes.remove(0);
+            let expr = ast::Expression::NewArray {
+                ty,
+                size: Box::new(size),
+                rows: sizes,
+            };
+            return Ok(Spanned::new(expr, span));
+        }
+
+        let value = match (&ty.inner, &self.lexer.peek_token()?.inner) {
+            (&ast::Type::Identifier(ref name), &Token::LeftBracket) => {
+                Some(self.parse_struct_literal(name.clone(), ty.span)?)
+            }
+            _ => None,
+        };
+
+        let end_span = value.as_ref().map_or(ty.span, |value| value.span);
+        let span = Span::merge(begin_span, end_span);
+        let expr = ast::Expression::New {
+            ty,
+            value: value.map(Box::new),
+        };
+        Ok(Spanned::new(expr, span))
+    }
+
     fn parse_mid_expression(&mut self) -> ParsingResult<Spanned<ast::Expression>> {
         let mut sub = self.parse_leaf_expression()?;
         loop {
@@ -524,11 +907,25 @@ impl<'si, 'input> Parser<'si, 'input> {
                 Token::LeftSquare => {
                     self.lexer.next_token()?;
                     let index_expr = self.parse_expression()?;
+                    let end_expr = if let Token::DotDot = self.lexer.peek_token()?.inner {
+                        self.lexer.next_token()?;
+                        Some(self.parse_expression()?)
+                    } else {
+                        None
+                    };
                     let end_span = expect!(self.lexer; Token::RightSquare, "]");
                     let span = Span::merge(sub.span, end_span);
-                    let expr = ast::Expression::Subscript {
-                        array: Box::new(sub),
-                        index: Box::new(index_expr),
+                    let expr = if let Some(end_expr) = end_expr {
+                        ast::Expression::Slice {
+                            array: Box::new(sub),
+                            begin: Box::new(index_expr),
+                            end: Box::new(end_expr),
+                        }
+                    } else {
+                        ast::Expression::Subscript {
+                            array: Box::new(sub),
+                            index: Box::new(index_expr),
+                        }
                     };
                     sub = Spanned::new(expr, span);
                     continue;
@@ -582,12 +979,35 @@ impl<'si, 'input> Parser<'si, 'input> {
     }
 
     fn parse_leaf_expression(&mut self) -> ParsingResult<Spanned<ast::Expression>> {
+        let span = self.lexer.peek_token()?.span;
+        match self.lexer.peek_token()?.inner {
+            Token::IntegerLiteral(_)
+            | Token::SizedIntegerLiteral(..)
+            | Token::DoubleLiteral(_)
+            | Token::BooleanLiteral(_)
+            | Token::CharLiteral(_)
+            | Token::StringLiteral(_)
+            | Token::NullptrKeyword
+            | Token::SizeofKeyword
+            | Token::AlignofKeyword
+            | Token::MatchKeyword
+            | Token::LeftParenthesis
+            | Token::LeftSquare
+            | Token::Identifier(_) => {}
+            _ => return_unexpected!(span, "literal", "(", "[", "identifier"),
+        }
+
         let Spanned { inner: token, span } = self.lexer.next_token()?;
         match token {
             Token::IntegerLiteral(i) => {
                 let expr = ast::Expression::Literal(common::Literal::IntLiteral(i));
                 Ok(Spanned::new(expr, span))
             }
+            Token::SizedIntegerLiteral(i, kind) => {
+                let expr =
+                    ast::Expression::Literal(common::Literal::SizedIntLiteral(i as i64, kind));
+                Ok(Spanned::new(expr, span))
+            }
             Token::DoubleLiteral(d) => {
                 let expr = ast::Expression::Literal(common::Literal::DoubleLiteral(d));
                 Ok(Spanned::new(expr, span))
@@ -596,15 +1016,42 @@ impl<'si, 'input> Parser<'si, 'input> {
                 let expr = ast::Expression::Literal(common::Literal::BooleanLiteral(b));
                 Ok(Spanned::new(expr, span))
             }
+            Token::CharLiteral(c) => {
+                let expr = ast::Expression::Literal(common::Literal::CharLiteral(c));
+                Ok(Spanned::new(expr, span))
+            }
             Token::StringLiteral(s) => {
-                // TODO parse string (escape, etc)
-                let s = &s[1..s.len() - 1];
-                let s = convert_escape_string(s);
                 let sid = self.string_interner.intern(s);
                 let expr = ast::Expression::Literal(common::Literal::StringLiteral(sid));
                 Ok(Spanned::new(expr, span))
             }
             Token::NullptrKeyword => Ok(Spanned::new(ast::Expression::Nullptr, span)),
+            Token::MatchKeyword => {
+                expect!(self.lexer; Token::LeftParenthesis, "(");
+                let scrutinee = self.parse_expression()?;
+                expect!(self.lexer; Token::RightParenthesis, ")");
+                expect!(self.lexer; Token::LeftBracket, "{");
+                let arms =
+                    self.parse_comma_sep(&Token::RightBracket, Parser::parse_match_arm, false)?;
+                let end_span = expect!(self.lexer; Token::RightBracket, "}");
+                let expr = ast::Expression::Match {
+                    scrutinee: Box::new(scrutinee),
+                    arms,
+                };
+                Ok(Spanned::new(expr, Span::merge(span, end_span)))
+            }
+            Token::SizeofKeyword | Token::AlignofKeyword => {
+                expect!(self.lexer; Token::LeftParenthesis, "(");
+                let ty = self.parse_type()?;
+                let end_span = expect!(self.lexer; Token::RightParenthesis, ")");
+                let span = Span::merge(span, end_span);
+                let expr = if let Token::SizeofKeyword = token {
+                    ast::Expression::SizeOf(ty)
+                } else {
+                    ast::Expression::AlignOf(ty)
+                };
+                Ok(Spanned::new(expr, span))
+            }
             Token::LeftParenthesis => {
                 let sub_expr = self.parse_expression()?;
                 match self.lexer.peek_token()?.inner {
@@ -673,18 +1120,85 @@ impl<'si, 'input> Parser<'si, 'input> {
                 let name = id.to_string();
                 match self.lexer.peek_token()?.inner {
                     Token::LeftBracket => self.parse_struct_literal(name, span),
+                    Token::ColonColon => {
+                        self.lexer.next_token()?;
+                        let (variant, mut end_span) = accept!(
+                            self.lexer; Token::Identifier(id) => id.to_string(), "identifier"
+                        );
+                        let variant = Spanned::new(variant, end_span);
+                        // the payload of a tagged union variant
+                        let payload = if let Token::LeftParenthesis =
+                            self.lexer.peek_token()?.inner
+                        {
+                            self.lexer.next_token()?;
+                            let payload = self.parse_comma_sep(
+                                &Token::RightParenthesis,
+                                Parser::parse_expression,
+                                false,
+                            )?;
+                            end_span = expect!(self.lexer; Token::RightParenthesis, ")");
+                            payload
+                        } else {
+                            Vec::new()
+                        };
+                        let expr = ast::Expression::EnumVariant {
+                            enum_name: name,
+                            variant,
+                            payload,
+                        };
+                        Ok(Spanned::new(expr, Span::merge(span, end_span)))
+                    }
                     _ => {
                         let expr = ast::Expression::Identifier(name);
                         Ok(Spanned::new(expr, span))
                     }
                 }
             }
-            _ => {
-                return_unexpected!(span, "literal", "(", "[", "identifier");
-            }
+            _ => unreachable!(),
         }
     }
 
+    fn parse_match_arm(&mut self) -> ParsingResult<ast::MatchArm> {
+        let (name, span) =
+            accept!(self.lexer; Token::Identifier(id) => id.to_string(), "identifier", "_");
+        let pattern = if name == "_" {
+            Spanned::new(ast::Pattern::Wildcard, span)
+        } else {
+            expect!(self.lexer; Token::ColonColon, "::");
+            let (variant, mut end_span) =
+                accept!(self.lexer; Token::Identifier(id) => id.to_string(), "identifier");
+            let variant = Spanned::new(variant, end_span);
+            let bindings = if let Token::LeftParenthesis = self.lexer.peek_token()?.inner {
+                self.lexer.next_token()?;
+                let bindings = self.parse_comma_sep(
+                    &Token::RightParenthesis,
+                    Parser::parse_spanned_identifier,
+                    false,
+                )?;
+                end_span = expect!(self.lexer; Token::RightParenthesis, ")");
+                bindings
+            } else {
+                Vec::new()
+            };
+            let pattern = ast::Pattern::Variant {
+                enum_name: name,
+                variant,
+                bindings,
+            };
+            Spanned::new(pattern, Span::merge(span, end_span))
+        };
+
+        expect!(self.lexer; Token::FatArrow, "=>");
+        let value = self.parse_expression()?;
+        Ok(ast::MatchArm { pattern, value })
+    }
+
+    fn parse_spanned_identifier(&mut self) -> ParsingResult<Spanned<String>> {
+        let (id, span) =
+            accept!(self.lexer; Token::Identifier(id) => id.to_string(), "identifier");
+        Ok(Spanned::new(id, span))
+    }
+
     fn parse_struct_literal(
         &mut self,
         name: String,
@@ -744,27 +1258,34 @@ impl<'si, 'input> Parser<'si, 'input> {
     }
 }
 
-fn convert_escape_string(s: &str) -> String {
-    let mut iter = s.chars();
-    let mut output = String::new();
-
-    while let Some(c) = iter.next() {
-        if c != '\\' {
-            output.push(c);
-            continue;
+#[cfg(test)]
+mod tests {
+    use super::parse_repl_entry;
+    use ast::ReplEntry;
+    use errors::ParsingError;
+    use interner::Interner;
+    use lexer::Lexer;
+
+    fn repl_errors(input: &str) -> Vec<ParsingError> {
+        match parse_repl_entry(Lexer::new(input), &mut Interner::new()) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|error| error.inner).collect(),
         }
+    }
 
-        match iter.next() {
-            Some('b') => output.push('\u{0008}'),
-            Some('f') => output.push('\u{000C}'),
-            Some('n') => output.push('\n'),
-            Some('r') => output.push('\r'),
-            Some('t') => output.push('\t'),
-            Some('\'') => output.push('\''),
-            Some('\"') => output.push('\"'),
-            Some('\\') => output.push('\\'),
-            _ => panic!("Uncatched unterminated escape in string literal"),
-        };
+    #[test]
+    fn stray_closing_bracket() {
+        assert_eq!(repl_errors("}\n").len(), 1);
+        assert_eq!(repl_errors("} }").len(), 2);
+        assert_eq!(repl_errors("let x = 1; }\nx").len(), 1);
+    }
+
+    #[test]
+    fn final_expression() {
+        let entry = parse_repl_entry(Lexer::new("let x = 1;\nx + 1\n"), &mut Interner::new());
+        match entry {
+            Ok(ReplEntry::Statements(statements, Some(_))) => assert_eq!(statements.len(), 1),
+            _ => panic!("expected statements ending with an expression"),
+        }
     }
-    output
 }
@@ -0,0 +1,330 @@
+use std::ffi::CStr;
+use std::io::{self, BufRead, Write};
+
+use libc;
+
+use ast;
+use backend;
+use backend::execution_module::{JitValue, SessionEngine};
+use codemap::{CodeMap, Spanned};
+use diagnostics::{self, ErrorFormat};
+use errors::{self, UserError};
+use interner::Interner;
+use ir;
+use lexer::Lexer;
+use parser;
+use trans::{self, TranslationErrors};
+use trans::tables::Tables;
+use ty;
+
+const HELP: &str = "\
+Enter declarations (struct, extern, fn, const) to add them to the session, or statements
+followed by an optional expression to run them and display its value.
+The variables declared with let outside of a block are kept for the next entries.
+Commands: :help, :quit";
+
+/// An interactive session: every entry is compiled against the declarations accepted so far.
+pub struct Repl {
+    strings: Interner<String>,
+    tables: Tables,
+    program: ir::Program,
+    globals: Vec<(String, Vec<u8>)>, // the values of the variables, saved after each entry
+    engines: Vec<SessionEngine>, // the saved values can point into the previous entries
+    entry_count: usize,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let mut strings = Interner::new();
+        let mut tables = Tables::default();
+
+        let program = trans::translate_runtime(&mut strings, &mut tables);
+
+        Repl {
+            strings,
+            tables,
+            program,
+            globals: Vec::new(),
+            engines: Vec::new(),
+            entry_count: 0,
+        }
+    }
+
+    pub fn run(&mut self) {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+
+        loop {
+            let mut input = String::new();
+            let mut prompt = ">> ";
+            loop {
+                print!("{}", prompt);
+                io::stdout().flush().unwrap();
+
+                match lines.next() {
+                    Some(Ok(line)) => {
+                        input.push_str(&line);
+                        input.push('\n');
+                    }
+                    _ => return,
+                }
+
+                if is_complete(&input) {
+                    break;
+                }
+                prompt = ".. ";
+            }
+
+            match input.trim() {
+                "" => continue,
+                ":quit" | ":q" => return,
+                ":help" | ":h" => {
+                    println!("{}", HELP);
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let Err(errors) = self.eval(&input) {
+                let codemap = CodeMap::new("<repl>", &input);
+                for error in &errors {
+                    diagnostics::emit_error(&codemap, error, ErrorFormat::Human);
+                }
+            }
+        }
+    }
+
+    fn eval(&mut self, input: &str) -> Result<(), Vec<Spanned<UserError>>> {
+        let lexer = Lexer::new(input);
+        let entry =
+            parser::parse_repl_entry(lexer, &mut self.strings).map_err(errors::into_user_errors)?;
+        self.tables.set_source(input);
+
+        let result = match entry {
+            ast::ReplEntry::Declarations(declarations) => self.declare(declarations),
+            ast::ReplEntry::Statements(statements, final_expr) => {
+                self.execute(statements, final_expr)
+            }
+        };
+        result.map_err(errors::into_user_errors)
+    }
+
+    fn declare(&mut self, declarations: ast::Program) -> Result<(), TranslationErrors> {
+        // a failed entry must not leave half of its declarations behind, and the
+        // warnings are dropped since the next entries may use what looks unused
+        let snapshot = self.tables.clone();
+        let previous = Some(self.program.clone());
+        match trans::translate_program(&mut self.tables, declarations, previous, &mut Vec::new()) {
+            Ok(program) => {
+                let start = self.program.declarations.len();
+                for decl in &program.declarations[start..] {
+                    if let ir::Declaration::GlobalVariable(ref global) = *decl {
+                        self.add_global(global);
+                    }
+                }
+                self.program = program;
+                Ok(())
+            }
+            Err(errors) => {
+                self.tables = snapshot;
+                Err(errors)
+            }
+        }
+    }
+
+    fn execute(
+        &mut self,
+        statements: Vec<Spanned<ast::Statement>>,
+        final_expr: Option<Spanned<ast::Expression>>,
+    ) -> Result<(), TranslationErrors> {
+        let name = format!("___repl_{}", self.entry_count);
+        self.entry_count += 1;
+
+        // the variables declared by a failed entry are dropped
+        let snapshot = self.tables.clone();
+        let result =
+            trans::translate_repl_entry(&mut self.tables, name.clone(), statements, final_expr);
+        let (mut function, globals) = match result {
+            Ok(entry) => entry,
+            Err(errors) => {
+                self.tables = snapshot;
+                return Err(errors);
+            }
+        };
+        for global in globals {
+            self.add_global(&global);
+            self.program
+                .declarations
+                .push(ir::Declaration::GlobalVariable(global));
+        }
+
+        // the JIT can only hand back scalar values, other results are just evaluated
+        if !is_displayable(function.return_ty) {
+            if let Some(ir::Statement::Return(Some(expr))) = function.body.pop() {
+                function.body.push(ir::Statement::Expression(expr));
+            }
+            function.body.push(ir::Statement::Return(None));
+            function.return_ty = self.tables.types.get_void_ty();
+        }
+        let return_ty = function.return_ty;
+
+        let mut program = self.program.clone();
+        program.declarations.push(ir::Declaration::Function(function));
+        let result = backend::llvm_codegen_program(program, &self.strings, &self.tables.types)
+            .and_then(|llvm_exec| {
+                llvm_exec.verify_module();
+                llvm_exec.jit_session_function(&name, &mut self.globals, |value| {
+                    print_value(value, return_ty)
+                })
+            });
+        match result {
+            Ok(((), engine)) => self.engines.push(engine),
+            Err(err) => eprintln!("<repl>: {}", err.to_string_lossy()),
+        }
+        Ok(())
+    }
+
+    // a variable keeps its value from one entry to the next, constants are left to the module
+    fn add_global(&mut self, global: &ir::GlobalVariable) {
+        if !global.is_const {
+            self.globals.push((global.name.clone(), Vec::new()));
+        }
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Repl::new()
+    }
+}
+
+// an entry continues on the next line while a brace or a parenthesis is left open, the
+// ones in strings, chars and comments don't count
+fn is_complete(input: &str) -> bool {
+    let mut depth = 0;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let quote = c;
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        _ if c == quote => break,
+                        _ => {}
+                    }
+                }
+            }
+            '#' | '/' if c == '#' || chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut star = false;
+                loop {
+                    match chars.next() {
+                        Some('/') if star => break,
+                        Some(c) => star = c == '*',
+                        // the comment goes on, and so does the entry
+                        None => return false,
+                    }
+                }
+            }
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+fn is_displayable(ty: ty::Type) -> bool {
+    match *ty {
+        ty::TypeValue::Void
+        | ty::TypeValue::Int
+        | ty::TypeValue::SizedInt(_)
+        | ty::TypeValue::Char
+        | ty::TypeValue::Double
+        | ty::TypeValue::Boolean
+        | ty::TypeValue::String
+        | ty::TypeValue::Pointer(_) => true,
+        ty::TypeValue::Enum(ref e) => !e.has_payload(), // a tagged union is an aggregate
+        _ => false,
+    }
+}
+
+fn print_value(value: JitValue, ty: ty::Type) {
+    match (value, &*ty) {
+        (_, &ty::TypeValue::Void) | (JitValue::Void, _) => {}
+        (JitValue::Int(i), &ty::TypeValue::Boolean) => println!("{}: {}", i != 0, ty),
+        (JitValue::Int(i), &ty::TypeValue::Char) => println!("{:?}: {}", i as u8 as char, ty),
+        (JitValue::Int(i), &ty::TypeValue::Enum(e)) => match e.variant_name(i as i32) {
+            Some(variant) => println!("{}::{}: {}", e.name, variant, ty),
+            None => println!("{}: {}", i, ty),
+        },
+        // the JIT sign extends every integer
+        (JitValue::Int(i), &ty::TypeValue::SizedInt(kind)) if !kind.is_signed() => {
+            println!("{}: {}", kind.wrap(i) as u64, ty)
+        }
+        (JitValue::Int(i), _) => println!("{}: {}", i, ty),
+        (JitValue::Double(d), _) => println!("{}: {}", d, ty),
+        (JitValue::Pointer(p), &ty::TypeValue::String) => {
+            let s = unsafe { CStr::from_ptr(p as *const libc::c_char) };
+            println!("{:?}: {}", s.to_string_lossy(), ty)
+        }
+        (JitValue::Pointer(p), _) => println!("{:?}: {}", p, ty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_complete;
+
+    #[test]
+    fn complete_entries() {
+        assert!(is_complete(""));
+        assert!(is_complete("let x = 1;\n"));
+        assert!(is_complete("fn f() -> int { return (1 + 2); }\n"));
+        assert!(is_complete("a[0]\n"));
+        // a stray closing brace is left to the parser
+        assert!(is_complete("}\n"));
+    }
+
+    #[test]
+    fn open_entries() {
+        assert!(!is_complete("fn f() -> int {\n"));
+        assert!(!is_complete("printInt((1 + 2)\n"));
+        assert!(!is_complete("let a = [1, 2,\n"));
+        assert!(!is_complete("if (x) { while (y) {\n}\n"));
+    }
+
+    #[test]
+    fn brackets_in_literals() {
+        assert!(is_complete("printString(\"{\");\n"));
+        assert!(is_complete("printString(\"\\\"{\");\n"));
+        assert!(!is_complete("{ printString(\"}\");\n"));
+        assert!(is_complete("1 // (\n"));
+        assert!(is_complete("1 /* { */\n"));
+        assert!(is_complete("f(1, /* ) */ 2)\n"));
+        assert!(!is_complete("1 /* a comment\n"));
+        assert!(!is_complete("f( // )\n"));
+        assert!(is_complete("8 / 2\n"));
+        assert!(is_complete("# [\n"));
+    }
+
+    #[test]
+    fn brackets_in_chars() {
+        assert!(is_complete("let c = '(';\n"));
+        assert!(is_complete("let c = '\\'';\n"));
+        assert!(is_complete("printChar('}')\n"));
+        assert!(is_complete("\"'\" + '(' + \"\\\"\"\n"));
+        assert!(!is_complete("f('\\'', '\"'\n"));
+    }
+}
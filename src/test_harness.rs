@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::FromRawFd;
+
+use libc;
+use llvm;
+
+use backend::execution_module::ExecutionModule;
+use codemap::CodeMap;
+use trans::ASSERT_FAILED_FUNCTION;
+use trans::tables::TestFunction;
+
+// the exit code of a test stopped by a failed assert, the other failures are crashes
+const ASSERT_EXIT_CODE: i32 = 101;
+
+thread_local! {
+    // the name and the text of the tested file, to locate the failed asserts
+    static SOURCE: RefCell<(String, String)> = RefCell::new((String::new(), String::new()));
+}
+
+extern "C" fn assert_failed(message: *const libc::c_char, position: libc::c_int) {
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    // what the test printed before comes first
+    unsafe {
+        libc::fflush(::std::ptr::null_mut());
+    }
+    SOURCE.with(|source| {
+        let source = source.borrow();
+        let codemap = CodeMap::new(&source.0, &source.1);
+        let loc = codemap.bytepos_to_sourceloc(position as usize);
+        println!(
+            "{}:{}:{}: assertion failed: {}",
+            source.0, loc.line, loc.column, message
+        );
+    });
+    let _ = io::stdout().flush();
+    unsafe { libc::exit(ASSERT_EXIT_CODE) }
+}
+
+/// Run every test of the module in its own process, print a report and return the number
+/// of failures.
+///
+/// A test passes if it returns. The output of a failed test is printed after the report
+/// line, with the location of the failed assert.
+pub fn run_tests(
+    module: &ExecutionModule,
+    tests: &[TestFunction],
+    input_name: &str,
+    input: &str,
+) -> usize {
+    SOURCE.with(|source| *source.borrow_mut() = (input_name.to_string(), input.to_string()));
+    let symbol = CString::new(ASSERT_FAILED_FUNCTION).unwrap();
+    unsafe {
+        llvm::support::LLVMAddSymbol(symbol.as_ptr(), assert_failed as *mut libc::c_void);
+    }
+
+    let mut failures = 0;
+    for test in tests {
+        match run_test(module, test) {
+            Ok(None) => println!("test {} ... ok", test.name),
+            Ok(Some((reason, output))) => {
+                failures += 1;
+                println!("test {} ... FAILED ({})", test.name, reason);
+                for line in output.lines() {
+                    println!("    {}", line);
+                }
+            }
+            Err(err) => {
+                failures += 1;
+                println!("test {} ... FAILED (can't run the test: {})", test.name, err);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} tests | {} passed | {} failed",
+        tests.len(),
+        tests.len() - failures,
+        failures
+    );
+    failures
+}
+
+// a crash in a test must not stop the others, so each one runs in a child process whose
+// output is captured, None if the test passed
+fn run_test(
+    module: &ExecutionModule,
+    test: &TestFunction,
+) -> io::Result<Option<(String, String)>> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (read_fd, write_fd) = (fds[0], fds[1]);
+
+    let _ = io::stdout().flush();
+    unsafe {
+        libc::fflush(::std::ptr::null_mut());
+    }
+
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if pid == 0 {
+        unsafe {
+            libc::close(read_fd);
+            libc::dup2(write_fd, libc::STDOUT_FILENO);
+            libc::dup2(write_fd, libc::STDERR_FILENO);
+            libc::close(write_fd);
+        }
+        let code = match module.jit_function(&test.function_name, |_| ()) {
+            Ok(()) => 0,
+            Err(err) => {
+                println!("jit error: {}", err.to_string_lossy());
+                1
+            }
+        };
+        let _ = io::stdout().flush();
+        unsafe { libc::exit(code) }
+    }
+
+    unsafe {
+        libc::close(write_fd);
+    }
+    let mut output = Vec::new();
+    unsafe { File::from_raw_fd(read_fd) }.read_to_end(&mut output)?;
+    let output = String::from_utf8_lossy(&output).into_owned();
+
+    let mut status = 0;
+    if unsafe { libc::waitpid(pid, &mut status, 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let (exit_code, signal) = unsafe {
+        if libc::WIFEXITED(status) {
+            (Some(libc::WEXITSTATUS(status)), None)
+        } else if libc::WIFSIGNALED(status) {
+            (None, Some(libc::WTERMSIG(status)))
+        } else {
+            (None, None)
+        }
+    };
+    let reason = match (exit_code, signal) {
+        (Some(0), _) => return Ok(None),
+        (Some(ASSERT_EXIT_CODE), _) => "assertion failed".to_string(),
+        (Some(code), _) => format!("exited with code {}", code),
+        (None, Some(signal)) => format!("killed by signal {}", signal),
+        (None, None) => "stopped".to_string(),
+    };
+    Ok(Some((reason, output)))
+}
@@ -0,0 +1,638 @@
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::FromRawFd;
+use std::os::unix::process::ExitStatusExt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libc;
+
+use backend;
+use codemap::{CodeMap, Span, Spanned};
+use diagnostics::{LintLevel, LintLevels};
+use errors::{self, UserError};
+use formatter;
+use interner::Interner;
+use interpreter;
+use ir;
+use lexer::Lexer;
+use lsp::analysis;
+use parser;
+use trans;
+use trans::tables::Tables;
+
+// a program running longer than this is considered stuck
+const TIMEOUT_SECS: u64 = 10;
+const MAX_DIFF_LINES: usize = 20;
+// the programs run on the stack of the thread that forked them, as large as a main thread's
+const STACK_SIZE: usize = 8 << 20;
+
+// the types live in a global context behind a lock, a child forked while another thread
+// holds it would block on it: the compilations and the forks take turns
+static COMPILATION: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+    /// The backend running the good tests, "jit" or "interpreter".
+    pub backend: String,
+    pub jobs: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Test {
+    name: String,
+    path: PathBuf,
+}
+
+/// Run every test found in the directory, print a report and return the number of failures.
+///
+/// A `.yal` file with a `.output` file must run and print it, reading the `.input` file if
+/// there is one, then fail with the runtime error of its `.stderr` file if there is one.
+/// Any other `.yal` file must fail to compile, with the errors given by its
+/// `// error: Name` annotations or its `.error` file.
+///
+/// The programs are compiled in the process, then each one runs in a forked child whose
+/// output is captured, so that a crash or an `exit` only stops its own test.
+pub fn run_tests(dir: &Path, options: &TestOptions) -> io::Result<usize> {
+    let mut tests = Vec::new();
+    discover(dir, dir, &mut tests)?;
+    let tests = Arc::new(tests);
+    let next = Arc::new(AtomicUsize::new(0));
+
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..options.jobs.max(1) {
+        let tests = tests.clone();
+        let next = next.clone();
+        let sender = sender.clone();
+        let options = options.clone();
+        thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                if index >= tests.len() {
+                    break;
+                }
+                let result = run_test(&tests[index].path, &options);
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            })?;
+    }
+    drop(sender);
+
+    let mut failures = Vec::new();
+    for (index, result) in receiver {
+        let status = if result.is_ok() { "ok" } else { "FAILED" };
+        println!("test {} ... {}", tests[index].name, status);
+        if let Err(reason) = result {
+            failures.push((index, reason));
+        }
+    }
+
+    failures.sort();
+    if !failures.is_empty() {
+        println!();
+        println!("failures:");
+        for &(index, ref reason) in &failures {
+            println!();
+            println!("---- {} ----", tests[index].name);
+            println!("{}", reason.trim_end());
+        }
+    }
+
+    println!();
+    println!(
+        "{} tests | {} passed | {} failed",
+        tests.len(),
+        tests.len() - failures.len(),
+        failures.len()
+    );
+    Ok(failures.len())
+}
+
+fn discover(dir: &Path, root: &Path, tests: &mut Vec<Test>) -> io::Result<()> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            discover(&path, root, tests)?;
+        } else if path.extension().is_some_and(|ext| ext == "yal") {
+            let name = path.strip_prefix(root)
+                .unwrap_or(&path)
+                .with_extension("")
+                .display()
+                .to_string();
+            tests.push(Test { name, path });
+        }
+    }
+    Ok(())
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    let mut buffer = String::new();
+    fs::File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut buffer))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(buffer)
+}
+
+fn run_test(path: &Path, options: &TestOptions) -> Result<(), String> {
+    let input = read_file(path)?;
+    if path.with_extension("output").is_file() {
+        run_good_test(path, &input, options)
+    } else {
+        run_bad_test(path, &input)
+    }
+}
+
+fn run_good_test(path: &Path, input: &str, options: &TestOptions) -> Result<(), String> {
+    let expected_output = read_file(&path.with_extension("output"))?;
+
+    let (child, warnings) = {
+        let _lock = lock_compilation();
+        let compiled = compile(input)?;
+        let child = spawn_program(path, &compiled, &options.backend)
+            .map_err(|err| format!("can't run the program: {}", err))?;
+        (child, compiled.warnings)
+    };
+    let run = wait_program(child).map_err(|err| format!("can't run the program: {}", err))?;
+    let status = match run.status {
+        Some(status) => status,
+        None => return Err(format!("timed out after {} seconds", TIMEOUT_SECS)),
+    };
+
+    let mut reasons = Vec::new();
+    if let Ok(expected_stderr) = read_file(&path.with_extension("stderr")) {
+        // the program must stop with this runtime error
+        let stderr = run.stderr.trim_end();
+        if status.success() {
+            reasons.push("exited successfully instead of failing".to_string());
+        }
+        if !stderr.contains(expected_stderr.trim_end()) {
+            reasons.push(format!(
+                "expected the error {:?}, got stderr:\n{}",
+                expected_stderr.trim_end(),
+                stderr
+            ));
+        }
+    } else {
+        if !status.success() {
+            reasons.push(exit_reason(status));
+        }
+        if !run.stderr.is_empty() {
+            reasons.push(format!("stderr:\n{}", run.stderr.trim_end()));
+        }
+    }
+    if run.stdout != expected_output {
+        reasons.push(format!(
+            "wrong output (- expected, + actual):\n{}",
+            diff(&expected_output, &run.stdout)
+        ));
+    }
+    if let Ok(expected_warnings) = read_file(&path.with_extension("warning")) {
+        let expected_warnings: Vec<_> = expected_warnings.split_whitespace().collect();
+        if expected_warnings != warnings {
+            reasons.push(format!(
+                "expected the warnings [{}], got [{}]",
+                expected_warnings.join(" "),
+                warnings.join(" ")
+            ));
+        }
+    }
+    if let Err(reason) = check_formatting(input, &[]) {
+        reasons.push(reason);
+    }
+
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(reasons.join("\n"))
+    }
+}
+
+fn exit_reason(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exited with code {}", code),
+        None => "killed by a signal".to_string(),
+    }
+}
+
+// formatting must be stable and keep the errors of the program, none for a good test
+fn check_formatting(input: &str, codes: &[&str]) -> Result<(), String> {
+    let once = formatter::format_program(input)
+        .map_err(|_| "the formatter can't parse the program".to_string())?;
+    let twice = formatter::format_program(&once)
+        .map_err(|_| "the formatter can't parse its own output".to_string())?;
+    if once != twice {
+        return Err(format!(
+            "formatting is not idempotent (- once, + twice):\n{}",
+            diff(&once, &twice)
+        ));
+    }
+
+    let errors = lock_compilation_then(|| analysis::analyze(&once)).errors;
+    let formatted_codes: Vec<_> = errors.iter().map(|e| e.inner.code()).collect();
+    if codes.is_empty() && !formatted_codes.is_empty() {
+        return Err("the formatted program does not compile".to_string());
+    }
+    if formatted_codes != codes {
+        return Err(format!(
+            "the formatted program has the errors [{}] instead of [{}]",
+            formatted_codes.join(" "),
+            codes.join(" ")
+        ));
+    }
+    Ok(())
+}
+
+fn lock_compilation() -> MutexGuard<'static, ()> {
+    // a panic while compiling is reported by the test that made it
+    COMPILATION.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn lock_compilation_then<F: FnOnce() -> R, R>(f: F) -> R {
+    let _lock = lock_compilation();
+    f()
+}
+
+struct Compiled {
+    program: ir::Program,
+    strings: Interner<String>,
+    tables: Tables,
+    warnings: Vec<&'static str>, // the codes of the warnings, as the compiler reports them
+}
+
+// the passes of the compiler, with its errors formatted for the report
+fn compile(input: &str) -> Result<Compiled, String> {
+    let mut strings = Interner::new();
+    let mut tables = Tables::default();
+
+    let runtime = trans::translate_runtime(&mut strings, &mut tables);
+
+    let mut warnings = Vec::new();
+    let result = parser::parse_program(Lexer::new(input), &mut strings)
+        .map_err(errors::into_user_errors)
+        .and_then(|ast| {
+            tables.set_source(input);
+            trans::translate_program(&mut tables, ast, Some(runtime), &mut warnings)
+                .map_err(errors::into_user_errors)
+        })
+        .and_then(|program| {
+            trans::check_if_main_declaration(&tables, &program).map_err(|err| vec![err.into()])?;
+            Ok(program)
+        });
+
+    let program = match result {
+        Ok(program) => program,
+        Err(errors) => {
+            let codemap = CodeMap::new("", input);
+            let errors: Vec<_> = errors
+                .iter()
+                .map(|error| {
+                    format!(
+                        "line {}: error[{}]: {}",
+                        error_line(&codemap, input, error.span).unwrap_or(0),
+                        error.inner.code(),
+                        error.inner
+                    )
+                })
+                .collect();
+            return Err(format!("compilation failed:\n{}", errors.join("\n")));
+        }
+    };
+
+    let levels = LintLevels::default();
+    let warnings = warnings
+        .iter()
+        .filter(|warning| levels.level(warning.inner.lint()) != LintLevel::Allow)
+        .map(|warning| warning.inner.code())
+        .collect();
+    Ok(Compiled {
+        program,
+        strings,
+        tables,
+        warnings,
+    })
+}
+
+struct Child {
+    pid: libc::pid_t,
+    stdout: File,
+    stderr: File,
+}
+
+// the child reads the `.input` file and writes to pipes, it runs the program like the
+// compiler does and exits with its exit code
+//
+// the process is multithreaded and the child only gets the thread that forked it, so a lock
+// held by another thread at the fork stays locked in the child. The caller must hold
+// COMPILATION, then what the child locks is free:
+// - the type context, that the other workers only use while holding COMPILATION
+// - the allocator, that the C library resets in the child
+// - the output, the programs print through the C library and the runtime errors go to the
+//   Rust stderr, while the other threads only write to the Rust stdout
+fn spawn_program(path: &Path, compiled: &Compiled, backend: &str) -> io::Result<Child> {
+    let input_path = path.with_extension("input");
+    let input_path = if input_path.is_file() {
+        input_path
+    } else {
+        PathBuf::from("/dev/null")
+    };
+    let input_path = CString::new(input_path.as_os_str().as_bytes()).unwrap();
+
+    let (stdout_read, stdout_write) = pipe()?;
+    let (stderr_read, stderr_write) = pipe()?;
+
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if pid == 0 {
+        unsafe {
+            let input = libc::open(input_path.as_ptr(), libc::O_RDONLY);
+            libc::dup2(input, libc::STDIN_FILENO);
+            libc::dup2(stdout_write, libc::STDOUT_FILENO);
+            libc::dup2(stderr_write, libc::STDERR_FILENO);
+            for &fd in &[input, stdout_read, stdout_write, stderr_read, stderr_write] {
+                libc::close(fd);
+            }
+        }
+        let code = panic::catch_unwind(AssertUnwindSafe(|| run_program(path, compiled, backend)));
+        unsafe { libc::exit(code.unwrap_or(101)) }
+    }
+
+    unsafe {
+        libc::close(stdout_write);
+        libc::close(stderr_write);
+    }
+    Ok(Child {
+        pid,
+        stdout: unsafe { File::from_raw_fd(stdout_read) },
+        stderr: unsafe { File::from_raw_fd(stderr_read) },
+    })
+}
+
+fn pipe() -> io::Result<(libc::c_int, libc::c_int)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((fds[0], fds[1]))
+}
+
+fn run_program(path: &Path, compiled: &Compiled, backend: &str) -> i32 {
+    if backend == "interpreter" {
+        let result = interpreter::interpret_program(
+            &compiled.program,
+            &compiled.strings,
+            &compiled.tables.types,
+        );
+        return match result {
+            Ok(code) => code,
+            Err(err) => {
+                eprintln!("{}: runtime error: {}", path.display(), err);
+                1
+            }
+        };
+    }
+
+    let result = backend::llvm_codegen_program(
+        compiled.program.clone(),
+        &compiled.strings,
+        &compiled.tables.types,
+    ).and_then(|mut module| {
+        module.verify_module();
+        module.optimize_required();
+        module.jit_main()
+    });
+    result.unwrap_or_else(|err| {
+        eprintln!("{}: {}", path.display(), err.to_string_lossy());
+        1
+    })
+}
+
+struct Run {
+    stdout: String,
+    stderr: String,
+    status: Option<ExitStatus>, // None if the program timed out
+}
+
+fn wait_program(child: Child) -> io::Result<Run> {
+    // the pipes are served by threads so that a chatty program can't block
+    let stdout = read_in_thread(child.stdout);
+    let stderr = read_in_thread(child.stderr);
+
+    let deadline = Instant::now() + Duration::from_secs(TIMEOUT_SECS);
+    let mut status = 0;
+    let status = loop {
+        match unsafe { libc::waitpid(child.pid, &mut status, libc::WNOHANG) } {
+            0 => {}
+            pid if pid < 0 => return Err(io::Error::last_os_error()),
+            _ => break Some(ExitStatus::from_raw(status)),
+        }
+        if Instant::now() >= deadline {
+            unsafe {
+                libc::kill(child.pid, libc::SIGKILL);
+                libc::waitpid(child.pid, &mut status, 0);
+            }
+            break None;
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    Ok(Run {
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        status,
+    })
+}
+
+fn read_in_thread<R: Read + Send + 'static>(mut reader: R) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = reader.read_to_end(&mut buffer);
+        String::from_utf8_lossy(&buffer).into_owned()
+    })
+}
+
+fn run_bad_test(path: &Path, input: &str) -> Result<(), String> {
+    let analysis = lock_compilation_then(|| analysis::analyze(input));
+    if analysis.errors.is_empty() {
+        return Err("compiled without errors".to_string());
+    }
+    let codes: Vec<_> = analysis.errors.iter().map(|e| e.inner.code()).collect();
+
+    let mut reasons = Vec::new();
+    let annotations = error_annotations(input);
+    if !annotations.is_empty() {
+        if let Err(reason) = check_annotations(input, &analysis.errors, annotations) {
+            reasons.push(reason);
+        }
+    } else if let Ok(expected) = read_file(&path.with_extension("error")) {
+        let expected: Vec<_> = expected.split_whitespace().collect();
+        if expected != codes {
+            reasons.push(format!(
+                "expected the errors [{}], got [{}]",
+                expected.join(" "),
+                codes.join(" ")
+            ));
+        }
+    }
+
+    // the programs with syntax errors can't be formatted
+    if formatter::format_program(input).is_ok() {
+        if let Err(reason) = check_formatting(input, &codes) {
+            reasons.push(reason);
+        }
+    }
+
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(reasons.join("\n"))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Annotation {
+    line: usize,
+    name: String,
+}
+
+// `// error: Name` expects an error on its line, several names are separated by commas
+fn error_annotations(input: &str) -> Vec<Annotation> {
+    const MARKER: &str = "// error:";
+
+    let mut annotations = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        if let Some(pos) = line.find(MARKER) {
+            for name in line[pos + MARKER.len()..].split(',') {
+                annotations.push(Annotation {
+                    line: index + 1,
+                    name: name.trim().to_string(),
+                });
+            }
+        }
+    }
+    annotations
+}
+
+// the name of an error is its code, its kind or the name of its variant
+fn annotation_matches(name: &str, error: &UserError) -> bool {
+    let kind = error.kind();
+    let variant: String = kind[kind.find('.').map_or(0, |pos| pos + 1)..]
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        })
+        .collect();
+    name == error.code() || name == kind || name == variant
+}
+
+fn check_annotations(
+    input: &str,
+    errors: &[Spanned<UserError>],
+    mut annotations: Vec<Annotation>,
+) -> Result<(), String> {
+    let codemap = CodeMap::new("", input);
+    let mut reasons = Vec::new();
+
+    for error in errors {
+        // the errors without location, like a missing main, can be annotated anywhere
+        let line = error_line(&codemap, input, error.span);
+
+        let found = annotations.iter().position(|annotation| {
+            line.is_none_or(|line| line == annotation.line)
+                && annotation_matches(&annotation.name, &error.inner)
+        });
+        match found {
+            Some(index) => {
+                annotations.remove(index);
+            }
+            None => reasons.push(format!(
+                "line {}: unexpected error[{}]: {}",
+                line.unwrap_or(0),
+                error.inner.code(),
+                error.inner
+            )),
+        }
+    }
+
+    for annotation in annotations {
+        reasons.push(format!(
+            "line {}: expected error {} was not reported",
+            annotation.line, annotation.name
+        ));
+    }
+
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(reasons.join("\n"))
+    }
+}
+
+// None for the errors without location
+fn error_line(codemap: &CodeMap, input: &str, span: Span) -> Option<usize> {
+    if span.end > input.len() {
+        None
+    } else {
+        Some(codemap.bytepos_to_sourceloc(span.start).line)
+    }
+}
+
+// a line diff from the longest common subsequence, showing only the changed lines
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+    if expected == actual {
+        return "the outputs only differ by their last newline".to_string();
+    }
+
+    let mut lcs = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            lines.push(format!("{:>5} + {}", j + 1, actual[j]));
+            j += 1;
+        } else {
+            lines.push(format!("{:>5} - {}", i + 1, expected[i]));
+            i += 1;
+        }
+    }
+
+    if lines.len() > MAX_DIFF_LINES {
+        let more = lines.len() - MAX_DIFF_LINES;
+        lines.truncate(MAX_DIFF_LINES);
+        lines.push(format!("      ... {} more changed lines", more));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,357 @@
+use ast;
+use ty;
+use ir;
+use common::Literal;
+use codemap::{Span, Spanned};
+use errors::TranslationError;
+use trans::{typeck, utils, TranslationResult};
+use trans::tables::Tables;
+
+/// Translate the initializer of a global, checked against the type it is expected to have.
+pub(super) fn translate_constant(
+    tables: &mut Tables,
+    expression: Spanned<ast::Expression>,
+    expected: Option<ty::Type>,
+) -> TranslationResult<(ty::Type, ir::Constant)> {
+    let Spanned {
+        inner: expression,
+        span: expr_span,
+    } = expression;
+
+    let (ty, constant) = match expression {
+        ast::Expression::Literal(lit) => {
+            let lit = utils::check_literal(&tables.types, lit, false, expr_span)?;
+            (lit.get_type(&tables.types), ir::Constant::Literal(lit))
+        }
+        ast::Expression::Parenthesis(sub) => return translate_constant(tables, *sub, expected),
+        ast::Expression::Identifier(id) => {
+            // only a const has a value before the program runs, a variable can be assigned
+            let global = match tables.globals.lookup_variable(&id).cloned() {
+                Some(global) if global.is_const => global,
+                _ => return error!(TranslationError::NonConstantInitializer, expr_span),
+            };
+            if let Some(ref mut index) = tables.index {
+                index.add_reference(expr_span, global.span);
+            }
+            // the error in the value of the const was already reported
+            let constant = tables
+                .globals
+                .lookup_constant(&id)
+                .cloned()
+                .unwrap_or(ir::Constant::Null);
+            (global.ty, constant)
+        }
+        ast::Expression::UnaryOperator { unop, sub } => {
+            // the minus is part of a number literal, `-2147483648` is an `int`
+            let negated = match (unop, &sub.inner) {
+                (ast::UnaryOperatorKind::Minus, &ast::Expression::Literal(lit)) => match lit {
+                    Literal::IntLiteral(_)
+                    | Literal::SizedIntLiteral(..)
+                    | Literal::DoubleLiteral(_) => Some(lit),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(lit) = negated {
+                let lit = utils::check_literal(&tables.types, lit, true, expr_span)?;
+                (lit.get_type(&tables.types), ir::Constant::Literal(lit))
+            } else {
+                let (sub_ty, sub) = translate_constant(tables, *sub, None)?;
+                let (ty, op) = if let Some(res) = typeck::unop_typeck(&tables.types, unop, sub_ty)
+                {
+                    res
+                } else {
+                    return error!(TranslationError::UnOpUndefined(unop, sub_ty), expr_span);
+                };
+                (ty, folded(fold_unop(op, &sub), expr_span)?)
+            }
+        }
+        ast::Expression::BinaryOperator { binop, lhs, rhs } => {
+            let (lhs_ty, lhs) = translate_constant(tables, *lhs, None)?;
+            let (rhs_ty, rhs) = translate_constant(tables, *rhs, None)?;
+            let (ty, op) =
+                if let Some(res) = typeck::binop_typeck(&tables.types, binop, lhs_ty, rhs_ty) {
+                    res
+                } else {
+                    return error!(
+                        TranslationError::BinOpUndefined(binop, lhs_ty, rhs_ty),
+                        expr_span
+                    );
+                };
+            (ty, folded(fold_binop(op, &lhs, &rhs), expr_span)?)
+        }
+        ast::Expression::LazyOperator { lazyop, lhs, rhs } => {
+            let (lhs_ty, lhs) = translate_constant(tables, *lhs, None)?;
+            let (rhs_ty, rhs) = translate_constant(tables, *rhs, None)?;
+            let bool_ty = tables.types.get_boolean_ty();
+            if lhs_ty != bool_ty || rhs_ty != bool_ty {
+                return error!(
+                    TranslationError::LazyOpUndefined(lazyop, lhs_ty, rhs_ty),
+                    expr_span
+                );
+            }
+            let value = match (&lhs, &rhs) {
+                (
+                    &ir::Constant::Literal(Literal::BooleanLiteral(a)),
+                    &ir::Constant::Literal(Literal::BooleanLiteral(b)),
+                ) => Some(match lazyop {
+                    ast::LazyOperatorKind::LogicalAnd => Literal::BooleanLiteral(a && b),
+                    ast::LazyOperatorKind::LogicalOr => Literal::BooleanLiteral(a || b),
+                }),
+                _ => None,
+            };
+            (bool_ty, folded(value, expr_span)?)
+        }
+        ast::Expression::EnumVariant {
+            enum_name,
+            variant,
+            payload,
+        } => {
+            let (ty, variant) =
+                utils::translate_enum_variant(tables, enum_name, variant, expr_span)?;
+            // a tagged union is built at runtime
+            match *ty {
+                ty::TypeValue::Enum(e) if !e.has_payload() && payload.is_empty() => {}
+                _ => return error!(TranslationError::NonConstantInitializer, expr_span),
+            }
+            let lit = Literal::IntLiteral(i64::from(variant.discriminant));
+            (ty, ir::Constant::Literal(lit))
+        }
+        ast::Expression::Nullptr => {
+            // like the auto cast of `*void`, the null pointer takes the expected pointer type
+            let ty = match expected {
+                Some(ty) => match *ty {
+                    ty::TypeValue::Pointer(_) => ty,
+                    _ => null_ty(tables),
+                },
+                None => null_ty(tables),
+            };
+            (ty, ir::Constant::Null)
+        }
+        ast::Expression::TupleLiteral { values } => {
+            let expected_types = match expected {
+                Some(ty) => match *ty {
+                    ty::TypeValue::Tuple(ref types) if types.len() == values.len() => {
+                        types.iter().map(|&ty| Some(ty)).collect()
+                    }
+                    _ => vec![None; values.len()],
+                },
+                None => vec![None; values.len()],
+            };
+
+            let mut types = Vec::with_capacity(values.len());
+            let mut constants = Vec::with_capacity(values.len());
+            for (value, expected) in values.into_iter().zip(expected_types) {
+                let (ty, constant) = translate_constant(tables, value, expected)?;
+                types.push(ty);
+                constants.push(constant);
+            }
+            (
+                tables.types.tuple_of(types),
+                ir::Constant::Aggregate(constants),
+            )
+        }
+        ast::Expression::ArrayLiteral { values } => {
+            let array_size = values.len();
+            let mut sub_ty = expected.and_then(array_item_ty);
+            let mut constants = Vec::with_capacity(values.len());
+            for value in values {
+                let (ty, constant) = translate_constant(tables, value, sub_ty)?;
+                // the first item gives its type to the others
+                sub_ty = sub_ty.or(Some(ty));
+                constants.push(constant);
+            }
+            let sub_ty = sub_ty.unwrap_or_else(|| tables.types.get_error_ty());
+            (
+                tables.types.array_of(sub_ty, array_size),
+                ir::Constant::Aggregate(constants),
+            )
+        }
+        ast::Expression::ArrayFillLiteral { value, size } => {
+            let sub_ty = expected.and_then(array_item_ty);
+            let (sub_ty, constant) = translate_constant(tables, *value, sub_ty)?;
+            (
+                tables.types.array_of(sub_ty, size),
+                ir::Constant::Aggregate(vec![constant; size]),
+            )
+        }
+        ast::Expression::StructLiteral {
+            struct_name,
+            fields,
+        } => translate_struct_constant(tables, struct_name, fields, expr_span)?,
+        _ => return error!(TranslationError::NonConstantInitializer, expr_span),
+    };
+
+    if let Some(expected) = expected {
+        utils::check_eq_types(ty, expected, expr_span)?;
+    }
+    if let Some(ref mut index) = tables.index {
+        index.add_expression(expr_span, ty);
+    }
+    Ok((ty, constant))
+}
+
+fn translate_struct_constant(
+    tables: &mut Tables,
+    struct_name: String,
+    fields: Vec<(Spanned<String>, Spanned<ast::Expression>)>,
+    expr_span: Span,
+) -> TranslationResult<(ty::Type, ir::Constant)> {
+    let ty = if let Some(ty) = tables.types.lookup_type(&struct_name) {
+        ty
+    } else {
+        return error!(TranslationError::UndefinedType(struct_name), expr_span);
+    };
+
+    if let Some(ref mut index) = tables.index {
+        if let Some(&definition) = index.types.get(&struct_name) {
+            index.add_reference(expr_span, definition);
+        }
+    }
+
+    let struct_fields = if let ty::TypeValue::Struct(s) = *ty {
+        s.fields.clone()
+    } else {
+        return error!(TranslationError::NonStructType(struct_name), expr_span);
+    };
+
+    // the fields are stored in the order of the struct declaration
+    let mut constants = vec![None; struct_fields.len()];
+    for (field_name, field_expr) in fields {
+        let index = if let Some(index) = struct_fields
+            .iter()
+            .position(|&(ref name, _)| *name == field_name.inner)
+        {
+            index
+        } else {
+            return error!(
+                TranslationError::UndefinedField(field_name.inner),
+                field_name.span
+            );
+        };
+        if constants[index].is_some() {
+            return error!(
+                TranslationError::FieldAreadySet(field_name.inner),
+                field_name.span
+            );
+        }
+
+        let field_ty = struct_fields[index].1;
+        let (_, constant) = translate_constant(tables, field_expr, Some(field_ty))?;
+        constants[index] = Some(constant);
+    }
+
+    if let Some(constants) = constants.into_iter().collect() {
+        Ok((ty, ir::Constant::Aggregate(constants)))
+    } else {
+        error!(TranslationError::FieldNotSet, expr_span)
+    }
+}
+
+fn null_ty(tables: &Tables) -> ty::Type {
+    let void_ty = tables.types.get_void_ty();
+    tables.types.pointer_of(void_ty)
+}
+
+fn array_item_ty(ty: ty::Type) -> Option<ty::Type> {
+    if let ty::TypeValue::Array(sub, _) = *ty {
+        Some(sub)
+    } else {
+        None
+    }
+}
+
+// an operator without a value before the program runs, like a string concatenation or a
+// division by zero, makes the initializer non constant
+fn folded(value: Option<Literal>, span: Span) -> TranslationResult<ir::Constant> {
+    match value {
+        Some(lit) => Ok(ir::Constant::Literal(lit)),
+        None => error!(TranslationError::NonConstantInitializer, span),
+    }
+}
+
+// the integers are computed on 64 bits then wrapped to their kind, like the interpreter does
+fn int_value(constant: &ir::Constant) -> Option<(i64, ty::IntKind)> {
+    match *constant {
+        ir::Constant::Literal(Literal::IntLiteral(i)) => Some((i, ty::IntKind::I32)),
+        ir::Constant::Literal(Literal::SizedIntLiteral(i, kind)) => Some((i, kind)),
+        ir::Constant::Literal(Literal::CharLiteral(c)) => Some((i64::from(c), ty::IntKind::U8)),
+        _ => None,
+    }
+}
+
+fn int_literal(value: i64, kind: ty::IntKind) -> Literal {
+    match kind {
+        ty::IntKind::I32 => Literal::IntLiteral(kind.wrap(value)),
+        _ => Literal::SizedIntLiteral(kind.wrap(value), kind),
+    }
+}
+
+fn fold_unop(unop: ir::UnaryOperatorKind, sub: &ir::Constant) -> Option<Literal> {
+    match (unop, sub) {
+        (ir::UnaryOperatorKind::IntMinus, sub) => {
+            let (a, kind) = int_value(sub)?;
+            Some(int_literal(a.wrapping_neg(), kind))
+        }
+        (ir::UnaryOperatorKind::IntNot, sub) => {
+            let (a, kind) = int_value(sub)?;
+            Some(int_literal(!a, kind))
+        }
+        (
+            ir::UnaryOperatorKind::DoubleMinus,
+            &ir::Constant::Literal(Literal::DoubleLiteral(d)),
+        ) => Some(Literal::DoubleLiteral(-d)),
+        (
+            ir::UnaryOperatorKind::BooleanNot,
+            &ir::Constant::Literal(Literal::BooleanLiteral(b)),
+        ) => Some(Literal::BooleanLiteral(!b)),
+        _ => None,
+    }
+}
+
+fn fold_binop(
+    binop: ir::BinaryOperatorKind,
+    lhs: &ir::Constant,
+    rhs: &ir::Constant,
+) -> Option<Literal> {
+    use ir::BinaryOperatorKind as bok;
+
+    match (lhs, rhs) {
+        (
+            &ir::Constant::Literal(Literal::DoubleLiteral(a)),
+            &ir::Constant::Literal(Literal::DoubleLiteral(b)),
+        ) => {
+            // the comparisons are unordered ones, like in the backends
+            let unordered = a.is_nan() || b.is_nan();
+            let value = match binop {
+                bok::DoublePlus => return Some(Literal::DoubleLiteral(a + b)),
+                bok::DoubleMinus => return Some(Literal::DoubleLiteral(a - b)),
+                bok::DoubleMultiply => return Some(Literal::DoubleLiteral(a * b)),
+                bok::DoubleDivide => return Some(Literal::DoubleLiteral(a / b)),
+                bok::DoubleEqual => a == b,
+                bok::DoubleNotEqual => a != b,
+                bok::DoubleLess => a < b,
+                bok::DoubleLessEqual => a <= b,
+                bok::DoubleGreater => a > b,
+                bok::DoubleGreaterEqual => a >= b,
+                _ => return None,
+            };
+            Some(Literal::BooleanLiteral(value || unordered))
+        }
+        (
+            &ir::Constant::Literal(Literal::BooleanLiteral(a)),
+            &ir::Constant::Literal(Literal::BooleanLiteral(b)),
+        ) => match binop {
+            bok::BooleanEqual => Some(Literal::BooleanLiteral(a == b)),
+            bok::BooleanNotEqual => Some(Literal::BooleanLiteral(a != b)),
+            _ => None,
+        },
+        _ => {
+            let ((a, kind), (b, _)) = (int_value(lhs)?, int_value(rhs)?);
+            match binop.eval_int(a, b, kind)? {
+                ir::IntResult::Integer(value) => Some(int_literal(value, kind)),
+                ir::IntResult::Boolean(b) => Some(Literal::BooleanLiteral(b)),
+            }
+        }
+    }
+}
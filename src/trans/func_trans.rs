@@ -6,7 +6,10 @@ pub(super) struct FunctionBuilder<'ctxt> {
     tables: &'ctxt mut tables::Tables,
     ret_ty: ty::Type,
     in_loop: bool,
+    pub in_test: bool,
     pub var_declarations: Vec<ir::VarDeclaration>,
+    pub errors: Vec<Spanned<TranslationError>>,
+    pub warnings: Warnings,
 }
 
 impl<'ctxt> FunctionBuilder<'ctxt> {
@@ -15,48 +18,79 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
             tables,
             ret_ty,
             in_loop: false,
+            in_test: false,
             var_declarations: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
+    // the error is recorded so that the translation can go on
+    fn report<T>(&mut self, result: TranslationResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.errors.push(err);
+                None
+            }
+        }
+    }
+
+    fn warn(&mut self, warning: Warning, span: Span) {
+        self.warnings.push(Spanned::new(warning, span));
+    }
+
     pub(super) fn register_temp_local(&mut self, ty: ty::Type) -> ir::IdentifierId {
         let id = self.tables.locals.new_identifier_id();
         self.var_declarations.push(ir::VarDeclaration { ty, id });
         id
     }
 
-    pub(super) fn translate_type(
-        &mut self,
-        ty: Spanned<ast::Type>,
-        void: bool,
-    ) -> TranslationResult<ty::Type> {
-        translate_type(&mut self.tables.types, ty, void)
+    pub(super) fn translate_type(&mut self, ty: Spanned<ast::Type>, void: bool) -> ty::Type {
+        if let Some(ref mut index) = self.tables.index {
+            index.add_type_references(&ty);
+        }
+        translate_type(&mut self.tables.types, ty, void, &mut self.errors)
     }
 
     pub(super) fn translate_block_statement(
         &mut self,
         block: ast::BlockStatement,
-    ) -> TranslationResult<ir::BlockStatement> {
+    ) -> ir::BlockStatement {
         self.tables.locals.begin_scope();
 
         let mut ir_block = Vec::new();
+        let mut terminated = false;
+        let mut reported = false;
         for stmt in block.statements {
-            ir_block.push(self.translate_statement(stmt)?);
+            // only the first unreachable statement of the block is reported
+            match stmt.inner {
+                ast::Statement::Empty => {}
+                _ if terminated && !reported => {
+                    self.warn(Warning::UnreachableCode, stmt.span);
+                    reported = true;
+                }
+                ast::Statement::Return(_) | ast::Statement::Break | ast::Statement::Continue => {
+                    terminated = true;
+                }
+                _ => {}
+            }
+            ir_block.push(self.translate_statement(stmt));
         }
 
         self.tables.locals.end_scope();
-        Ok(ir_block)
+        ir_block
     }
 
     pub(super) fn translate_statement_as_block(
         &mut self,
         statement: Spanned<ast::Statement>,
-    ) -> TranslationResult<ir::BlockStatement> {
-        let ir_stmt = self.translate_statement(statement)?;
+    ) -> ir::BlockStatement {
+        let ir_stmt = self.translate_statement(statement);
         if let ir::Statement::Block(block) = ir_stmt {
-            Ok(block)
+            block
         } else {
-            Ok(vec![ir_stmt])
+            vec![ir_stmt]
         }
     }
 
@@ -68,16 +102,17 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         error_span: Span,
     ) -> TranslationResult<ir::Statement> {
         // first compute the rhs to avoir local shadowing
+        let rhs = self.translate_var_value(ty, value);
 
-        let value_span = value.span;
-        let mut rhs = self.translate_expression(value)?;
-        rhs = utils::lvalue_to_rvalue(rhs);
-        if let Some(ty) = ty {
-            let ty = self.translate_type(ty, false)?;
-            rhs = utils::check_eq_types_auto_cast(rhs, ty, value_span)?;
-        }
-
-        if let Some(id) = self.tables.locals.register_local(name.clone(), rhs.ty) {
+        let shadows = self.tables.locals.lookup_local(&name).is_some();
+        if let Some(id) =
+            self.tables
+                .locals
+                .register_local(name.clone(), rhs.ty, tables::SymbolKind::Local, error_span)
+        {
+            if shadows {
+                self.warn(Warning::ShadowedLocal(name), error_span);
+            }
             self.var_declarations
                 .push(ir::VarDeclaration { ty: rhs.ty, id });
             Ok(ir::Statement::Expression(utils::build_assign_to_id(
@@ -89,7 +124,73 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         }
     }
 
-    pub(super) fn translate_statement(
+    fn translate_var_value(
+        &mut self,
+        ty: Option<Spanned<ast::Type>>,
+        value: Spanned<ast::Expression>,
+    ) -> utils::TypedExpression {
+        let value_span = value.span;
+        let mut rhs = self.translate_expression(value);
+        let ty = ty.map(|ty| self.translate_type(ty, false));
+        if let Some(ty) = ty {
+            rhs = self.coerce_to_slice(rhs, ty);
+        }
+        rhs = utils::lvalue_to_rvalue(rhs);
+        if let Some(ty) = ty {
+            let checked = utils::check_eq_types_auto_cast(rhs, ty, value_span);
+            // the variable is still declared, with the type it was given
+            rhs = self.report(checked).unwrap_or_else(|| utils::TypedExpression {
+                ty,
+                expr: utils::error_expression(&self.tables.types).expr,
+            });
+        }
+        rhs
+    }
+
+    /// Translate a top level `let` of a REPL entry into a global of the session, assigned
+    /// by the entry, so that the next entries can use it.
+    pub(super) fn translate_session_global(
+        &mut self,
+        ty: Option<Spanned<ast::Type>>,
+        name: String,
+        value: Spanned<ast::Expression>,
+        error_span: Span,
+    ) -> TranslationResult<(ir::GlobalVariable, ir::Statement)> {
+        let rhs = self.translate_var_value(ty, value);
+        if rhs.ty == self.tables.types.get_void_ty() {
+            return error!(TranslationError::UnexpectedVoid, error_span);
+        }
+
+        let symbol = tables::GlobalSymbol {
+            ty: rhs.ty,
+            is_const: false,
+            span: error_span,
+        };
+        if self.tables.globals.register_variable(name.clone(), symbol) {
+            return error!(TranslationError::GlobalAlreadyDefined(name), error_span);
+        }
+
+        let assign = ir::Expression::Assign {
+            lhs: Box::new(ir::Expression::Value(ir::Value::Global(name.clone()))),
+            rhs: Box::new(rhs.expr),
+        };
+        let global = ir::GlobalVariable {
+            ty: rhs.ty,
+            name,
+            value: ir::Constant::Null, // the zero of its type until the entry runs
+            is_const: false,
+            span: error_span,
+        };
+        Ok((global, ir::Statement::Expression(assign)))
+    }
+
+    pub(super) fn translate_statement(&mut self, statement: Spanned<ast::Statement>) -> ir::Statement {
+        let result = self.try_translate_statement(statement);
+        self.report(result)
+            .unwrap_or_else(|| ir::Statement::Block(ir::BlockStatement::new()))
+    }
+
+    fn try_translate_statement(
         &mut self,
         statement: Spanned<ast::Statement>,
     ) -> TranslationResult<ir::Statement> {
@@ -100,7 +201,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         match statement {
             ast::Statement::Empty => Ok(ir::Statement::Block(vec![])),
             ast::Statement::Block(block) => {
-                let block = self.translate_block_statement(block)?;
+                let block = self.translate_block_statement(block);
                 Ok(ir::Statement::Block(block))
             }
             ast::Statement::Let(ast::LetStatement { ty, name, value }) => {
@@ -112,18 +213,19 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 else_clause,
             }) => {
                 let condition_span = condition.span;
-                let condition = self.translate_expression(condition)?;
+                let condition = self.translate_expression(condition);
                 let condition = utils::lvalue_to_rvalue(condition);
-                utils::check_expect_type(
+                let checked = utils::check_expect_type(
                     self.tables.types.get_boolean_ty(),
                     condition.ty,
                     condition_span,
-                )?;
+                );
+                self.report(checked);
 
-                let body = self.translate_statement_as_block(*body)?;
+                let body = self.translate_statement_as_block(*body);
 
                 let else_clause = if let Some(stmt) = else_clause {
-                    self.translate_statement_as_block(*stmt)?
+                    self.translate_statement_as_block(*stmt)
                 } else {
                     ir::BlockStatement::new()
                 };
@@ -143,7 +245,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 };
 
                 let spanned = Spanned::new(ast::Statement::For(fake_ast_for), stmt_span);
-                self.translate_statement(spanned)
+                self.try_translate_statement(spanned)
             }
             ast::Statement::For(ast::ForStatement {
                 init,
@@ -151,26 +253,27 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 step,
                 body,
             }) => {
-                let init = Box::new(self.translate_statement(*init)?);
+                let init = Box::new(self.translate_statement(*init));
 
                 let condition_span = condition.span;
-                let condition = self.translate_expression(condition)?;
+                let condition = self.translate_expression(condition);
                 let condition = utils::lvalue_to_rvalue(condition);
-                utils::check_expect_type(
+                let checked = utils::check_expect_type(
                     self.tables.types.get_boolean_ty(),
                     condition.ty,
                     condition_span,
-                )?;
+                );
+                self.report(checked);
 
                 let step = if let Some(step) = step {
-                    Some(self.translate_expression(step)?.expr)
+                    Some(self.translate_expression(step).expr)
                 } else {
                     None
                 };
 
                 let old_in_loop = self.in_loop;
                 self.in_loop = true;
-                let body = self.translate_statement_as_block(*body)?;
+                let body = self.translate_statement_as_block(*body);
                 self.in_loop = old_in_loop;
 
                 Ok(ir::Statement::For {
@@ -180,23 +283,34 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                     body,
                 })
             }
+            ast::Statement::ForEach(for_each) => self.translate_for_each(for_each),
             ast::Statement::Return(maybe_expr) => {
+                // a mistyped return still ends its path, so no missing return is reported
                 let expr = if let Some(expr) = maybe_expr {
                     let expr_span = expr.span;
-                    let expr = self.translate_expression(expr)?;
+                    let expr = self.translate_expression(expr);
+                    let ret_ty = self.ret_ty;
+                    let expr = self.coerce_to_slice(expr, ret_ty);
                     let expr = utils::lvalue_to_rvalue(expr);
-                    let expr = utils::check_eq_types_auto_cast(expr, self.ret_ty, expr_span)?;
+                    let checked = utils::check_eq_types_auto_cast(expr, self.ret_ty, expr_span);
+                    let expr = self.report(checked)
+                        .unwrap_or_else(|| utils::error_expression(&self.tables.types));
 
                     Some(expr.expr)
                 } else {
-                    utils::check_eq_types(self.tables.types.get_void_ty(), self.ret_ty, stmt_span)?;
+                    let void_ty = self.tables.types.get_void_ty();
+                    let checked = utils::check_eq_types(void_ty, self.ret_ty, stmt_span);
+                    self.report(checked);
                     None
                 };
 
                 Ok(ir::Statement::Return(expr))
             }
             ast::Statement::Expression(expr) => {
-                let expr = self.translate_expression(expr)?;
+                if self.is_builtin_assert(&expr) {
+                    return self.translate_assert(expr);
+                }
+                let expr = self.translate_expression(expr);
                 let expr = utils::lvalue_to_rvalue(expr);
                 Ok(ir::Statement::Expression(expr.expr))
             }
@@ -217,9 +331,179 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         }
     }
 
+    fn translate_for_each(
+        &mut self,
+        for_each: ast::ForEachStatement,
+    ) -> TranslationResult<ir::Statement> {
+        let ast::ForEachStatement {
+            ty,
+            name,
+            array,
+            body,
+        } = for_each;
+        let ty_span = ty.span;
+        let array_span = array.span;
+        let item_ty = self.translate_type(ty, false);
+        let array = self.translate_expression(array);
+        if item_ty.is_error() || array.ty.is_error() {
+            return Ok(ir::Statement::Block(ir::BlockStatement::new()));
+        }
+
+        let array_ty = utils::rvalue_type(array.ty);
+        let mut stmts = Vec::new();
+        let (sub_ty, ptr, len) = if let Some(s) = self.spill_sized_array(array, &mut stmts) {
+            s
+        } else {
+            return error!(TranslationError::LengthOnNonArray(array_ty), array_span);
+        };
+        utils::check_eq_types(item_ty, sub_ty, ty_span)?;
+
+        let int_ty = self.tables.types.get_int_ty();
+        let index_id = self.register_temp_local(int_ty);
+        let zero_literal =
+            ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(0)));
+        stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+            index_id,
+            zero_literal,
+        )));
+
+        self.tables.locals.begin_scope();
+        let id = self.tables.locals.register_local(
+            name.inner.clone(),
+            item_ty,
+            tables::SymbolKind::Local,
+            name.span,
+        );
+        let id = if let Some(id) = id {
+            id
+        } else {
+            self.tables.locals.end_scope();
+            return error!(TranslationError::LocalAlreadyDefined(name.inner), name.span);
+        };
+        self.var_declarations
+            .push(ir::VarDeclaration { ty: item_ty, id });
+
+        // the index moves before the body runs, so a `continue` still goes to the next item
+        let mut loop_body = vec![
+            ir::Statement::Expression(utils::build_assign_to_id(
+                id,
+                ir::Expression::LValueToRValue(Box::new(utils::build_subscript(
+                    ptr,
+                    utils::rvalue_of_id(index_id),
+                ))),
+            )),
+            ir::Statement::Expression(ir::Expression::LValueUnaryOperator {
+                lvalue_unop: ir::LValueUnaryOperatorKind::IntIncrement,
+                sub: Box::new(ir::Expression::Value(ir::Value::Local(index_id))),
+            }),
+        ];
+        let old_in_loop = self.in_loop;
+        self.in_loop = true;
+        loop_body.extend(self.translate_statement_as_block(*body));
+        self.in_loop = old_in_loop;
+        self.tables.locals.end_scope();
+
+        stmts.push(ir::Statement::For {
+            init: Box::new(ir::Statement::Block(ir::BlockStatement::new())),
+            condition: ir::Expression::BinaryOperator {
+                binop: ir::BinaryOperatorKind::IntLess,
+                lhs: Box::new(utils::rvalue_of_id(index_id)),
+                rhs: Box::new(len),
+            },
+            step: None,
+            body: loop_body,
+        });
+        Ok(ir::Statement::Block(stmts))
+    }
+
+    // `assert` is a builtin statement, unless a variable or a function takes its name
+    fn is_builtin_assert(&self, expr: &Spanned<ast::Expression>) -> bool {
+        if let ast::Expression::FunctionCall { ref function, .. } = expr.inner {
+            if let ast::Expression::Identifier(ref id) = function.inner {
+                return id == "assert" && self.tables.locals.lookup_local(id).is_none()
+                    && self.tables.globals.lookup_function(id).is_none()
+                    && self.tables.globals.lookup_variable(id).is_none();
+            }
+        }
+        false
+    }
+
+    fn translate_assert(
+        &mut self,
+        expr: Spanned<ast::Expression>,
+    ) -> TranslationResult<ir::Statement> {
+        let args = match expr.inner {
+            ast::Expression::FunctionCall { args, .. } => args,
+            _ => unreachable!(),
+        };
+
+        if !self.in_test {
+            return error!(TranslationError::AssertOutsideTest, expr.span);
+        }
+        if args.len() != 2 {
+            return error!(
+                TranslationError::FunctionCallArityMismatch(2, args.len()),
+                expr.span
+            );
+        }
+
+        let mut args = args.into_iter();
+        let condition = args.next().unwrap();
+        let condition_span = condition.span;
+        let condition = utils::lvalue_to_rvalue(self.translate_expression(condition));
+        let checked = utils::check_expect_type(
+            self.tables.types.get_boolean_ty(),
+            condition.ty,
+            condition_span,
+        );
+        self.report(checked);
+
+        let message = args.next().unwrap();
+        let message_span = message.span;
+        let message = utils::lvalue_to_rvalue(self.translate_expression(message));
+        let checked = utils::check_expect_type(
+            self.tables.types.get_string_ty(),
+            message.ty,
+            message_span,
+        );
+        self.report(checked);
+
+        // the harness turns the position of the assert into a source location
+        let failed = ir::Expression::FunctionCall {
+            function: Box::new(ir::Expression::Value(ir::Value::Global(
+                ASSERT_FAILED_FUNCTION.to_string(),
+            ))),
+            args: vec![
+                message.expr,
+                ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(
+                    expr.span.start as _,
+                ))),
+            ],
+        };
+        Ok(ir::Statement::If {
+            condition: condition.expr,
+            body: ir::BlockStatement::new(),
+            else_clause: vec![ir::Statement::Expression(failed)],
+        })
+    }
+
     pub(super) fn translate_expression(
         &mut self,
         expression: Spanned<ast::Expression>,
+    ) -> utils::TypedExpression {
+        let expr_span = expression.span;
+        let result = self.try_translate_expression(expression);
+        let expr = self.report(result)
+            .unwrap_or_else(|| utils::error_expression(&self.tables.types));
+        if let Some(ref mut index) = self.tables.index {
+            index.add_expression(expr_span, expr.ty);
+        }
+        expr
+    }
+
+    fn try_translate_expression(
+        &mut self,
+        expression: Spanned<ast::Expression>,
     ) -> TranslationResult<utils::TypedExpression> {
         let Spanned {
             inner: expression,
@@ -228,6 +512,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
 
         match expression {
             ast::Expression::Literal(lit) => {
+                let lit = utils::check_literal(&self.tables.types, lit, false, expr_span)?;
                 let ty = lit.get_type(&self.tables.types);
                 Ok(utils::TypedExpression {
                     ty,
@@ -235,13 +520,28 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 })
             }
             ast::Expression::Identifier(id) => {
-                if let Some(symbol) = self.tables.locals.lookup_local(&id).cloned() {
+                if let Some(symbol) = self.tables.locals.use_local(&id).cloned() {
+                    if let Some(ref mut index) = self.tables.index {
+                        index.add_reference(expr_span, symbol.span);
+                    }
                     let lvalue_ty = self.tables.types.lvalue_of(symbol.ty, true);
                     Ok(utils::TypedExpression {
                         ty: lvalue_ty,
                         expr: ir::Expression::Value(ir::Value::Local(symbol.id)),
                     })
-                } else if let Some(func_ty) = self.tables.globals.lookup_function(&id).cloned() {
+                } else if let Some(global) = self.tables.globals.lookup_variable(&id).cloned() {
+                    if let Some(ref mut index) = self.tables.index {
+                        index.add_reference(expr_span, global.span);
+                    }
+                    let lvalue_ty = self.tables.types.lvalue_of(global.ty, !global.is_const);
+                    Ok(utils::TypedExpression {
+                        ty: lvalue_ty,
+                        expr: ir::Expression::Value(ir::Value::Global(id)),
+                    })
+                } else if let Some(func_ty) = self.tables.globals.use_function(&id).cloned() {
+                    if let Some(ref mut index) = self.tables.index {
+                        index.add_function_reference(expr_span, &id);
+                    }
                     Ok(utils::TypedExpression {
                         ty: self.tables.types.function_of(func_ty),
                         expr: ir::Expression::Value(ir::Value::Global(id)),
@@ -250,13 +550,20 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                     error!(TranslationError::UndefinedVariable(id), expr_span)
                 }
             }
-            ast::Expression::Parenthesis(sub) => self.translate_expression(*sub),
+            ast::Expression::Parenthesis(sub) => self.try_translate_expression(*sub),
             ast::Expression::Assign { lhs, rhs } => {
                 let lhs_span = lhs.span;
-                let lhs = self.translate_expression(*lhs)?;
-                let mut rhs = self.translate_expression(*rhs)?;
+                let lhs = self.translate_expression(*lhs);
+                let mut rhs = self.translate_expression(*rhs);
+                if let ty::TypeValue::LValue(sub, _) = *lhs.ty {
+                    rhs = self.coerce_to_slice(rhs, sub);
+                }
                 rhs = utils::lvalue_to_rvalue(rhs);
 
+                if lhs.ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
+
                 if let ty::TypeValue::LValue(sub, true) = *lhs.ty {
                     rhs = utils::check_eq_types_auto_cast(rhs, sub, expr_span)?;
                 } else {
@@ -271,20 +578,23 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                     },
                 })
             }
+            ast::Expression::CompoundAssign { binop, lhs, rhs } => {
+                self.translate_compound_assign(binop, *lhs, *rhs, expr_span)
+            }
             ast::Expression::BinaryOperator { binop, lhs, rhs } => {
-                let lhs = self.translate_expression(*lhs)?;
+                let lhs = self.translate_expression(*lhs);
                 let lhs = utils::lvalue_to_rvalue(lhs);
-                let rhs = self.translate_expression(*rhs)?;
+                let rhs = self.translate_expression(*rhs);
                 let rhs = utils::lvalue_to_rvalue(rhs);
 
+                if lhs.ty.is_error() || rhs.ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
+
                 if let Some((ty, op)) =
                     typeck::binop_typeck(&self.tables.types, binop, lhs.ty, rhs.ty)
                 {
-                    let expr = ir::Expression::BinaryOperator {
-                        binop: op,
-                        lhs: Box::new(lhs.expr),
-                        rhs: Box::new(rhs.expr),
-                    };
+                    let expr = utils::build_binop(op, lhs.expr, rhs.expr);
                     Ok(utils::TypedExpression { ty, expr })
                 } else {
                     error!(
@@ -297,9 +607,29 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 self.translate_lazyop(lazyop, *lhs, *rhs, expr_span)
             }
             ast::Expression::UnaryOperator { unop, sub } => {
-                let sub = self.translate_expression(*sub)?;
+                // the minus is part of an integer literal, `-2147483648` is an `int`
+                if let ast::UnaryOperatorKind::Minus = unop {
+                    match sub.inner {
+                        ast::Expression::Literal(lit @ common::Literal::IntLiteral(_))
+                        | ast::Expression::Literal(lit @ common::Literal::SizedIntLiteral(..)) => {
+                            let lit =
+                                utils::check_literal(&self.tables.types, lit, true, expr_span)?;
+                            return Ok(utils::TypedExpression {
+                                ty: lit.get_type(&self.tables.types),
+                                expr: ir::Expression::Value(ir::Value::Literal(lit)),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+
+                let sub = self.translate_expression(*sub);
                 let sub = utils::lvalue_to_rvalue(sub);
 
+                if sub.ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
+
                 if let Some((ty, op)) = typeck::unop_typeck(&mut self.tables.types, unop, sub.ty) {
                     let expr = ir::Expression::UnaryOperator {
                         unop: op,
@@ -311,7 +641,11 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 }
             }
             ast::Expression::LValueUnaryOperator { lvalue_unop, sub } => {
-                let sub = self.translate_expression(*sub)?;
+                let sub = self.translate_expression(*sub);
+
+                if sub.ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
 
                 if let ty::TypeValue::LValue(sub_ty, true) = *sub.ty {
                     if let Some((ty, op)) =
@@ -334,10 +668,13 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 }
             }
             ast::Expression::Cast { as_ty, sub } => {
-                let sub = self.translate_expression(*sub)?;
+                let sub = self.translate_expression(*sub);
                 let sub = utils::lvalue_to_rvalue(sub);
 
-                let as_ty = self.translate_type(as_ty, false)?;
+                let as_ty = self.translate_type(as_ty, false);
+                if sub.ty.is_error() || as_ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
 
                 match typeck::cast_typeck(sub.ty, as_ty) {
                     typeck::CastTypeckResult::Cast(kind) => {
@@ -360,9 +697,15 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 }
             }
             ast::Expression::Subscript { array, index } => self.translate_subscript(*array, *index),
+            ast::Expression::Slice { array, begin, end } => {
+                self.translate_slice(*array, *begin, *end, expr_span)
+            }
             ast::Expression::FunctionCall { function, args } => {
-                let function = self.translate_expression(*function)?;
+                let function = self.translate_expression(*function);
                 let function = utils::lvalue_to_rvalue(function);
+                if function.ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
 
                 let func_ty = if let ty::TypeValue::FunctionPtr(ref func_ty) = *function.ty {
                     func_ty.clone()
@@ -393,7 +736,10 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
 
                 for (index, arg) in args.into_iter().enumerate() {
                     let arg_span = arg.span;
-                    let mut arg = self.translate_expression(arg)?;
+                    let mut arg = self.translate_expression(arg);
+                    if index < func_ty.parameters_ty.len() {
+                        arg = self.coerce_to_slice(arg, func_ty.parameters_ty[index]);
+                    }
                     arg = utils::lvalue_to_rvalue(arg);
                     if index < func_ty.parameters_ty.len() {
                         arg = utils::check_eq_types_auto_cast(
@@ -401,6 +747,24 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                             func_ty.parameters_ty[index],
                             arg_span,
                         )?;
+                    } else {
+                        // like in C, the integers smaller than `int` are passed as `int`
+                        let promotion = match *arg.ty {
+                            ty::TypeValue::Char => Some(ir::CastKind::CharToInt(ty::IntKind::I32)),
+                            ty::TypeValue::SizedInt(kind) if kind.bits() < 32 => {
+                                Some(ir::CastKind::IntToInt(kind, ty::IntKind::I32))
+                            }
+                            _ => None,
+                        };
+                        if let Some(kind) = promotion {
+                            arg = utils::TypedExpression {
+                                ty: self.tables.types.get_int_ty(),
+                                expr: ir::Expression::Cast {
+                                    kind,
+                                    sub: Box::new(arg.expr),
+                                },
+                            };
+                        }
                     }
                     args_translated.push(arg.expr);
                 }
@@ -424,13 +788,18 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                 fields,
             } => self.translate_struct_literal(struct_name, fields, expr_span),
             ast::Expression::FieldAccess { expr, field } => {
-                let expr = self.translate_expression(*expr)?;
+                let expr = self.translate_expression(*expr);
+                // the fields of a constant are constants too
+                let assignable = utils::is_assignable(expr.ty);
                 let (expr, sub_ty) = utils::rvalue_to_lvalue(&self.tables.types, expr);
+                if sub_ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
 
                 match sub_ty.has_field(&field) {
                     Some(ty::FieldInfo::StructField(index, ty))
                     | Some(ty::FieldInfo::TupleField(index, ty)) => {
-                        let lvalue_ty = self.tables.types.lvalue_of(ty, true);
+                        let lvalue_ty = self.tables.types.lvalue_of(ty, assignable);
                         Ok(utils::TypedExpression {
                             ty: lvalue_ty,
                             expr: ir::Expression::FieldAccess {
@@ -445,6 +814,24 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                             common::Literal::IntLiteral(size as _),
                         )),
                     }),
+                    Some(ty::FieldInfo::RuntimeLen) => {
+                        // the length is read at runtime but can't be changed
+                        let int_ty = self.tables.types.get_int_ty();
+                        Ok(utils::TypedExpression {
+                            ty: self.tables.types.lvalue_of(int_ty, false),
+                            expr: ir::Expression::FieldAccess {
+                                sub: Box::new(expr.expr),
+                                index: 0,
+                            },
+                        })
+                    }
+                    Some(ty::FieldInfo::StringLen) => Ok(utils::TypedExpression {
+                        ty: self.tables.types.get_int_ty(),
+                        expr: utils::build_runtime_call(
+                            STRING_LENGTH_FUNCTION,
+                            vec![utils::lvalue_to_rvalue(expr).expr],
+                        ),
+                    }),
                     None => error!(
                         TranslationError::UndefinedField(field.to_string()),
                         expr_span
@@ -464,9 +851,429 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
                     },
                 })
             }
+            ast::Expression::EnumVariant {
+                enum_name,
+                variant,
+                payload,
+            } => self.translate_enum_value(enum_name, variant, payload, expr_span),
+            ast::Expression::Match { scrutinee, arms } => {
+                self.translate_match(*scrutinee, arms, expr_span)
+            }
+            ast::Expression::SizeOf(ty) => {
+                Ok(self.translate_layout_query(ty, ir::Expression::SizeOf))
+            }
+            ast::Expression::AlignOf(ty) => {
+                Ok(self.translate_layout_query(ty, ir::Expression::AlignOf))
+            }
+            ast::Expression::New { ty, value } => self.translate_new(ty, value),
+            ast::Expression::NewArray { ty, size, rows } => {
+                self.translate_new_array(ty, *size, rows)
+            }
+            ast::Expression::Delete(sub) => {
+                let sub_span = sub.span;
+                let sub = self.translate_expression(*sub);
+                if sub.ty.is_error() {
+                    return Ok(utils::error_expression(&self.tables.types));
+                }
+
+                // deleting a dynamic array frees its items
+                let (sub, sub_ty) = utils::rvalue_to_lvalue(&self.tables.types, sub);
+                let ptr = match *sub_ty {
+                    ty::TypeValue::Pointer(_) => utils::lvalue_to_rvalue(sub).expr,
+                    ty::TypeValue::DynArray(_) => utils::array_data(sub.expr),
+                    _ => return error!(TranslationError::DeleteNonPointer(sub_ty), sub_span),
+                };
+                Ok(utils::TypedExpression {
+                    ty: self.tables.types.get_void_ty(),
+                    expr: ir::Expression::Delete(Box::new(ptr)),
+                })
+            }
         }
     }
 
+    pub(super) fn translate_compound_assign(
+        &mut self,
+        binop: ast::BinaryOperatorKind,
+        lhs: Spanned<ast::Expression>,
+        rhs: Spanned<ast::Expression>,
+        expr_span: Span,
+    ) -> TranslationResult<utils::TypedExpression> {
+        let lhs_span = lhs.span;
+        let lhs = self.translate_expression(lhs);
+        let rhs = self.translate_expression(rhs);
+        let rhs = utils::lvalue_to_rvalue(rhs);
+
+        if lhs.ty.is_error() || rhs.ty.is_error() {
+            return Ok(utils::error_expression(&self.tables.types));
+        }
+
+        let lhs_ty = if let ty::TypeValue::LValue(sub, true) = *lhs.ty {
+            sub
+        } else {
+            return error!(TranslationError::NonLValueAssign, lhs_span);
+        };
+
+        let op = match typeck::binop_typeck(&self.tables.types, binop, lhs_ty, rhs.ty) {
+            Some((ty, op)) => {
+                utils::check_eq_types(ty, lhs_ty, expr_span)?;
+                op
+            }
+            None => {
+                return error!(
+                    TranslationError::BinOpUndefined(binop, lhs_ty, rhs.ty),
+                    expr_span
+                )
+            }
+        };
+
+        // the address of the lvalue is computed once, `a[f()] += 1` calls `f` once
+        let mut stmts = Vec::new();
+        let lvalue = if let ir::Expression::Value(_) = lhs.expr {
+            lhs.expr
+        } else {
+            let ptr_id = self.register_temp_local(self.tables.types.pointer_of(lhs_ty));
+            let address = ir::Expression::LValueUnaryOperator {
+                lvalue_unop: ir::LValueUnaryOperatorKind::LValueToPtr,
+                sub: Box::new(lhs.expr),
+            };
+            stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+                ptr_id, address,
+            )));
+            ir::Expression::UnaryOperator {
+                unop: ir::UnaryOperatorKind::PointerDeref,
+                sub: Box::new(utils::rvalue_of_id(ptr_id)),
+            }
+        };
+
+        let assign = ir::Expression::Assign {
+            lhs: Box::new(lvalue.clone()),
+            rhs: Box::new(utils::build_binop(
+                op,
+                ir::Expression::LValueToRValue(Box::new(lvalue)),
+                rhs.expr,
+            )),
+        };
+        let expr = if stmts.is_empty() {
+            assign
+        } else {
+            ir::Expression::Block(Box::new(ir::BlockExpression {
+                stmts,
+                final_expr: assign,
+            }))
+        };
+        Ok(utils::TypedExpression { ty: lhs_ty, expr })
+    }
+
+    fn translate_enum_value(
+        &mut self,
+        enum_name: String,
+        variant: Spanned<String>,
+        payload: Vec<Spanned<ast::Expression>>,
+        expr_span: Span,
+    ) -> TranslationResult<utils::TypedExpression> {
+        let (ty, variant) =
+            utils::translate_enum_variant(self.tables, enum_name, variant, expr_span)?;
+        if variant.payload.len() != payload.len() {
+            return error!(
+                TranslationError::VariantArityMismatch(
+                    variant.name,
+                    variant.payload.len(),
+                    payload.len()
+                ),
+                expr_span
+            );
+        }
+
+        let tag = ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(
+            i64::from(variant.discriminant),
+        )));
+        match *ty {
+            ty::TypeValue::Enum(e) if e.has_payload() => {}
+            _ => return Ok(utils::TypedExpression { ty, expr: tag }),
+        }
+
+        // the tag and the fields are written to a temporary, like a struct literal
+        let res_id = self.register_temp_local(ty);
+        let res_id_expr = ir::Expression::Value(ir::Value::Local(res_id));
+        let payload_expr = ir::Expression::VariantPayload {
+            sub: Box::new(res_id_expr.clone()),
+            payload_ty: self.tables.types.tuple_of(variant.payload.clone()),
+        };
+        let mut stmts = vec![ir::Statement::Expression(utils::build_assign_to_field(
+            res_id_expr.clone(),
+            0,
+            tag,
+        ))];
+        for (index, (value, &field_ty)) in payload.into_iter().zip(&variant.payload).enumerate() {
+            let value_span = value.span;
+            let value = self.translate_expression(value);
+            let value = self.coerce_to_slice(value, field_ty);
+            let value = utils::lvalue_to_rvalue(value);
+            let value = utils::check_eq_types_auto_cast(value, field_ty, value_span)?;
+            stmts.push(ir::Statement::Expression(utils::build_assign_to_field(
+                payload_expr.clone(),
+                index,
+                value.expr,
+            )));
+        }
+
+        Ok(utils::TypedExpression {
+            ty: self.tables.types.lvalue_of(ty, false),
+            expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                stmts,
+                final_expr: res_id_expr,
+            })),
+        })
+    }
+
+    // a chain of ternaries testing the tag, the first matching arm is evaluated
+    fn translate_match(
+        &mut self,
+        scrutinee: Spanned<ast::Expression>,
+        arms: Vec<ast::MatchArm>,
+        expr_span: Span,
+    ) -> TranslationResult<utils::TypedExpression> {
+        let scrutinee_span = scrutinee.span;
+        let scrutinee = self.translate_expression(scrutinee);
+        let scrutinee = utils::lvalue_to_rvalue(scrutinee);
+        if scrutinee.ty.is_error() {
+            return Ok(utils::error_expression(&self.tables.types));
+        }
+
+        let enum_ty = scrutinee.ty;
+        let enum_tv = if let ty::TypeValue::Enum(e) = *enum_ty {
+            e
+        } else {
+            return error!(TranslationError::MatchNonEnum(enum_ty), scrutinee_span);
+        };
+
+        // the scrutinee is evaluated once
+        let scrutinee_id = self.register_temp_local(enum_ty);
+        let scrutinee_lvalue = ir::Expression::Value(ir::Value::Local(scrutinee_id));
+        let tag = if enum_tv.has_payload() {
+            ir::Expression::LValueToRValue(Box::new(ir::Expression::FieldAccess {
+                sub: Box::new(scrutinee_lvalue.clone()),
+                index: 0,
+            }))
+        } else {
+            utils::rvalue_of_id(scrutinee_id)
+        };
+
+        let mut covered: Vec<String> = Vec::new();
+        let mut wildcard = false;
+        let mut arm_ty = None;
+        let mut branches = Vec::with_capacity(arms.len());
+        for arm in arms {
+            let pattern_span = arm.pattern.span;
+            let mut stmts = Vec::new();
+            let mut bindings_scope = false;
+            let (discriminant, reachable) = match arm.pattern.inner {
+                ast::Pattern::Wildcard => {
+                    let reachable = !wildcard;
+                    wildcard = true;
+                    (None, reachable)
+                }
+                ast::Pattern::Variant {
+                    enum_name,
+                    variant,
+                    bindings,
+                } => {
+                    let (ty, variant) = utils::translate_enum_variant(
+                        self.tables,
+                        enum_name,
+                        variant,
+                        pattern_span,
+                    )?;
+                    utils::check_eq_types(ty, enum_ty, pattern_span)?;
+                    if variant.payload.len() != bindings.len() {
+                        return error!(
+                            TranslationError::VariantArityMismatch(
+                                variant.name,
+                                variant.payload.len(),
+                                bindings.len()
+                            ),
+                            pattern_span
+                        );
+                    }
+
+                    // the bindings are copies of the fields of the payload
+                    self.tables.locals.begin_scope();
+                    bindings_scope = true;
+                    let payload_expr = ir::Expression::VariantPayload {
+                        sub: Box::new(scrutinee_lvalue.clone()),
+                        payload_ty: self.tables.types.tuple_of(variant.payload.clone()),
+                    };
+                    for (index, (binding, &field_ty)) in
+                        bindings.into_iter().zip(&variant.payload).enumerate()
+                    {
+                        if binding.inner == "_" {
+                            continue;
+                        }
+                        let id = self.tables.locals.register_local(
+                            binding.inner.clone(),
+                            field_ty,
+                            tables::SymbolKind::Local,
+                            binding.span,
+                        );
+                        if let Some(id) = id {
+                            self.var_declarations
+                                .push(ir::VarDeclaration { ty: field_ty, id });
+                            let field = ir::Expression::LValueToRValue(Box::new(
+                                ir::Expression::FieldAccess {
+                                    sub: Box::new(payload_expr.clone()),
+                                    index,
+                                },
+                            ));
+                            stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+                                id, field,
+                            )));
+                        } else {
+                            self.errors.push(Spanned::new(
+                                TranslationError::LocalAlreadyDefined(binding.inner),
+                                binding.span,
+                            ));
+                        }
+                    }
+
+                    let reachable = !wildcard && !covered.contains(&variant.name);
+                    covered.push(variant.name);
+                    (Some(variant.discriminant), reachable)
+                }
+            };
+
+            let value_span = arm.value.span;
+            let value = self.translate_expression(arm.value);
+            let value = utils::lvalue_to_rvalue(value);
+            if bindings_scope {
+                self.tables.locals.end_scope();
+            }
+
+            match arm_ty {
+                Some(arm_ty) => utils::check_eq_types(value.ty, arm_ty, value_span)?,
+                None => arm_ty = Some(value.ty),
+            }
+            if reachable {
+                let branch = ir::Expression::Block(Box::new(ir::BlockExpression {
+                    stmts,
+                    final_expr: value.expr,
+                }));
+                branches.push((discriminant, branch));
+            } else {
+                self.warn(Warning::UnreachableCode, pattern_span);
+            }
+        }
+
+        if !wildcard {
+            let missing: Vec<_> = enum_tv
+                .variants
+                .iter()
+                .filter(|variant| !covered.contains(&variant.name))
+                .map(|variant| variant.name.clone())
+                .collect();
+            if !missing.is_empty() {
+                return error!(TranslationError::NonExhaustiveMatch(missing), expr_span);
+            }
+        }
+
+        // the last arm is taken when all the others fail
+        let mut branches = branches.into_iter().rev();
+        let mut expr = branches.next().unwrap().1;
+        for (discriminant, branch) in branches {
+            let discriminant = discriminant.unwrap(); // only the last arm can be a wildcard
+            let condition = utils::build_binop(
+                ir::BinaryOperatorKind::IntEqual,
+                tag.clone(),
+                ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(
+                    i64::from(discriminant),
+                ))),
+            );
+            expr = ir::Expression::Ternary {
+                condition: Box::new(condition),
+                true_expr: Box::new(branch),
+                false_expr: Box::new(expr),
+            };
+        }
+
+        Ok(utils::TypedExpression {
+            ty: arm_ty.unwrap(), // there is at least one arm
+            expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                stmts: vec![ir::Statement::Expression(utils::build_assign_to_id(
+                    scrutinee_id,
+                    scrutinee.expr,
+                ))],
+                final_expr: expr,
+            })),
+        })
+    }
+
+    // `sizeof(T)` and `alignof(T)` are `u64`, known once the backend lays out `T`
+    fn translate_layout_query<F>(
+        &mut self,
+        ty: Spanned<ast::Type>,
+        query: F,
+    ) -> utils::TypedExpression
+    where
+        F: FnOnce(ty::Type) -> ir::Expression,
+    {
+        let ty = self.translate_type(ty, false);
+        if ty.is_error() {
+            return utils::error_expression(&self.tables.types);
+        }
+        utils::TypedExpression {
+            ty: self.tables.types.get_integer_ty(ty::IntKind::U64),
+            expr: query(ty),
+        }
+    }
+
+    pub(super) fn translate_new(
+        &mut self,
+        ty: Spanned<ast::Type>,
+        value: Option<Box<Spanned<ast::Expression>>>,
+    ) -> TranslationResult<utils::TypedExpression> {
+        let ty = self.translate_type(ty, false);
+        if ty.is_error() {
+            return Ok(utils::error_expression(&self.tables.types));
+        }
+        let ptr_ty = self.tables.types.pointer_of(ty);
+        let new_expr = ir::Expression::New(ty);
+
+        let value = if let Some(value) = value {
+            let value_span = value.span;
+            let value = self.translate_expression(*value);
+            let value = utils::lvalue_to_rvalue(value);
+            utils::check_eq_types(value.ty, ty, value_span)?;
+            value
+        } else {
+            return Ok(utils::TypedExpression {
+                ty: ptr_ty,
+                expr: new_expr,
+            });
+        };
+
+        // the value is written to the new memory through a temporary pointer
+        let res_id = self.register_temp_local(ptr_ty);
+        let res_id_expr = ir::Expression::Value(ir::Value::Local(res_id));
+        let res_id_rvalue = ir::Expression::LValueToRValue(Box::new(res_id_expr));
+        let stmts = vec![
+            ir::Statement::Expression(utils::build_assign_to_id(res_id, new_expr)),
+            ir::Statement::Expression(ir::Expression::Assign {
+                lhs: Box::new(ir::Expression::UnaryOperator {
+                    unop: ir::UnaryOperatorKind::PointerDeref,
+                    sub: Box::new(res_id_rvalue.clone()),
+                }),
+                rhs: Box::new(value.expr),
+            }),
+        ];
+
+        Ok(utils::TypedExpression {
+            ty: ptr_ty,
+            expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                stmts,
+                final_expr: res_id_rvalue,
+            })),
+        })
+    }
+
     pub(super) fn translate_struct_literal(
         &mut self,
         struct_name: String,
@@ -479,6 +1286,12 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
             return error!(TranslationError::UndefinedType(struct_name), expr_span);
         };
 
+        if let Some(ref mut index) = self.tables.index {
+            if let Some(&definition) = index.types.get(&struct_name) {
+                index.add_reference(expr_span, definition);
+            }
+        }
+
         let struct_tv = if let ty::TypeValue::Struct(s) = *ty {
             (&*s).clone()
         } else {
@@ -492,7 +1305,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
 
         let mut checker = utils::StructLitChecker::new(struct_tv, expr_span);
         for (field_name, field_expr) in fields {
-            let expr = self.translate_expression(field_expr)?;
+            let expr = self.translate_expression(field_expr);
             let expr = utils::lvalue_to_rvalue(expr);
             let (expr, index) = checker.set_field(&field_name.inner, expr, field_name.span)?;
             stmts.push(ir::Statement::Expression(utils::build_assign_to_field(
@@ -520,7 +1333,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         let mut trans_values = Vec::with_capacity(values.len());
 
         for value in values {
-            let value = self.translate_expression(value)?;
+            let value = self.translate_expression(value);
             let value = utils::lvalue_to_rvalue(value);
             types.push(value.ty);
             trans_values.push(value.expr);
@@ -565,7 +1378,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         let mut trans_values = Vec::with_capacity(values.len());
         for value in values {
             let value_span = value.span;
-            let mut value = self.translate_expression(value)?;
+            let mut value = self.translate_expression(value);
             value = utils::lvalue_to_rvalue(value);
             if let Some(ty) = sub_ty {
                 value = utils::check_eq_types_auto_cast(value, ty, value_span)?;
@@ -608,7 +1421,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         value: Spanned<ast::Expression>,
         size: usize,
     ) -> TranslationResult<utils::TypedExpression> {
-        let value = self.translate_expression(value)?;
+        let value = self.translate_expression(value);
         let value = utils::lvalue_to_rvalue(value);
 
         let zero_literal =
@@ -671,6 +1484,140 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         })
     }
 
+    pub(super) fn translate_new_array(
+        &mut self,
+        ty: Spanned<ast::Type>,
+        size: Spanned<ast::Expression>,
+        rows: Vec<Spanned<ast::Expression>>,
+    ) -> TranslationResult<utils::TypedExpression> {
+        let ty = self.translate_type(ty, false);
+        let int_ty = self.tables.types.get_int_ty();
+        let mut sizes = Vec::new();
+        let mut error = ty.is_error();
+        for size in Some(size).into_iter().chain(rows) {
+            let size_span = size.span;
+            let size = utils::lvalue_to_rvalue(self.translate_expression(size));
+            if size.ty.is_error() {
+                error = true;
+                continue;
+            }
+            let size = utils::check_eq_types_auto_cast(size, int_ty, size_span)?;
+            // a negative size stops the program
+            sizes.push(ir::Expression::LengthCheck {
+                len: Box::new(size.expr),
+                line: self.tables.line_of(size_span.start),
+            });
+        }
+        if error {
+            return Ok(utils::error_expression(&self.tables.types));
+        }
+
+        // the sizes are computed once, before any array is allocated
+        let mut stmts = Vec::new();
+        let sizes: Vec<_> = sizes
+            .into_iter()
+            .map(|size| {
+                let size_id = self.register_temp_local(int_ty);
+                stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+                    size_id, size,
+                )));
+                utils::rvalue_of_id(size_id)
+            })
+            .collect();
+        let array = self.build_new_array(ty, &sizes);
+
+        Ok(utils::TypedExpression {
+            ty: array.ty,
+            expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                stmts,
+                final_expr: array.expr,
+            })),
+        })
+    }
+
+    // allocates an array of `sizes[0]` items, each one an array of the next sizes
+    fn build_new_array(
+        &mut self,
+        ty: ty::Type,
+        sizes: &[ir::Expression],
+    ) -> utils::TypedExpression {
+        let mut item_ty = ty;
+        for _ in 1..sizes.len() {
+            item_ty = self.tables.types.dyn_array_of(item_ty);
+        }
+
+        // the size is stored first, then the items are allocated with it
+        let array_ty = self.tables.types.dyn_array_of(item_ty);
+        let res_id = self.register_temp_local(array_ty);
+        let res_id_expr = ir::Expression::Value(ir::Value::Local(res_id));
+        let size_expr = ir::Expression::LValueToRValue(Box::new(ir::Expression::FieldAccess {
+            sub: Box::new(res_id_expr.clone()),
+            index: 0,
+        }));
+        let new_expr = ir::Expression::NewArray {
+            ty: item_ty,
+            size: Box::new(size_expr.clone()),
+        };
+        let mut stmts = vec![
+            ir::Statement::Expression(utils::build_assign_to_field(
+                res_id_expr.clone(),
+                0,
+                sizes[0].clone(),
+            )),
+            ir::Statement::Expression(utils::build_assign_to_field(
+                res_id_expr.clone(),
+                1,
+                new_expr,
+            )),
+        ];
+
+        if sizes.len() > 1 {
+            let int_ty = self.tables.types.get_int_ty();
+            let index_id = self.register_temp_local(int_ty);
+            let index_id_expr = ir::Expression::Value(ir::Value::Local(index_id));
+            let zero_literal =
+                ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(0)));
+            let items_expr =
+                ir::Expression::LValueToRValue(Box::new(ir::Expression::FieldAccess {
+                    sub: Box::new(res_id_expr.clone()),
+                    index: 1,
+                }));
+            let row = self.build_new_array(ty, &sizes[1..]);
+            stmts.push(ir::Statement::For {
+                init: Box::new(ir::Statement::Expression(utils::build_assign_to_id(
+                    index_id,
+                    zero_literal,
+                ))),
+                condition: ir::Expression::BinaryOperator {
+                    binop: ir::BinaryOperatorKind::IntLess,
+                    lhs: Box::new(utils::rvalue_of_id(index_id)),
+                    rhs: Box::new(size_expr),
+                },
+                step: Some(ir::Expression::LValueUnaryOperator {
+                    lvalue_unop: ir::LValueUnaryOperatorKind::IntIncrement,
+                    sub: Box::new(index_id_expr),
+                }),
+                body: vec![
+                    ir::Statement::Expression(ir::Expression::Assign {
+                        lhs: Box::new(utils::build_subscript(
+                            items_expr,
+                            utils::rvalue_of_id(index_id),
+                        )),
+                        rhs: Box::new(row.expr),
+                    }),
+                ],
+            });
+        }
+
+        utils::TypedExpression {
+            ty: array_ty,
+            expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                stmts,
+                final_expr: ir::Expression::LValueToRValue(Box::new(res_id_expr)),
+            })),
+        }
+    }
+
     pub(super) fn translate_subscript(
         &mut self,
         array: Spanned<ast::Expression>,
@@ -679,10 +1626,57 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         let array_span = array.span;
         let index_span = index.span;
 
-        let array = self.translate_expression(array)?;
-        let mut index = self.translate_expression(index)?;
+        let array = self.translate_expression(array);
+        let mut index = self.translate_expression(index);
         index = utils::lvalue_to_rvalue(index);
         let array_ty = array.ty;
+        // the items of a constant array are constants, not the values behind a pointer
+        let assignable = match *array_ty {
+            ty::TypeValue::LValue(sub, assignable) => match *sub {
+                ty::TypeValue::Array(_, _) => assignable,
+                _ => true,
+            },
+            _ => true,
+        };
+        if array_ty.is_error() {
+            return Ok(utils::error_expression(&self.tables.types));
+        }
+
+        if utils::runtime_sized_item(utils::rvalue_type(array_ty)).is_some() {
+            // the index of a slice or a dynamic array is checked against its length at runtime
+            let mut stmts = Vec::new();
+            let (sub_ty, ptr, len) = self.spill_sized_array(array, &mut stmts)
+                .expect("a slice or a dynamic array has a length");
+            index =
+                utils::check_eq_types_auto_cast(index, self.tables.types.get_int_ty(), index_span)?;
+            let index = ir::Expression::BoundsCheck {
+                index: Box::new(index.expr),
+                len: Box::new(len),
+                line: self.tables.line_of(index_span.start),
+            };
+            return Ok(utils::TypedExpression {
+                ty: self.tables.types.lvalue_of(sub_ty, true),
+                expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                    stmts,
+                    final_expr: utils::build_subscript(ptr, index),
+                })),
+            });
+        }
+
+        if let ty::TypeValue::String = *utils::rvalue_type(array_ty) {
+            // the bytes of a string can be read but not written
+            let char_ty = self.tables.types.get_char_ty();
+            let ptr = ir::Expression::BitCast {
+                dest_ty: self.tables.types.pointer_of(char_ty),
+                sub: Box::new(utils::lvalue_to_rvalue(array).expr),
+            };
+            index =
+                utils::check_eq_types_auto_cast(index, self.tables.types.get_int_ty(), index_span)?;
+            return Ok(utils::TypedExpression {
+                ty: self.tables.types.lvalue_of(char_ty, false),
+                expr: utils::build_subscript(ptr, index.expr),
+            });
+        }
 
         let (sub_ty, ptr) = if let Some(s) = utils::unsure_subscriptable(&self.tables.types, array)
         {
@@ -692,7 +1686,7 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         };
 
         index = utils::check_eq_types_auto_cast(index, self.tables.types.get_int_ty(), index_span)?;
-        let lvalue_ty = self.tables.types.lvalue_of(sub_ty, true);
+        let lvalue_ty = self.tables.types.lvalue_of(sub_ty, assignable);
 
         Ok(utils::TypedExpression {
             ty: lvalue_ty,
@@ -700,6 +1694,203 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         })
     }
 
+    pub(super) fn translate_slice(
+        &mut self,
+        array: Spanned<ast::Expression>,
+        begin: Spanned<ast::Expression>,
+        end: Spanned<ast::Expression>,
+        expr_span: Span,
+    ) -> TranslationResult<utils::TypedExpression> {
+        let array_span = array.span;
+        let begin_span = begin.span;
+        let end_span = end.span;
+
+        let array = self.translate_expression(array);
+        let begin = utils::lvalue_to_rvalue(self.translate_expression(begin));
+        let end = utils::lvalue_to_rvalue(self.translate_expression(end));
+        if array.ty.is_error() || begin.ty.is_error() || end.ty.is_error() {
+            return Ok(utils::error_expression(&self.tables.types));
+        }
+        let int_ty = self.tables.types.get_int_ty();
+        let begin = utils::check_eq_types_auto_cast(begin, int_ty, begin_span)?;
+        let end = utils::check_eq_types_auto_cast(end, int_ty, end_span)?;
+
+        let array_ty = utils::rvalue_type(array.ty);
+        let mut stmts = Vec::new();
+        // a string is sliced by the runtime, `ptr` is the string itself
+        let (sub_ty, ptr, len) = if let ty::TypeValue::String = *array_ty {
+            let string_id = self.register_temp_local(array_ty);
+            stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+                string_id,
+                utils::lvalue_to_rvalue(array).expr,
+            )));
+            let len = utils::build_runtime_call(
+                STRING_LENGTH_FUNCTION,
+                vec![utils::rvalue_of_id(string_id)],
+            );
+            (array_ty, utils::rvalue_of_id(string_id), len)
+        } else if let Some(s) = self.spill_sized_array(array, &mut stmts) {
+            s
+        } else {
+            return error!(TranslationError::SliceUnsized(array_ty), array_span);
+        };
+
+        // the bounds must verify `0 <= begin <= end <= len`
+        let line = self.tables.line_of(expr_span.start);
+        let begin_id = self.register_temp_local(int_ty);
+        let end_id = self.register_temp_local(int_ty);
+        let begin_value = utils::rvalue_of_id(begin_id);
+        let end_value = utils::rvalue_of_id(end_id);
+        let one = ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(1)));
+        let end_check = ir::Expression::BoundsCheck {
+            index: Box::new(end.expr),
+            len: Box::new(ir::Expression::BinaryOperator {
+                binop: ir::BinaryOperatorKind::IntPlus,
+                lhs: Box::new(len),
+                rhs: Box::new(one.clone()),
+            }),
+            line,
+        };
+        let begin_check = ir::Expression::BoundsCheck {
+            index: Box::new(begin_value.clone()),
+            len: Box::new(ir::Expression::BinaryOperator {
+                binop: ir::BinaryOperatorKind::IntPlus,
+                lhs: Box::new(end_value.clone()),
+                rhs: Box::new(one),
+            }),
+            line,
+        };
+        stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+            begin_id,
+            begin.expr,
+        )));
+        stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+            end_id,
+            end_check,
+        )));
+        stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+            begin_id,
+            begin_check,
+        )));
+
+        if let ty::TypeValue::String = *array_ty {
+            let substring =
+                utils::build_runtime_call(SUBSTRING_FUNCTION, vec![ptr, begin_value, end_value]);
+            return Ok(utils::TypedExpression {
+                ty: array_ty,
+                expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                    stmts,
+                    final_expr: substring,
+                })),
+            });
+        }
+
+        let len = ir::Expression::BinaryOperator {
+            binop: ir::BinaryOperatorKind::IntMinus,
+            lhs: Box::new(end_value),
+            rhs: Box::new(begin_value.clone()),
+        };
+        let ptr = ir::Expression::BinaryOperator {
+            binop: ir::BinaryOperatorKind::PtrPlusOffset,
+            lhs: Box::new(ptr),
+            rhs: Box::new(begin_value),
+        };
+        let slice_ty = self.tables.types.slice_of(sub_ty);
+        Ok(self.build_slice(slice_ty, stmts, len, ptr))
+    }
+
+    // fixed and dynamic arrays are given as slices of their items where a slice is expected
+    pub(super) fn coerce_to_slice(
+        &mut self,
+        expr: utils::TypedExpression,
+        target_ty: ty::Type,
+    ) -> utils::TypedExpression {
+        let target_sub = if let ty::TypeValue::Slice(sub) = *target_ty {
+            sub
+        } else {
+            return expr;
+        };
+        match *utils::rvalue_type(expr.ty) {
+            ty::TypeValue::Array(sub, _) | ty::TypeValue::DynArray(sub) if sub == target_sub => {}
+            _ => return expr,
+        }
+
+        let mut stmts = Vec::new();
+        let (_, ptr, len) = self.spill_sized_array(expr, &mut stmts)
+            .expect("an array has a length");
+        self.build_slice(target_ty, stmts, len, ptr)
+    }
+
+    // evaluate an array, a dynamic array or a slice once, and return its item type, a pointer
+    // to its items and its length, which can both be used several times
+    fn spill_sized_array(
+        &mut self,
+        array: utils::TypedExpression,
+        stmts: &mut Vec<ir::Statement>,
+    ) -> Option<(ty::Type, ir::Expression, ir::Expression)> {
+        let array_ty = utils::rvalue_type(array.ty);
+        if let ty::TypeValue::Array(_, size) = *array_ty {
+            // the items stay in the array, only their address is kept
+            let (array, _) = utils::rvalue_to_lvalue(&self.tables.types, array);
+            let (sub_ty, ptr) = utils::unsure_subscriptable(&self.tables.types, array)?;
+            let ptr_ty = self.tables.types.pointer_of(sub_ty);
+            let ptr_id = self.register_temp_local(ptr_ty);
+            stmts.push(ir::Statement::Expression(utils::build_assign_to_id(ptr_id, ptr)));
+            let len = ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(
+                size as _,
+            )));
+            return Some((sub_ty, utils::rvalue_of_id(ptr_id), len));
+        }
+
+        utils::runtime_sized_item(array_ty)?;
+        let array = utils::lvalue_to_rvalue(array);
+        let array_id = self.register_temp_local(array_ty);
+        stmts.push(ir::Statement::Expression(utils::build_assign_to_id(
+            array_id,
+            array.expr,
+        )));
+        let array_expr = ir::Expression::Value(ir::Value::Local(array_id));
+        let lvalue = utils::TypedExpression {
+            ty: self.tables.types.lvalue_of(array_ty, true),
+            expr: array_expr.clone(),
+        };
+        let (sub_ty, ptr) = utils::unsure_subscriptable(&self.tables.types, lvalue)?;
+        let len = ir::Expression::LValueToRValue(Box::new(ir::Expression::FieldAccess {
+            sub: Box::new(array_expr),
+            index: 0,
+        }));
+        Some((sub_ty, ptr, len))
+    }
+
+    fn build_slice(
+        &mut self,
+        slice_ty: ty::Type,
+        mut stmts: Vec<ir::Statement>,
+        len: ir::Expression,
+        ptr: ir::Expression,
+    ) -> utils::TypedExpression {
+        let res_id = self.register_temp_local(slice_ty);
+        let res_id_expr = ir::Expression::Value(ir::Value::Local(res_id));
+        stmts.push(ir::Statement::Expression(utils::build_assign_to_field(
+            res_id_expr.clone(),
+            0,
+            len,
+        )));
+        stmts.push(ir::Statement::Expression(utils::build_assign_to_field(
+            res_id_expr,
+            1,
+            ptr,
+        )));
+
+        utils::TypedExpression {
+            ty: slice_ty,
+            expr: ir::Expression::Block(Box::new(ir::BlockExpression {
+                stmts,
+                final_expr: utils::rvalue_of_id(res_id),
+            })),
+        }
+    }
+
     pub(super) fn translate_lazyop(
         &mut self,
         lazyop: ast::LazyOperatorKind,
@@ -707,11 +1898,15 @@ impl<'ctxt> FunctionBuilder<'ctxt> {
         rhs: Spanned<ast::Expression>,
         expr_span: Span,
     ) -> TranslationResult<utils::TypedExpression> {
-        let lhs = self.translate_expression(lhs)?;
+        let lhs = self.translate_expression(lhs);
         let lhs = utils::lvalue_to_rvalue(lhs);
-        let rhs = self.translate_expression(rhs)?;
+        let rhs = self.translate_expression(rhs);
         let rhs = utils::lvalue_to_rvalue(rhs);
 
+        if lhs.ty.is_error() || rhs.ty.is_error() {
+            return Ok(utils::error_expression(&self.tables.types));
+        }
+
         let bool_ty = self.tables.types.get_boolean_ty();
         if lhs.ty != bool_ty || rhs.ty != bool_ty {
             return error!(
@@ -1,9 +1,14 @@
+use std::collections::HashSet;
+
 use ast;
 use ty;
 use ir;
 use common;
 use codemap::*;
-use errors::TranslationError;
+use errors::{TranslationError, Warning};
+use interner::Interner;
+use lexer::Lexer;
+use parser;
 
 pub mod tables;
 #[macro_use]
@@ -11,47 +16,84 @@ mod utils;
 mod typeck;
 mod func_trans;
 mod pretrans;
+mod constant;
 
 use self::func_trans::*;
-use self::tables::{Tables, TypeTable};
+use self::tables::{GlobalSymbol, SymbolKind, Tables, TestFunction, TypeTable};
 
 pub type TranslationResult<T> = Result<T, Spanned<TranslationError>>;
+pub type TranslationErrors = Vec<Spanned<TranslationError>>;
+pub type Warnings = Vec<Spanned<Warning>>;
+
+/// The extern function called by a failed assert, provided by the test harness.
+pub const ASSERT_FAILED_FUNCTION: &str = "___assert_failed";
+
+/// The source of the runtime, translated before every program.
+pub const RUNTIME: &str = concat!(
+    include_str!("../../runtime/io.yal"),
+    include_str!("../../runtime/string.yal")
+);
+
+/// The runtime functions behind the string operators.
+pub const STRING_CONCAT_FUNCTION: &str = "stringConcat";
+pub const STRING_COMPARE_FUNCTION: &str = "stringCompare";
+pub const STRING_LENGTH_FUNCTION: &str = "stringLength";
+pub const SUBSTRING_FUNCTION: &str = "substring";
+
+/// Parse and translate the runtime into fresh tables, the start of every compilation.
+pub fn translate_runtime(strings: &mut Interner<String>, tables: &mut Tables) -> ir::Program {
+    let runtime =
+        parser::parse_program(Lexer::new(RUNTIME), strings).expect("runtime parse error");
+    translate_program(tables, runtime, None, &mut Vec::new()).expect("runtime translate error")
+}
 
 pub fn translate_program(
     tables: &mut Tables,
     program: ast::Program,
     runtime: Option<ir::Program>,
-) -> TranslationResult<ir::Program> {
+    warnings: &mut Warnings,
+) -> Result<ir::Program, TranslationErrors> {
+    let mut errors = Vec::new();
     let mut declarations = runtime.map(|r| r.declarations).unwrap_or_default();
     declarations.reserve(program.declarations.len());
 
     let mut functions = Vec::new();
     let mut exfunctions = Vec::new();
     let mut structs = Vec::new();
+    let mut enums = Vec::new();
+    let mut tests = Vec::new();
+    let mut globals = Vec::new();
 
     for decl in program.declarations {
         match decl {
             ast::Declaration::Struct(s) => structs.push(s),
+            ast::Declaration::Enum(e) => enums.push(e),
             ast::Declaration::ExternFunction(exfunc) => exfunctions.push(exfunc),
             ast::Declaration::Function(func) => functions.push(func),
+            ast::Declaration::Test(test) => tests.push(test),
+            ast::Declaration::Global(global) => globals.push(global),
         }
     }
 
     // type building TODO check for cycles
-    pretrans::translate_types(tables, structs)?;
+    pretrans::translate_types(tables, structs, enums, &mut errors);
 
     // translate extern functions and collect names
     for exfunc in exfunctions {
+        if let Some(ref mut index) = tables.index {
+            index.functions.insert(exfunc.name.clone(), exfunc.span);
+        }
         let func_ty = exfunc.get_type();
-        let func_ty = translate_function_type(&mut tables.types, func_ty)?;
+        let func_ty = translate_function_type(&mut tables.types, func_ty, &mut errors);
         if tables
             .globals
             .register_function(exfunc.name.clone(), func_ty.clone())
         {
-            return error!(
+            errors.push(Spanned::new(
                 TranslationError::FunctionAlreadyDefined(exfunc.name.clone()),
-                exfunc.span
-            );
+                exfunc.span,
+            ));
+            continue;
         }
 
         declarations.push(ir::Declaration::ExternFunction(ir::ExternFunction {
@@ -63,73 +105,314 @@ pub fn translate_program(
 
     // collect names for local functions
     for func in &functions {
+        if let Some(ref mut index) = tables.index {
+            index.functions.insert(func.name.clone(), func.span);
+        }
         let func_ty = func.get_type();
-        let func_ty = translate_function_type(&mut tables.types, func_ty)?;
+        let func_ty = translate_function_type(&mut tables.types, func_ty, &mut errors);
         if tables.globals.register_function(func.name.clone(), func_ty) {
-            return error!(
+            errors.push(Spanned::new(
                 TranslationError::FunctionAlreadyDefined(func.name.clone()),
-                func.span
-            );
+                func.span,
+            ));
+        }
+    }
+
+    // the globals are translated before the functions that use them
+    for global in globals {
+        if let Some(global) = translate_global(tables, global, &mut errors) {
+            declarations.push(ir::Declaration::GlobalVariable(global));
         }
     }
 
-    // translate local functions
+    let function_names: Vec<_> = functions
+        .iter()
+        .map(|func| (func.name.clone(), func.span))
+        .collect();
+
+    // translate local functions, each one reports its errors independently
     for func in functions {
-        declarations.push(ir::Declaration::Function(translate_function(tables, func)?))
+        let func = translate_function(tables, func, &mut errors, warnings, false);
+        declarations.push(ir::Declaration::Function(func))
+    }
+
+    // the tests are checked by every build, but only kept when they are run
+    let mut test_names = HashSet::new();
+    for test in tests {
+        let (name, span) = (test.name.clone(), test.span);
+        if !test_names.insert(name.clone()) {
+            errors.push(Spanned::new(
+                TranslationError::FunctionAlreadyDefined(name.clone()),
+                span,
+            ));
+        }
+        let returns_value = match test.return_ty.inner {
+            ast::Type::Void => false,
+            _ => true,
+        };
+        if !test.parameters.is_empty() || returns_value {
+            errors.push(Spanned::new(TranslationError::TestWrongType, span));
+        }
+
+        let mut func = translate_function(tables, test, &mut errors, warnings, true);
+        if let Some(ref mut tests) = tables.tests {
+            func.name = format!("___test_{}", name);
+            tests.push(TestFunction {
+                name,
+                function_name: func.name.clone(),
+                span,
+            });
+            declarations.push(ir::Declaration::Function(func));
+        }
+    }
+
+    if tables.tests.is_some() && !test_names.is_empty() {
+        let string_ty = tables.types.get_string_ty();
+        let int_ty = tables.types.get_int_ty();
+        declarations.push(ir::Declaration::ExternFunction(ir::ExternFunction {
+            ty: ty::FunctionType {
+                return_ty: tables.types.get_void_ty(),
+                parameters_ty: vec![string_ty, int_ty],
+                is_vararg: false,
+            },
+            name: ASSERT_FAILED_FUNCTION.to_string(),
+            span: Span::dummy(),
+        }));
+    }
+
+    for (name, span) in function_names {
+        if name != "main" && !name.starts_with('_') && !tables.globals.is_used(&name) {
+            warnings.push(Spanned::new(Warning::UnusedFunction(name), span));
+        }
     }
+    warnings.sort_by_key(|warning| warning.span.start);
 
-    Ok(ir::Program { declarations })
+    if errors.is_empty() {
+        Ok(ir::Program { declarations })
+    } else {
+        // the passes over the program report errors out of order
+        errors.sort_by_key(|err| err.span.start);
+        Err(errors)
+    }
+}
+
+// a global with an invalid value is still registered, to avoid errors where it is used
+fn translate_global(
+    tables: &mut Tables,
+    global: ast::Global,
+    errors: &mut TranslationErrors,
+) -> Option<ir::GlobalVariable> {
+    let expected = global.ty.map(|ty| {
+        if let Some(ref mut index) = tables.index {
+            index.add_type_references(&ty);
+        }
+        translate_type(&mut tables.types, ty, false, errors)
+    });
+
+    let value = match constant::translate_constant(tables, global.value, expected) {
+        Ok((ty, value)) => Some((expected.unwrap_or(ty), value)),
+        Err(err) => {
+            errors.push(err);
+            None
+        }
+    };
+    let ty = value
+        .as_ref()
+        .map(|&(ty, _)| ty)
+        .or(expected)
+        .unwrap_or_else(|| tables.types.get_error_ty());
+
+    let symbol = GlobalSymbol {
+        ty,
+        is_const: global.is_const,
+        span: global.span,
+    };
+    if tables
+        .globals
+        .register_variable(global.name.clone(), symbol)
+    {
+        errors.push(Spanned::new(
+            TranslationError::GlobalAlreadyDefined(global.name),
+            global.span,
+        ));
+        return None;
+    }
+
+    let (name, is_const, span) = (global.name, global.is_const, global.span);
+    if let Some((_, ref value)) = value {
+        if is_const {
+            tables.globals.register_constant(name.clone(), value.clone());
+        }
+    }
+    value.map(|(ty, value)| ir::GlobalVariable {
+        ty,
+        name,
+        value,
+        is_const,
+        span,
+    })
 }
 
 fn translate_function(
     tables: &mut Tables,
     function: ast::Function,
-) -> TranslationResult<ir::Function> {
+    errors: &mut TranslationErrors,
+    warnings: &mut Warnings,
+    is_test: bool,
+) -> ir::Function {
     tables.new_locals();
     tables.locals.begin_scope();
 
+    // the errors in the signature were reported when the function was registered
+    let mut signature_errors = Vec::new();
+
     let mut parameters = Vec::with_capacity(function.parameters.len());
+    if let Some(ref mut index) = tables.index {
+        index.add_type_references(&function.return_ty);
+        for &(_, ref param_ty) in &function.parameters {
+            index.add_type_references(param_ty);
+        }
+    }
+
     for (param_name, param_ty) in function.parameters {
-        let param_ty = translate_type(&mut tables.types, param_ty, false)?;
-        if let Some(id) = tables.locals.register_local(param_name.clone(), param_ty) {
+        let param_span = param_ty.span;
+        let param_ty = translate_type(&mut tables.types, param_ty, false, &mut signature_errors);
+        if let Some(id) = tables.locals.register_local(
+            param_name.clone(),
+            param_ty,
+            SymbolKind::Parameter,
+            param_span,
+        ) {
             parameters.push((param_ty, id));
         } else {
-            return error!(
+            errors.push(Spanned::new(
                 TranslationError::ParameterAlreadyDefined(param_name),
-                function.span
-            );
+                function.span,
+            ));
         }
     }
 
     tables.locals.begin_scope();
 
-    let func_return_ty = translate_type(&mut tables.types, function.return_ty, true)?;
+    let func_return_ty = translate_type(
+        &mut tables.types,
+        function.return_ty,
+        true,
+        &mut signature_errors,
+    );
 
     let (mut body, var_declarations) = {
         let mut func_builder = FunctionBuilder::new(tables, func_return_ty);
-        let mut body = func_builder.translate_block_statement(function.body)?;
+        func_builder.in_test = is_test;
+        let body = func_builder.translate_block_statement(function.body.inner);
+        errors.append(&mut func_builder.errors);
+        warnings.append(&mut func_builder.warnings);
         (body, func_builder.var_declarations)
     };
 
     if !utils::check_return_paths(&body) {
-        if func_return_ty != tables.types.get_void_ty() {
-            return error!(TranslationError::NotAllPathsReturn, function.span);
-        } else {
+        if func_return_ty == tables.types.get_void_ty() {
             body.push(ir::Statement::Return(None)); // we add a return void
+        } else if !func_return_ty.is_error() {
+            errors.push(Spanned::new(
+                TranslationError::NotAllPathsReturn,
+                function.span,
+            ));
         }
     }
 
     tables.locals.end_scope();
     tables.locals.end_scope();
 
-    Ok(ir::Function {
+    for (name, symbol) in tables.locals.take_unused() {
+        if name.starts_with('_') {
+            continue;
+        }
+        let warning = match symbol.kind {
+            SymbolKind::Parameter => Warning::UnusedParameter(name),
+            SymbolKind::Local => Warning::UnusedVariable(name),
+        };
+        warnings.push(Spanned::new(warning, symbol.span));
+    }
+
+    ir::Function {
         return_ty: func_return_ty,
         name: function.name,
         parameters,
         var_declarations,
         body,
         span: function.span,
-    })
+    }
+}
+
+/// Translate the statements of a REPL entry into a function without parameters,
+/// returning the value of the final expression if there is one, along with the globals
+/// declared by its top level `let`s.
+pub fn translate_repl_entry(
+    tables: &mut Tables,
+    name: String,
+    statements: Vec<Spanned<ast::Statement>>,
+    final_expr: Option<Spanned<ast::Expression>>,
+) -> Result<(ir::Function, Vec<ir::GlobalVariable>), TranslationErrors> {
+    tables.new_locals();
+    tables.locals.begin_scope();
+
+    let void_ty = tables.types.get_void_ty();
+    let mut globals = Vec::new();
+    let (body, return_ty, var_declarations, mut errors) = {
+        let mut func_builder = FunctionBuilder::new(tables, void_ty);
+        let mut errors = Vec::new();
+        // the final expression shares the scope of the statements
+        let mut body = Vec::new();
+        for stmt in statements {
+            let Spanned { inner, span } = stmt;
+            if let ast::Statement::Let(ast::LetStatement { ty, name, value }) = inner {
+                match func_builder.translate_session_global(ty, name, value, span) {
+                    Ok((global, assign)) => {
+                        globals.push(global);
+                        body.push(assign);
+                    }
+                    Err(err) => errors.push(err),
+                }
+            } else {
+                body.push(func_builder.translate_statement(Spanned::new(inner, span)));
+            }
+        }
+
+        let return_ty = if let Some(expr) = final_expr {
+            let expr = func_builder.translate_expression(expr);
+            let expr = utils::lvalue_to_rvalue(expr);
+            if expr.ty == void_ty {
+                body.push(ir::Statement::Expression(expr.expr));
+                body.push(ir::Statement::Return(None));
+            } else {
+                body.push(ir::Statement::Return(Some(expr.expr)));
+            }
+            expr.ty
+        } else {
+            body.push(ir::Statement::Return(None));
+            void_ty
+        };
+        errors.append(&mut func_builder.errors);
+        (body, return_ty, func_builder.var_declarations, errors)
+    };
+
+    tables.locals.end_scope();
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|err| err.span.start);
+        return Err(errors);
+    }
+
+    let function = ir::Function {
+        return_ty,
+        name,
+        parameters: Vec::new(),
+        var_declarations,
+        body,
+        span: Span::dummy(),
+    };
+    Ok((function, globals))
 }
 
 pub fn check_if_main_declaration(tables: &Tables, prog: &ir::Program) -> TranslationResult<()> {
@@ -139,6 +422,7 @@ pub fn check_if_main_declaration(tables: &Tables, prog: &ir::Program) -> Transla
                 (&exfunc.name, exfunc.ty.clone(), exfunc.span)
             }
             ir::Declaration::Function(ref func) => (&func.name, func.get_type(), func.span),
+            ir::Declaration::GlobalVariable(_) => continue,
         };
 
         if name == "main" {
@@ -154,66 +438,80 @@ pub fn check_if_main_declaration(tables: &Tables, prog: &ir::Program) -> Transla
     error!(TranslationError::NoMain, Span::dummy())
 }
 
+// an invalid type is reported and replaced by the error type
 fn translate_type(
     typectxt: &mut TypeTable,
     ty: Spanned<ast::Type>,
     void: bool,
-) -> TranslationResult<ty::Type> {
-    match ty.inner {
+    errors: &mut TranslationErrors,
+) -> ty::Type {
+    let ty_span = ty.span;
+    let error = match ty.inner {
         ast::Type::Void => {
             if void {
-                Ok(typectxt.get_void_ty())
+                return typectxt.get_void_ty();
             } else {
-                error!(TranslationError::UnexpectedVoid, ty.span)
+                TranslationError::UnexpectedVoid
             }
         }
         ast::Type::Identifier(id) => {
-            let ty_span = ty.span;
             if let Some(ty) = typectxt.lookup_type(&id) {
                 if !void && ty == typectxt.get_void_ty() {
-                    error!(TranslationError::UnexpectedVoid, ty_span)
+                    TranslationError::UnexpectedVoid
                 } else {
-                    Ok(ty)
+                    return ty;
                 }
             } else {
-                error!(TranslationError::UndefinedType(id), ty_span)
+                TranslationError::UndefinedType(id)
             }
         }
         ast::Type::Pointer(sub_ty) => {
-            let sub = translate_type(typectxt, *sub_ty, true)?;
-            Ok(typectxt.pointer_of(sub))
+            let sub = translate_type(typectxt, *sub_ty, true, errors);
+            return typectxt.pointer_of(sub);
         }
         ast::Type::Array(sub_ty, size) => {
-            let sub = translate_type(typectxt, *sub_ty, false)?;
-            Ok(typectxt.array_of(sub, size))
+            let sub = translate_type(typectxt, *sub_ty, false, errors);
+            return typectxt.array_of(sub, size);
+        }
+        ast::Type::DynArray(sub_ty) => {
+            let sub = translate_type(typectxt, *sub_ty, false, errors);
+            return typectxt.dyn_array_of(sub);
+        }
+        ast::Type::Slice(sub_ty) => {
+            let sub = translate_type(typectxt, *sub_ty, false, errors);
+            return typectxt.slice_of(sub);
         }
         ast::Type::Function(func_ty) => {
-            let func_ty = translate_function_type(typectxt, *func_ty)?;
-            Ok(typectxt.function_of(func_ty))
+            let func_ty = translate_function_type(typectxt, *func_ty, errors);
+            return typectxt.function_of(func_ty);
         }
         ast::Type::Tuple(types) => {
             let types = types
                 .into_iter()
-                .map(|ty| translate_type(typectxt, ty, false))
-                .collect::<Result<Vec<_>, _>>()?;
-            Ok(typectxt.tuple_of(types))
+                .map(|ty| translate_type(typectxt, ty, false, errors))
+                .collect();
+            return typectxt.tuple_of(types);
         }
-    }
+    };
+
+    errors.push(Spanned::new(error, ty_span));
+    typectxt.get_error_ty()
 }
 
 fn translate_function_type(
     typectxt: &mut TypeTable,
     func_ty: ast::FunctionType,
-) -> TranslationResult<ty::FunctionType> {
-    let return_ty = translate_type(typectxt, func_ty.return_ty, true)?;
+    errors: &mut TranslationErrors,
+) -> ty::FunctionType {
+    let return_ty = translate_type(typectxt, func_ty.return_ty, true, errors);
     let parameters_ty = func_ty
         .parameters_ty
         .into_iter()
-        .map(|ty| translate_type(typectxt, ty, false))
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(ty::FunctionType {
+        .map(|ty| translate_type(typectxt, ty, false, errors))
+        .collect();
+    ty::FunctionType {
         return_ty,
         parameters_ty,
         is_vararg: func_ty.is_vararg,
-    })
+    }
 }
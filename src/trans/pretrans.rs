@@ -1,31 +1,51 @@
 use ast;
 use ty;
-use codemap::Spanned;
+use codemap::{Span, Spanned};
 use errors::TranslationError;
-use trans::{self, TranslationResult};
+use trans::{self, TranslationErrors};
 use trans::tables::Tables;
 use std::collections::HashSet;
 
 pub(super) fn translate_types(
     tables: &mut Tables,
     structs: Vec<ast::Struct>,
-) -> TranslationResult<()> {
-    // collect all names
-    for s in &structs {
-        if tables.types.pre_register_struct_type(s.name.clone()) {
-            return error!(TranslationError::TypeAlreadyDefined(s.name.clone()), s.span);
+    enums: Vec<ast::Enum>,
+    errors: &mut TranslationErrors,
+) {
+    // collect all names, a redefinition is reported and left out
+    let mut defined_structs = Vec::with_capacity(structs.len());
+    for s in structs {
+        if pre_register_type(tables, &s.name, s.span, errors) {
+            defined_structs.push(s);
+        }
+    }
+    let mut defined_enums = Vec::with_capacity(enums.len());
+    for e in enums {
+        if pre_register_type(tables, &e.name, e.span, errors) {
+            defined_enums.push(e);
         }
     }
 
+    for e in defined_enums {
+        translate_enum(tables, e, errors);
+    }
+
     // really build structs
-    for s in structs {
+    for s in defined_structs {
         let mut fields_set = HashSet::new();
         let mut fields = Vec::new();
         for &(ref name, ref aty) in &s.fields {
-            let ty = trans::translate_type(&mut tables.types, aty.clone(), false)?;
+            if let Some(ref mut index) = tables.index {
+                index.add_type_references(aty);
+            }
+            let ty = trans::translate_type(&mut tables.types, aty.clone(), false, errors);
             let field_name = name.inner.clone();
             if !fields_set.insert(field_name.clone()) {
-                return error!(TranslationError::FieldAlreadyDefined(field_name), name.span);
+                errors.push(Spanned::new(
+                    TranslationError::FieldAlreadyDefined(field_name),
+                    name.span,
+                ));
+                continue;
             }
             fields.push((field_name, ty));
         }
@@ -35,9 +55,101 @@ pub(super) fn translate_types(
             fields,
         };
         if tables.types.register_struct_type(&s.name, s_tv) {
-            return error!(TranslationError::StructCycle(s.name), s.span);
+            errors.push(Spanned::new(TranslationError::StructCycle(s.name), s.span));
+        }
+    }
+}
+
+// false if the name is already defined
+fn pre_register_type(
+    tables: &mut Tables,
+    name: &str,
+    span: Span,
+    errors: &mut TranslationErrors,
+) -> bool {
+    if tables.types.pre_register_type(name.to_string()) {
+        errors.push(Spanned::new(
+            TranslationError::TypeAlreadyDefined(name.to_string()),
+            span,
+        ));
+        false
+    } else {
+        if let Some(ref mut index) = tables.index {
+            index.types.insert(name.to_string(), span);
+        }
+        true
+    }
+}
+
+fn translate_enum(tables: &mut Tables, e: ast::Enum, errors: &mut TranslationErrors) {
+    let int_ty = tables.types.get_int_ty();
+    let mut variants: Vec<ty::Variant> = Vec::with_capacity(e.variants.len());
+    let mut next = Some(0);
+    for variant in e.variants {
+        if variants.iter().any(|v| v.name == variant.name.inner) {
+            errors.push(Spanned::new(
+                TranslationError::VariantAlreadyDefined(variant.name.inner),
+                variant.name.span,
+            ));
+            continue;
         }
+
+        // the discriminants are `int`s, even the implicit ones
+        let discriminant = match variant.discriminant {
+            Some(value) if value.inner < i64::from(i32::min_value())
+                || value.inner > i64::from(i32::max_value()) =>
+            {
+                errors.push(Spanned::new(
+                    TranslationError::LiteralOutOfRange(int_ty),
+                    value.span,
+                ));
+                continue;
+            }
+            Some(value) => value.inner as i32,
+            None => if let Some(next) = next {
+                next
+            } else {
+                errors.push(Spanned::new(
+                    TranslationError::LiteralOutOfRange(int_ty),
+                    variant.name.span,
+                ));
+                continue;
+            },
+        };
+        next = discriminant.checked_add(1);
+
+        let mut payload = Vec::with_capacity(variant.payload.len());
+        for aty in variant.payload {
+            if let Some(ref mut index) = tables.index {
+                index.add_type_references(&aty);
+            }
+            payload.push(trans::translate_type(&mut tables.types, aty, false, errors));
+        }
+
+        // the tag alone must tell how to read the payload
+        let shared = variants.iter().any(|v| {
+            v.discriminant == discriminant && (!v.payload.is_empty() || !payload.is_empty())
+        });
+        if shared {
+            errors.push(Spanned::new(
+                TranslationError::SharedDiscriminant(variant.name.inner),
+                variant.name.span,
+            ));
+            continue;
+        }
+
+        variants.push(ty::Variant {
+            name: variant.name.inner,
+            discriminant,
+            payload,
+        });
     }
 
-    Ok(())
+    let e_tv = ty::EnumTypeValue {
+        name: e.name.clone(),
+        variants,
+    };
+    if tables.types.register_enum_type(&e.name, e_tv) {
+        errors.push(Spanned::new(TranslationError::StructCycle(e.name), e.span));
+    }
 }
@@ -1,44 +1,212 @@
+use std::collections::HashSet;
 use std::collections::hash_map::{Entry, HashMap};
 
+use ast;
 use ty;
-use ir::IdentifierId;
+use ir::{self, IdentifierId};
+use codemap::{Span, Spanned};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Tables {
     pub globals: GlobalsTable,
     pub locals: SymbolTable,
     pub types: TypeTable,
+    pub index: Option<SourceIndex>, // only filled for the language server
+    pub tests: Option<Vec<TestFunction>>, // the tests are only kept for `yalc --test`
+    line_starts: Vec<usize>, // of the translated source, for the runtime errors
 }
 
 impl Tables {
     pub fn new_locals(&mut self) {
         self.locals = SymbolTable::new();
     }
+
+    /// Remember where the lines of the source about to be translated start, the runtime
+    /// errors name the line they come from.
+    pub fn set_source(&mut self, input: &str) {
+        self.line_starts = ::std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+    }
+
+    // lines are 1-based, 0 if the source is unknown
+    pub fn line_of(&self, pos: usize) -> usize {
+        match self.line_starts.binary_search(&pos) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct GlobalsTable(HashMap<String, ty::FunctionType>);
+pub struct GlobalsTable {
+    functions: HashMap<String, ty::FunctionType>,
+    variables: HashMap<String, GlobalSymbol>,
+    constants: HashMap<String, ir::Constant>,
+    used: HashSet<String>,
+}
 
 impl GlobalsTable {
     pub fn register_function(&mut self, name: String, ty: ty::FunctionType) -> bool {
-        self.0.insert(name, ty).is_some()
+        self.functions.insert(name, ty).is_some()
     }
 
     pub fn lookup_function(&self, name: &str) -> Option<&ty::FunctionType> {
-        self.0.get(name)
+        self.functions.get(name)
+    }
+
+    // like lookup_function, but the function is marked as used
+    pub fn use_function(&mut self, name: &str) -> Option<&ty::FunctionType> {
+        if self.functions.contains_key(name) {
+            self.used.insert(name.to_string());
+        }
+        self.functions.get(name)
+    }
+
+    pub fn is_used(&self, name: &str) -> bool {
+        self.used.contains(name)
+    }
+
+    // return true if a function or another global has the same name
+    pub fn register_variable(&mut self, name: String, symbol: GlobalSymbol) -> bool {
+        // the first definition is kept
+        if self.functions.contains_key(&name) || self.variables.contains_key(&name) {
+            return true;
+        }
+        self.variables.insert(name, symbol);
+        false
+    }
+
+    pub fn lookup_variable(&self, name: &str) -> Option<&GlobalSymbol> {
+        self.variables.get(name)
+    }
+
+    // the value of a const, for the initializers of the globals declared after it
+    pub fn register_constant(&mut self, name: String, value: ir::Constant) {
+        self.constants.insert(name, value);
+    }
+
+    pub fn lookup_constant(&self, name: &str) -> Option<&ir::Constant> {
+        self.constants.get(name)
+    }
+}
+
+/// A global variable, or a constant if it can't be assigned.
+#[derive(Debug, Clone)]
+pub struct GlobalSymbol {
+    pub ty: ty::Type,
+    pub is_const: bool,
+    pub span: Span,
+}
+
+/// A test kept in the program, under a generated name that can't clash with a function.
+#[derive(Debug, Clone)]
+pub struct TestFunction {
+    pub name: String,
+    pub function_name: String,
+    pub span: Span,
+}
+
+/// Where the expressions and the names of a program are, with their types and definitions.
+#[derive(Debug, Clone, Default)]
+pub struct SourceIndex {
+    pub expressions: Vec<(Span, ty::Type)>,
+    pub references: Vec<(Span, Span)>, // use, definition
+    pub functions: HashMap<String, Span>,
+    pub types: HashMap<String, Span>, // the structs and the enums
+}
+
+impl SourceIndex {
+    pub fn add_expression(&mut self, span: Span, ty: ty::Type) {
+        // the fake nodes built by the translation have no real location
+        if span != Span::dummy() && !ty.is_error() {
+            self.expressions.push((span, ty));
+        }
+    }
+
+    pub fn add_reference(&mut self, span: Span, definition: Span) {
+        if span != Span::dummy() {
+            self.references.push((span, definition));
+        }
+    }
+
+    pub fn add_function_reference(&mut self, span: Span, name: &str) {
+        if let Some(&definition) = self.functions.get(name) {
+            self.add_reference(span, definition);
+        }
+    }
+
+    pub fn add_type_references(&mut self, ty: &Spanned<ast::Type>) {
+        match ty.inner {
+            ast::Type::Identifier(ref name) => {
+                if let Some(&definition) = self.types.get(name) {
+                    self.add_reference(ty.span, definition);
+                }
+            }
+            ast::Type::Pointer(ref sub)
+            | ast::Type::Array(ref sub, _)
+            | ast::Type::DynArray(ref sub)
+            | ast::Type::Slice(ref sub) => self.add_type_references(sub),
+            ast::Type::Function(ref func_ty) => {
+                self.add_type_references(&func_ty.return_ty);
+                for ty in &func_ty.parameters_ty {
+                    self.add_type_references(ty);
+                }
+            }
+            ast::Type::Tuple(ref types) => for ty in types {
+                self.add_type_references(ty);
+            },
+            ast::Type::Void => {}
+        }
+    }
+
+    // the innermost expression around the position
+    pub fn expression_at(&self, pos: usize) -> Option<(Span, ty::Type)> {
+        innermost(&self.expressions, pos)
+    }
+
+    pub fn definition_at(&self, pos: usize) -> Option<(Span, Span)> {
+        innermost(&self.references, pos)
+    }
+
+    // the innermost expression ending right before the position, like the left side of a `.`
+    pub fn expression_ending_at(&self, pos: usize) -> Option<ty::Type> {
+        self.expressions
+            .iter()
+            .filter(|&&(span, _)| span.end == pos)
+            .max_by_key(|&&(span, _)| span.start)
+            .map(|&(_, ty)| ty)
     }
 }
 
+fn innermost<T: Copy>(entries: &[(Span, T)], pos: usize) -> Option<(Span, T)> {
+    entries
+        .iter()
+        .filter(|&&(span, _)| span.start <= pos && pos < span.end)
+        .min_by_key(|&&(span, _)| span.end - span.start)
+        .cloned()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Parameter,
+    Local,
+}
+
 #[derive(Debug, Clone)]
 pub struct Symbol {
     pub ty: ty::Type,
     pub id: IdentifierId,
+    pub kind: SymbolKind,
+    pub span: Span,
+    pub used: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct SymbolTable {
     id_counter: usize,
     scopes: Vec<HashMap<String, Symbol>>,
+    unused: Vec<(String, Symbol)>,
 }
 
 impl Default for SymbolTable {
@@ -52,6 +220,7 @@ impl SymbolTable {
         SymbolTable {
             id_counter: 0,
             scopes: Vec::new(),
+            unused: Vec::new(),
         }
     }
 
@@ -60,7 +229,15 @@ impl SymbolTable {
     }
 
     pub fn end_scope(&mut self) {
-        self.scopes.pop();
+        if let Some(scope) = self.scopes.pop() {
+            self.unused
+                .extend(scope.into_iter().filter(|&(_, ref symbol)| !symbol.used));
+        }
+    }
+
+    // the symbols never used in the scopes that ended since the last call
+    pub fn take_unused(&mut self) -> Vec<(String, Symbol)> {
+        ::std::mem::replace(&mut self.unused, Vec::new())
     }
 
     pub fn new_identifier_id(&mut self) -> IdentifierId {
@@ -70,11 +247,20 @@ impl SymbolTable {
     }
 
     // return None if the local was already defined
-    pub fn register_local(&mut self, name: String, ty: ty::Type) -> Option<IdentifierId> {
+    pub fn register_local(
+        &mut self,
+        name: String,
+        ty: ty::Type,
+        kind: SymbolKind,
+        span: Span,
+    ) -> Option<IdentifierId> {
         let identifier_id = self.new_identifier_id();
         let symbol = Symbol {
             ty,
             id: identifier_id,
+            kind,
+            span,
+            used: false,
         };
 
         if self.scopes
@@ -97,6 +283,17 @@ impl SymbolTable {
         }
         None
     }
+
+    // like lookup_local, but the local is marked as used
+    pub fn use_local(&mut self, name: &str) -> Option<&Symbol> {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(t) = scope.get_mut(name) {
+                t.used = true;
+                return Some(t);
+            }
+        }
+        None
+    }
 }
 
 use context::Context;
@@ -104,6 +301,7 @@ lazy_static! {
     static ref CONTEXT: Context = Context::new();
 }
 
+#[derive(Clone)]
 pub struct TypeTable {
     names: HashMap<String, ty::Type>,
 }
@@ -144,13 +342,13 @@ impl TypeTable {
         table.register_type("double".to_string(), ty::TypeValue::Double);
         table.register_type("boolean".to_string(), ty::TypeValue::Boolean);
         table.register_type("string".to_string(), ty::TypeValue::String);
+        table.register_type("char".to_string(), ty::TypeValue::Char);
+        for &kind in &ty::IntKind::SIZED {
+            table.register_type(kind.name().to_string(), ty::TypeValue::SizedInt(kind));
+        }
         table
     }
 
-    fn append_type(&self, tv: ty::TypeValue) -> ty::Type {
-        CONTEXT.get_type(tv)
-    }
-
     fn register_type(&mut self, name: String, tv: ty::TypeValue) {
         // force the insert
         let ty = CONTEXT.get_type(tv);
@@ -166,10 +364,24 @@ impl TypeTable {
     get_builtin_type!(get_double_ty, "double");
     get_builtin_type!(get_boolean_ty, "boolean");
     get_builtin_type!(get_string_ty, "string");
+    get_builtin_type!(get_char_ty, "char");
 
-    pub fn pre_register_struct_type(&mut self, name: String) -> bool {
+    pub fn get_integer_ty(&self, kind: ty::IntKind) -> ty::Type {
+        if kind == ty::IntKind::I32 {
+            self.get_int_ty()
+        } else {
+            self.lookup_type(kind.name()).unwrap()
+        }
+    }
+
+    pub fn get_error_ty(&self) -> ty::Type {
+        // not registered by name, it can't be written in a program
+        CONTEXT.get_type(ty::TypeValue::Error)
+    }
+
+    pub fn pre_register_type(&mut self, name: String) -> bool {
         // true if a type with the same name is already defined
-        let ty = self.append_type(ty::TypeValue::Incomplete);
+        let ty = CONTEXT.alloc_unique_type(ty::TypeValue::Incomplete);
         if let Entry::Vacant(o) = self.names.entry(name) {
             o.insert(ty);
             false
@@ -181,7 +393,7 @@ impl TypeTable {
     pub fn register_struct_type(&mut self, name: &str, struct_tv: ty::StructTypeValue) -> bool {
         // return true if struct cycle
         let mut ty = self.lookup_type(name).unwrap();
-        if has_cycles(&struct_tv, ty) {
+        if struct_tv.fields.iter().any(|&(_, field_ty)| contains_type(field_ty, ty)) {
             true
         } else {
             *ty = ty::TypeValue::Struct(CONTEXT.alloc_struct_type(struct_tv));
@@ -189,6 +401,18 @@ impl TypeTable {
         }
     }
 
+    pub fn register_enum_type(&mut self, name: &str, enum_tv: ty::EnumTypeValue) -> bool {
+        // return true if enum cycle
+        let mut ty = self.lookup_type(name).unwrap();
+        let mut payloads = enum_tv.variants.iter().flat_map(|v| &v.payload);
+        if payloads.any(|&payload_ty| contains_type(payload_ty, ty)) {
+            true
+        } else {
+            *ty = ty::TypeValue::Enum(CONTEXT.alloc_enum_type(enum_tv));
+            false
+        }
+    }
+
     pub fn lvalue_of(&self, sub_ty: ty::Type, assignable: bool) -> ty::Type {
         let tv = ty::TypeValue::LValue(sub_ty, assignable);
         CONTEXT.get_type(tv)
@@ -204,6 +428,16 @@ impl TypeTable {
         CONTEXT.get_type(tv)
     }
 
+    pub fn dyn_array_of(&self, sub_ty: ty::Type) -> ty::Type {
+        let tv = ty::TypeValue::DynArray(sub_ty);
+        CONTEXT.get_type(tv)
+    }
+
+    pub fn slice_of(&self, sub_ty: ty::Type) -> ty::Type {
+        let tv = ty::TypeValue::Slice(sub_ty);
+        CONTEXT.get_type(tv)
+    }
+
     pub fn function_of(&self, func_ty: ty::FunctionType) -> ty::Type {
         let tv = ty::TypeValue::FunctionPtr(func_ty);
         CONTEXT.get_type(tv)
@@ -215,17 +449,24 @@ impl TypeTable {
     }
 }
 
-fn has_cycles(struct_tv: &ty::StructTypeValue, ty: ty::Type) -> bool {
-    for &(_, field_ty) in &struct_tv.fields {
-        if field_ty == ty {
-            return true;
-        }
+// true if a value of `container` holds a value of `ty` inline
+fn contains_type(container: ty::Type, ty: ty::Type) -> bool {
+    if container == ty {
+        return true;
+    }
 
-        if let ty::TypeValue::Struct(ref stv) = *field_ty {
-            if has_cycles(stv, ty) {
-                return true;
-            }
-        }
+    match *container {
+        ty::TypeValue::Struct(ref stv) => stv
+            .fields
+            .iter()
+            .any(|&(_, field_ty)| contains_type(field_ty, ty)),
+        ty::TypeValue::Enum(ref etv) => etv
+            .variants
+            .iter()
+            .flat_map(|v| &v.payload)
+            .any(|&payload_ty| contains_type(payload_ty, ty)),
+        ty::TypeValue::Tuple(ref types) => types.iter().any(|&sub| contains_type(sub, ty)),
+        ty::TypeValue::Array(sub, _) => contains_type(sub, ty),
+        _ => false,
     }
-    false
 }
@@ -13,111 +13,202 @@ pub fn binop_typeck(
     let double_ty = type_ctxt.get_double_ty();
     let bool_ty = type_ctxt.get_boolean_ty();
 
+    // the integers of every width share the operators, both sides have the same type
+    if let Some(kind) = lhs.int_kind() {
+        if lhs == rhs {
+            return Some(int_binop_typeck(binop, lhs, kind.is_signed(), bool_ty));
+        }
+    }
+
     use ast::BinaryOperatorKind::*;
     match (binop, &*lhs, &*rhs) {
-        (Plus, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((int_ty, ir::BinaryOperatorKind::IntPlus))
-        }
         (Plus, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((double_ty, ir::BinaryOperatorKind::DoublePlus))
         }
-        (Minus, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((int_ty, ir::BinaryOperatorKind::IntMinus))
-        }
         (Minus, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((double_ty, ir::BinaryOperatorKind::DoubleMinus))
         }
-        (Multiply, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((int_ty, ir::BinaryOperatorKind::IntMultiply))
-        }
         (Multiply, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((double_ty, ir::BinaryOperatorKind::DoubleMultiply))
         }
-        (Divide, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((int_ty, ir::BinaryOperatorKind::IntDivide))
-        }
         (Divide, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((double_ty, ir::BinaryOperatorKind::DoubleDivide))
         }
-        (Modulo, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((int_ty, ir::BinaryOperatorKind::IntModulo))
-        }
 
-        (Equal, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((bool_ty, ir::BinaryOperatorKind::IntEqual))
-        }
         (Equal, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((bool_ty, ir::BinaryOperatorKind::DoubleEqual))
         }
         (Equal, &ty::TypeValue::Boolean, &ty::TypeValue::Boolean) => {
             Some((bool_ty, ir::BinaryOperatorKind::BooleanEqual))
         }
-
-        (NotEqual, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((bool_ty, ir::BinaryOperatorKind::IntNotEqual))
+        (Equal, &ty::TypeValue::Char, &ty::TypeValue::Char) => {
+            Some((bool_ty, ir::BinaryOperatorKind::IntEqual))
         }
+
         (NotEqual, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((bool_ty, ir::BinaryOperatorKind::DoubleNotEqual))
         }
         (NotEqual, &ty::TypeValue::Boolean, &ty::TypeValue::Boolean) => {
             Some((bool_ty, ir::BinaryOperatorKind::BooleanNotEqual))
         }
-
-        (Less, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((bool_ty, ir::BinaryOperatorKind::IntLess))
+        (NotEqual, &ty::TypeValue::Char, &ty::TypeValue::Char) => {
+            Some((bool_ty, ir::BinaryOperatorKind::IntNotEqual))
         }
+
         (Less, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((bool_ty, ir::BinaryOperatorKind::DoubleLess))
         }
-
-        (LessEqual, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((bool_ty, ir::BinaryOperatorKind::IntLessEqual))
+        (Less, &ty::TypeValue::Char, &ty::TypeValue::Char) => {
+            Some((bool_ty, ir::BinaryOperatorKind::UIntLess))
         }
+
         (LessEqual, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((bool_ty, ir::BinaryOperatorKind::DoubleLessEqual))
         }
-
-        (Greater, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((bool_ty, ir::BinaryOperatorKind::IntGreater))
+        (LessEqual, &ty::TypeValue::Char, &ty::TypeValue::Char) => {
+            Some((bool_ty, ir::BinaryOperatorKind::UIntLessEqual))
         }
+
         (Greater, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((bool_ty, ir::BinaryOperatorKind::DoubleGreater))
         }
-
-        (GreaterEqual, &ty::TypeValue::Int, &ty::TypeValue::Int) => {
-            Some((bool_ty, ir::BinaryOperatorKind::IntGreaterEqual))
+        (Greater, &ty::TypeValue::Char, &ty::TypeValue::Char) => {
+            Some((bool_ty, ir::BinaryOperatorKind::UIntGreater))
         }
+
         (GreaterEqual, &ty::TypeValue::Double, &ty::TypeValue::Double) => {
             Some((bool_ty, ir::BinaryOperatorKind::DoubleGreaterEqual))
         }
-        (Plus, &ty::TypeValue::Pointer(sub), &ty::TypeValue::Int) => {
-            Some((sub, ir::BinaryOperatorKind::PtrPlusOffset))
+        (GreaterEqual, &ty::TypeValue::Char, &ty::TypeValue::Char) => {
+            Some((bool_ty, ir::BinaryOperatorKind::UIntGreaterEqual))
+        }
+        // a tagged union is only compared through a match
+        (Equal, &ty::TypeValue::Enum(e), &ty::TypeValue::Enum(_))
+            if lhs == rhs && !e.has_payload() =>
+        {
+            Some((bool_ty, ir::BinaryOperatorKind::IntEqual))
+        }
+        (NotEqual, &ty::TypeValue::Enum(e), &ty::TypeValue::Enum(_))
+            if lhs == rhs && !e.has_payload() =>
+        {
+            Some((bool_ty, ir::BinaryOperatorKind::IntNotEqual))
+        }
+        // the strings are concatenated and compared by the runtime
+        (Plus, &ty::TypeValue::String, &ty::TypeValue::String) => {
+            Some((lhs, ir::BinaryOperatorKind::StringConcat))
+        }
+        (Equal, &ty::TypeValue::String, &ty::TypeValue::String) => {
+            Some((bool_ty, ir::BinaryOperatorKind::StringEqual))
+        }
+        (NotEqual, &ty::TypeValue::String, &ty::TypeValue::String) => {
+            Some((bool_ty, ir::BinaryOperatorKind::StringNotEqual))
         }
-        (Minus, &ty::TypeValue::Pointer(sub), &ty::TypeValue::Int) => {
-            Some((sub, ir::BinaryOperatorKind::PtrMinusOffset))
+        (Less, &ty::TypeValue::String, &ty::TypeValue::String) => {
+            Some((bool_ty, ir::BinaryOperatorKind::StringLess))
+        }
+        (LessEqual, &ty::TypeValue::String, &ty::TypeValue::String) => {
+            Some((bool_ty, ir::BinaryOperatorKind::StringLessEqual))
+        }
+        (Greater, &ty::TypeValue::String, &ty::TypeValue::String) => {
+            Some((bool_ty, ir::BinaryOperatorKind::StringGreater))
+        }
+        (GreaterEqual, &ty::TypeValue::String, &ty::TypeValue::String) => {
+            Some((bool_ty, ir::BinaryOperatorKind::StringGreaterEqual))
+        }
+        (Plus, &ty::TypeValue::Pointer(_), &ty::TypeValue::Int) => {
+            Some((lhs, ir::BinaryOperatorKind::PtrPlusOffset))
+        }
+        (Minus, &ty::TypeValue::Pointer(_), &ty::TypeValue::Int) => {
+            Some((lhs, ir::BinaryOperatorKind::PtrMinusOffset))
         }
         (Minus, &ty::TypeValue::Pointer(a), &ty::TypeValue::Pointer(b)) if a == b => {
             Some((int_ty, ir::BinaryOperatorKind::PtrDiff))
         }
+        // `nullptr` is a `*void`, it can be compared with any pointer
+        (Equal, &ty::TypeValue::Pointer(a), &ty::TypeValue::Pointer(b))
+            if a == b || is_void(a) || is_void(b) =>
+        {
+            Some((bool_ty, ir::BinaryOperatorKind::PtrEqual))
+        }
+        (NotEqual, &ty::TypeValue::Pointer(a), &ty::TypeValue::Pointer(b))
+            if a == b || is_void(a) || is_void(b) =>
+        {
+            Some((bool_ty, ir::BinaryOperatorKind::PtrNotEqual))
+        }
         _ => None,
     }
 }
 
+// the unsigned integers divide, compare and shift right without the sign
+fn int_binop_typeck(
+    binop: ast::BinaryOperatorKind,
+    ty: ty::Type,
+    signed: bool,
+    bool_ty: ty::Type,
+) -> (ty::Type, ir::BinaryOperatorKind) {
+    use ast::BinaryOperatorKind::*;
+    use ir::BinaryOperatorKind as bok;
+    let (res_ty, op) = match (binop, signed) {
+        (Plus, _) => (ty, bok::IntPlus),
+        (Minus, _) => (ty, bok::IntMinus),
+        (Multiply, _) => (ty, bok::IntMultiply),
+        (Divide, true) => (ty, bok::IntDivide),
+        (Divide, false) => (ty, bok::UIntDivide),
+        (Modulo, true) => (ty, bok::IntModulo),
+        (Modulo, false) => (ty, bok::UIntModulo),
+        (BitAnd, _) => (ty, bok::IntAnd),
+        (BitOr, _) => (ty, bok::IntOr),
+        (BitXor, _) => (ty, bok::IntXor),
+        (ShiftLeft, _) => (ty, bok::IntShiftLeft),
+        (ShiftRight, true) => (ty, bok::IntShiftRight),
+        (ShiftRight, false) => (ty, bok::UIntShiftRight),
+        (Equal, _) => (bool_ty, bok::IntEqual),
+        (NotEqual, _) => (bool_ty, bok::IntNotEqual),
+        (Less, true) => (bool_ty, bok::IntLess),
+        (Less, false) => (bool_ty, bok::UIntLess),
+        (LessEqual, true) => (bool_ty, bok::IntLessEqual),
+        (LessEqual, false) => (bool_ty, bok::UIntLessEqual),
+        (Greater, true) => (bool_ty, bok::IntGreater),
+        (Greater, false) => (bool_ty, bok::UIntGreater),
+        (GreaterEqual, true) => (bool_ty, bok::IntGreaterEqual),
+        (GreaterEqual, false) => (bool_ty, bok::UIntGreaterEqual),
+    };
+    (res_ty, op)
+}
+
+fn is_char(ty: ty::Type) -> bool {
+    if let ty::TypeValue::Char = *ty {
+        true
+    } else {
+        false
+    }
+}
+
+fn is_void(ty: ty::Type) -> bool {
+    if let ty::TypeValue::Void = *ty {
+        true
+    } else {
+        false
+    }
+}
+
 pub fn unop_typeck(
     type_ctxt: &trans::tables::TypeTable,
     unop: ast::UnaryOperatorKind,
     sub: ty::Type,
 ) -> Option<(ty::Type, ir::UnaryOperatorKind)> {
-    let int_ty = type_ctxt.get_int_ty();
     let double_ty = type_ctxt.get_double_ty();
     let bool_ty = type_ctxt.get_boolean_ty();
     let void_ty = type_ctxt.get_void_ty();
 
     use ast::UnaryOperatorKind::*;
     match (unop, &*sub) {
-        (Minus, &ty::TypeValue::Int) => Some((int_ty, ir::UnaryOperatorKind::IntMinus)),
+        (Minus, tv) if tv.int_kind().map_or(false, ty::IntKind::is_signed) => {
+            Some((sub, ir::UnaryOperatorKind::IntMinus))
+        }
         (Minus, &ty::TypeValue::Double) => Some((double_ty, ir::UnaryOperatorKind::DoubleMinus)),
         (LogicalNot, &ty::TypeValue::Boolean) => Some((bool_ty, ir::UnaryOperatorKind::BooleanNot)),
+        (BitNot, tv) if tv.int_kind().is_some() => Some((sub, ir::UnaryOperatorKind::IntNot)),
         (PtrDeref, &ty::TypeValue::Pointer(sub)) if sub != void_ty => Some((
             type_ctxt.lvalue_of(sub, true),
             ir::UnaryOperatorKind::PointerDeref,
@@ -131,16 +222,14 @@ pub fn lvalue_unop_typeck(
     lvalue_unop: ast::LValueUnaryOperatorKind,
     sub: ty::Type,
 ) -> Option<(ty::Type, ir::LValueUnaryOperatorKind)> {
-    let int_ty = type_ctxt.get_int_ty();
-
     use ast::LValueUnaryOperatorKind::*;
     match (lvalue_unop, &*sub) {
-        (Increment, &ty::TypeValue::Int) => Some((
-            type_ctxt.lvalue_of(int_ty, true),
+        (Increment, tv) if tv.int_kind().is_some() => Some((
+            type_ctxt.lvalue_of(sub, true),
             ir::LValueUnaryOperatorKind::IntIncrement,
         )),
-        (Decrement, &ty::TypeValue::Int) => Some((
-            type_ctxt.lvalue_of(int_ty, true),
+        (Decrement, tv) if tv.int_kind().is_some() => Some((
+            type_ctxt.lvalue_of(sub, true),
             ir::LValueUnaryOperatorKind::IntDecrement,
         )),
         (AddressOf, _) => Some((
@@ -160,16 +249,44 @@ pub enum CastTypeckResult {
 
 pub fn cast_typeck(src_ty: ty::Type, target_ty: ty::Type) -> CastTypeckResult {
     use self::CastTypeckResult::*;
+    // the integers of every width, `int` included, are widened, narrowed and converted
+    match (src_ty.int_kind(), target_ty.int_kind()) {
+        (Some(from), Some(to)) if src_ty != target_ty => {
+            return Cast(ir::CastKind::IntToInt(from, to))
+        }
+        (Some(from), _) => match *target_ty {
+            ty::TypeValue::Double => return Cast(ir::CastKind::IntToDouble(from)),
+            ty::TypeValue::Pointer(_) => return Cast(ir::CastKind::IntToPtr(target_ty)),
+            ty::TypeValue::Char => return Cast(ir::CastKind::IntToChar(from)),
+            _ => {}
+        },
+        (_, Some(to)) => match *src_ty {
+            ty::TypeValue::Double => return Cast(ir::CastKind::DoubleToInt(to)),
+            ty::TypeValue::Pointer(_) => return Cast(ir::CastKind::PtrToInt(to)),
+            ty::TypeValue::Char => return Cast(ir::CastKind::CharToInt(to)),
+            _ => {}
+        },
+        _ => {}
+    }
+
     match (&*src_ty, &*target_ty) {
-        (&ty::TypeValue::Int, &ty::TypeValue::Double) => Cast(ir::CastKind::IntToDouble),
-        (&ty::TypeValue::Double, &ty::TypeValue::Int) => Cast(ir::CastKind::DoubleToInt),
         (&ty::TypeValue::Boolean, &ty::TypeValue::Int) => Cast(ir::CastKind::BooleanToInt),
         (&ty::TypeValue::Int, &ty::TypeValue::Boolean) => Cast(ir::CastKind::IntToBoolean),
-        (&ty::TypeValue::Pointer(_), &ty::TypeValue::Int) => Cast(ir::CastKind::PtrToInt),
-        (&ty::TypeValue::Int, &ty::TypeValue::Pointer(_)) => {
-            Cast(ir::CastKind::IntToPtr(target_ty))
-        }
         (&ty::TypeValue::Pointer(_), &ty::TypeValue::Pointer(_)) => BitCast,
+        // a C-like enum is its `int` discriminant
+        (&ty::TypeValue::Enum(e), &ty::TypeValue::Int)
+        | (&ty::TypeValue::Int, &ty::TypeValue::Enum(e))
+            if !e.has_payload() =>
+        {
+            BitCast
+        }
+        // a string points to its chars
+        (&ty::TypeValue::Pointer(sub), &ty::TypeValue::String)
+        | (&ty::TypeValue::String, &ty::TypeValue::Pointer(sub))
+            if is_char(sub) =>
+        {
+            BitCast
+        }
         (ref a, ref b) if a == b => BitCast,
         _ => None,
     }
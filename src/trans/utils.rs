@@ -14,12 +14,54 @@ macro_rules! error {
     }
 }
 
+/// Check that an integer literal, negated or not, is a value of its type and give the
+/// literal of this value. A negated double literal is folded too.
+pub fn check_literal(
+    types: &trans::tables::TypeTable,
+    lit: common::Literal,
+    negated: bool,
+    span: Span,
+) -> TranslationResult<common::Literal> {
+    let (magnitude, kind) = match lit {
+        common::Literal::IntLiteral(i) => (i as u64, ty::IntKind::I32),
+        common::Literal::SizedIntLiteral(i, kind) => (i as u64, kind),
+        common::Literal::DoubleLiteral(d) if negated => {
+            return Ok(common::Literal::DoubleLiteral(-d))
+        }
+        lit => return Ok(lit),
+    };
+    if !kind.fits(magnitude, negated) {
+        return error!(
+            TranslationError::LiteralOutOfRange(lit.get_type(types)),
+            span
+        );
+    }
+
+    let value = if negated {
+        (magnitude as i64).wrapping_neg()
+    } else {
+        magnitude as i64
+    };
+    Ok(match lit {
+        common::Literal::IntLiteral(_) => common::Literal::IntLiteral(value),
+        _ => common::Literal::SizedIntLiteral(value, kind),
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct TypedExpression {
     pub ty: ty::Type,
     pub expr: ir::Expression,
 }
 
+// the expression given in place of an ill-typed one, it is never generated
+pub fn error_expression(type_table: &trans::tables::TypeTable) -> TypedExpression {
+    TypedExpression {
+        ty: type_table.get_error_ty(),
+        expr: ir::Expression::Value(ir::Value::Literal(common::Literal::IntLiteral(0))),
+    }
+}
+
 pub fn build_assign_to_id(id: IdentifierId, rhs: ir::Expression) -> ir::Expression {
     let value = ir::Value::Local(id);
     ir::Expression::Assign {
@@ -28,6 +70,11 @@ pub fn build_assign_to_id(id: IdentifierId, rhs: ir::Expression) -> ir::Expressi
     }
 }
 
+pub fn rvalue_of_id(id: IdentifierId) -> ir::Expression {
+    let value = ir::Value::Local(id);
+    ir::Expression::LValueToRValue(Box::new(ir::Expression::Value(value)))
+}
+
 pub fn build_assign_to_field(
     struct_expr: ir::Expression,
     index: usize,
@@ -56,6 +103,72 @@ pub fn build_assign_to_array_index(
     }
 }
 
+/// Give the type of `Enum::Variant` and the variant.
+pub fn translate_enum_variant(
+    tables: &mut trans::tables::Tables,
+    enum_name: String,
+    variant: Spanned<String>,
+    expr_span: Span,
+) -> TranslationResult<(ty::Type, ty::Variant)> {
+    let ty = if let Some(ty) = tables.types.lookup_type(&enum_name) {
+        ty
+    } else {
+        return error!(TranslationError::UndefinedType(enum_name), expr_span);
+    };
+
+    if let Some(ref mut index) = tables.index {
+        if let Some(&definition) = index.types.get(&enum_name) {
+            index.add_reference(Span::new_with_len(expr_span.start, enum_name.len()), definition);
+        }
+    }
+
+    let enum_ty = if let ty::TypeValue::Enum(e) = *ty {
+        e
+    } else {
+        return error!(TranslationError::NonEnumType(enum_name), expr_span);
+    };
+    match enum_ty.variant(&variant.inner) {
+        Some(v) => Ok((ty, v.clone())),
+        None => error!(
+            TranslationError::UndefinedVariant(enum_name, variant.inner),
+            variant.span
+        ),
+    }
+}
+
+pub fn build_runtime_call(name: &str, args: Vec<ir::Expression>) -> ir::Expression {
+    ir::Expression::FunctionCall {
+        function: Box::new(ir::Expression::Value(ir::Value::Global(name.to_string()))),
+        args,
+    }
+}
+
+/// Build a binary operator, the string operators are calls to the runtime.
+pub fn build_binop(
+    binop: ir::BinaryOperatorKind,
+    lhs: ir::Expression,
+    rhs: ir::Expression,
+) -> ir::Expression {
+    if let ir::BinaryOperatorKind::StringConcat = binop {
+        return build_runtime_call(trans::STRING_CONCAT_FUNCTION, vec![lhs, rhs]);
+    }
+    if let Some(int_binop) = binop.string_comparison() {
+        let cmp = build_runtime_call(trans::STRING_COMPARE_FUNCTION, vec![lhs, rhs]);
+        return ir::Expression::BinaryOperator {
+            binop: int_binop,
+            lhs: Box::new(cmp),
+            rhs: Box::new(ir::Expression::Value(ir::Value::Literal(
+                common::Literal::IntLiteral(0),
+            ))),
+        };
+    }
+    ir::Expression::BinaryOperator {
+        binop,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
 pub fn build_subscript(ptr: ir::Expression, index: ir::Expression) -> ir::Expression {
     ir::Expression::UnaryOperator {
         unop: ir::UnaryOperatorKind::PointerDeref,
@@ -72,6 +185,14 @@ pub fn check_eq_types_auto_cast(
     target_ty: ty::Type,
     error_span: Span,
 ) -> TranslationResult<TypedExpression> {
+    if expr.ty.is_error() {
+        // the expected type is trusted to avoid errors further down
+        return Ok(TypedExpression {
+            ty: target_ty,
+            expr: expr.expr,
+        });
+    }
+
     match typeck::auto_cast(expr.ty, target_ty) {
         typeck::CastTypeckResult::Cast(kind) => Ok(TypedExpression {
             ty: target_ty,
@@ -95,7 +216,7 @@ pub fn check_eq_types_auto_cast(
 }
 
 pub fn check_eq_types(a: ty::Type, b: ty::Type, error_span: Span) -> TranslationResult<()> {
-    if a != b {
+    if a != b && !a.is_error() && !b.is_error() {
         error!(TranslationError::MismatchingTypes(a, b), error_span) // TODO convert Type to suitable format
     } else {
         Ok(())
@@ -107,7 +228,7 @@ pub fn check_expect_type(
     given: ty::Type,
     error_span: Span,
 ) -> TranslationResult<()> {
-    if expected != given {
+    if expected != given && !expected.is_error() && !given.is_error() {
         error!(
             TranslationError::UnexpectedType(expected, given),
             error_span
@@ -128,6 +249,23 @@ pub fn lvalue_to_rvalue(expression: TypedExpression) -> TypedExpression {
     }
 }
 
+// the rvalues are copied to temporaries, those can be assigned
+pub fn is_assignable(ty: ty::Type) -> bool {
+    match *ty {
+        ty::TypeValue::LValue(_, assignable) => assignable,
+        _ => true,
+    }
+}
+
+// the type of the value, without the lvalue around it
+pub fn rvalue_type(ty: ty::Type) -> ty::Type {
+    if let ty::TypeValue::LValue(sub, _) = *ty {
+        sub
+    } else {
+        ty
+    }
+}
+
 pub fn rvalue_to_lvalue(
     ty_table: &trans::tables::TypeTable,
     expression: TypedExpression,
@@ -157,9 +295,14 @@ pub fn unsure_subscriptable(
                 sub: Box::new(expr.expr),
             };
             return Some((sub, ptr));
+        } else if let Some(sub) = runtime_sized_item(sub) {
+            return Some((sub, array_data(expr.expr)));
         } else {
             lvalue_to_rvalue(expr)
         }
+    } else if let Some(sub) = runtime_sized_item(expr.ty) {
+        let lvalue = ir::Expression::RValueToLValue(Box::new(expr.expr));
+        return Some((sub, array_data(lvalue)));
     } else {
         expr
     };
@@ -171,6 +314,22 @@ pub fn unsure_subscriptable(
     }
 }
 
+// the item type of a dynamic array or a slice, both store their length then their items
+pub fn runtime_sized_item(ty: ty::Type) -> Option<ty::Type> {
+    match *ty {
+        ty::TypeValue::DynArray(sub) | ty::TypeValue::Slice(sub) => Some(sub),
+        _ => None,
+    }
+}
+
+// the pointer to the items of a dynamic array or a slice lvalue
+pub fn array_data(lvalue: ir::Expression) -> ir::Expression {
+    ir::Expression::LValueToRValue(Box::new(ir::Expression::FieldAccess {
+        sub: Box::new(lvalue),
+        index: 1,
+    }))
+}
+
 pub fn check_return_paths(block: &[ir::Statement]) -> bool {
     block.iter().map(check_return_paths_stmt).any(|b| b)
 }
@@ -12,6 +12,13 @@ macro_rules! wrapper {
         }
 
         impl Eq for $name {}
+
+        // hashed like it is compared, a struct type is still completed after it is used
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
     };
     (@eq_impl @sub_eq $name:ident) => {
         impl PartialEq for $name {
@@ -21,6 +28,12 @@ macro_rules! wrapper {
         }
 
         impl Eq for $name {}
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                (**self).hash(state);
+            }
+        }
     };
     ($name:ident -> $sub_ty:ty, @$eq:ident) => {
 
@@ -50,31 +63,44 @@ macro_rules! wrapper {
                 unsafe { &mut *self.0 }
             }
         }
-
-        impl Hash for $name {
-            fn hash<H: Hasher>(&self, state: &mut H) {
-                (**self).hash(state);
-            }
-        }
     }
 }
 
 wrapper!(Type -> TypeValue, @direct_eq);
 wrapper!(StructType -> StructTypeValue, @sub_eq);
+wrapper!(EnumType -> EnumTypeValue, @sub_eq);
 
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match **self {
             TypeValue::Incomplete => write!(f, "incomplete"),
+            TypeValue::Error => write!(f, "{{error}}"),
             TypeValue::Int => write!(f, "int"),
+            TypeValue::SizedInt(kind) => write!(f, "{}", kind.name()),
+            TypeValue::Char => write!(f, "char"),
             TypeValue::Double => write!(f, "double"),
             TypeValue::Boolean => write!(f, "boolean"),
             TypeValue::String => write!(f, "string"),
             TypeValue::Void => write!(f, "void"),
             TypeValue::Pointer(ref sub) => write!(f, "*{}", sub),
             TypeValue::Struct(ref s) => write!(f, "struct {} {{ .. }}", s.name),
+            TypeValue::Enum(ref e) => write!(f, "enum {}", e.name),
             TypeValue::Array(ref sub, ref size) => write!(f, "[{}; {}]", sub, size),
-            _ => panic!("Type not supposed to be displayed"),
+            TypeValue::DynArray(ref sub) => write!(f, "[]{}", sub),
+            TypeValue::Slice(ref sub) => write!(f, "&[{}]", sub),
+            TypeValue::LValue(ref sub, _) => write!(f, "{}", sub),
+            TypeValue::Tuple(ref types) => {
+                let types: Vec<_> = types.iter().map(ToString::to_string).collect();
+                write!(f, "({})", types.join(", "))
+            }
+            TypeValue::FunctionPtr(ref func_ty) => {
+                let mut parameters: Vec<_> =
+                    func_ty.parameters_ty.iter().map(ToString::to_string).collect();
+                if func_ty.is_vararg {
+                    parameters.push("...".to_string());
+                }
+                write!(f, "fn({}) -> {}", parameters.join(", "), func_ty.return_ty)
+            }
         }
     }
 }
@@ -82,27 +108,147 @@ impl fmt::Display for Type {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TypeValue {
     Incomplete,
+    Error, // given to ill-typed expressions, accepted everywhere to avoid cascading errors
     Int,
+    SizedInt(IntKind), // never I32, that one is `int`
+    Char,              // a byte, compared like an unsigned integer
     Double,
     Boolean,
     String,
     Void,
     Struct(StructType),
+    Enum(EnumType), // an `int` discriminant, with the payload of its variant if any
     Tuple(Vec<Type>),
     LValue(Type, bool), // assignable
     Pointer(Type),
     Array(Type, usize),
+    DynArray(Type), // the length and a pointer to the items on the heap
+    Slice(Type),    // the length and a pointer to items owned by an array
     FunctionPtr(FunctionType),
 }
 
+/// The width and signedness of an integer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntKind {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U32,
+    U64,
+}
+
+impl IntKind {
+    pub const SIZED: [IntKind; 6] = [
+        IntKind::I8,
+        IntKind::I16,
+        IntKind::I64,
+        IntKind::U8,
+        IntKind::U32,
+        IntKind::U64,
+    ];
+
+    pub fn from_name(name: &str) -> Option<IntKind> {
+        match name {
+            "i8" => Some(IntKind::I8),
+            "i16" => Some(IntKind::I16),
+            "i64" => Some(IntKind::I64),
+            "u8" => Some(IntKind::U8),
+            "u32" => Some(IntKind::U32),
+            "u64" => Some(IntKind::U64),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IntKind::I8 => "i8",
+            IntKind::I16 => "i16",
+            IntKind::I32 => "i32",
+            IntKind::I64 => "i64",
+            IntKind::U8 => "u8",
+            IntKind::U32 => "u32",
+            IntKind::U64 => "u64",
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            IntKind::I8 | IntKind::U8 => 8,
+            IntKind::I16 => 16,
+            IntKind::I32 | IntKind::U32 => 32,
+            IntKind::I64 | IntKind::U64 => 64,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        match self {
+            IntKind::I8 | IntKind::I16 | IntKind::I32 | IntKind::I64 => true,
+            IntKind::U8 | IntKind::U32 | IntKind::U64 => false,
+        }
+    }
+
+    /// True if a literal of this magnitude, negated or not, is a value of the type.
+    pub fn fits(self, magnitude: u64, negative: bool) -> bool {
+        if self.is_signed() {
+            let max = (1u64 << (self.bits() - 1)) - 1;
+            magnitude <= if negative { max + 1 } else { max }
+        } else {
+            !negative && (self.bits() == 64 || magnitude < 1u64 << self.bits())
+        }
+    }
+
+    /// Wrap a value to the type, sign extended for signed types and zero extended for the
+    /// others.
+    pub fn wrap(self, value: i64) -> i64 {
+        match self {
+            IntKind::I8 => i64::from(value as i8),
+            IntKind::I16 => i64::from(value as i16),
+            IntKind::I32 => i64::from(value as i32),
+            IntKind::U8 => i64::from(value as u8),
+            IntKind::U32 => i64::from(value as u32),
+            IntKind::I64 | IntKind::U64 => value,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum FieldInfo {
     StructField(usize, Type),
     TupleField(usize, Type),
     ArrayLen(usize),
+    RuntimeLen, // the length is the first field
+    StringLen,  // the length is counted by the runtime
 }
 
 impl TypeValue {
+    // true if the type is built from the error type, structs are not looked into
+    pub fn is_error(&self) -> bool {
+        match *self {
+            TypeValue::Error => true,
+            TypeValue::LValue(sub, _)
+            | TypeValue::Pointer(sub)
+            | TypeValue::Array(sub, _)
+            | TypeValue::DynArray(sub)
+            | TypeValue::Slice(sub) => sub.is_error(),
+            TypeValue::Tuple(ref types) => types.iter().any(|ty| ty.is_error()),
+            TypeValue::FunctionPtr(ref func_ty) => {
+                func_ty.return_ty.is_error() || func_ty.parameters_ty.iter().any(|ty| ty.is_error())
+            }
+            _ => false,
+        }
+    }
+
+    /// The kind of an integer type, `int` is the 32 bits signed one.
+    pub fn int_kind(&self) -> Option<IntKind> {
+        match *self {
+            TypeValue::Int => Some(IntKind::I32),
+            TypeValue::SizedInt(kind) => Some(kind),
+            _ => None,
+        }
+    }
+
     pub fn has_field(&self, field: &Field) -> Option<FieldInfo> {
         match *self {
             TypeValue::Struct(st) => {
@@ -117,12 +263,28 @@ impl TypeValue {
             }
             TypeValue::Array(_, size) => {
                 if let Field::Named(ref field_name) = *field {
-                    if field_name == "len" {
+                    if is_len_field(field_name) {
                         return Some(FieldInfo::ArrayLen(size));
                     }
                 }
                 None
             }
+            TypeValue::DynArray(_) | TypeValue::Slice(_) => {
+                if let Field::Named(ref field_name) = *field {
+                    if is_len_field(field_name) {
+                        return Some(FieldInfo::RuntimeLen);
+                    }
+                }
+                None
+            }
+            TypeValue::String => {
+                if let Field::Named(ref field_name) = *field {
+                    if is_len_field(field_name) {
+                        return Some(FieldInfo::StringLen);
+                    }
+                }
+                None
+            }
             TypeValue::Tuple(ref types) => {
                 if let Field::Index(index) = *field {
                     if index < types.len() {
@@ -136,6 +298,11 @@ impl TypeValue {
     }
 }
 
+// `length` is the name of the property in Javalette
+fn is_len_field(name: &str) -> bool {
+    name == "len" || name == "length"
+}
+
 #[derive(Debug, Clone, Eq)]
 pub struct StructTypeValue {
     pub name: String,
@@ -154,6 +321,52 @@ impl Hash for StructTypeValue {
     }
 }
 
+#[derive(Debug, Clone, Eq)]
+pub struct EnumTypeValue {
+    pub name: String,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub discriminant: i32,
+    pub payload: Vec<Type>,
+}
+
+impl EnumTypeValue {
+    pub fn variant(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    // the first variant with this discriminant, several variants can share one
+    pub fn variant_name(&self, discriminant: i32) -> Option<&str> {
+        self.variants
+            .iter()
+            .find(|variant| variant.discriminant == discriminant)
+            .map(|variant| variant.name.as_str())
+    }
+
+    /// A tagged union is stored as its tag then the payload of its largest variant, a
+    /// C-like enum is only its tag.
+    pub fn has_payload(&self) -> bool {
+        self.variants.iter().any(|variant| !variant.payload.is_empty())
+    }
+}
+
+// compared by name like the structs
+impl PartialEq for EnumTypeValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Hash for EnumTypeValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FunctionType {
     pub return_ty: Type,
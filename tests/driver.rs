@@ -0,0 +1,78 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const PROGRAM: &str = "fn main() -> int {\n    printInt(42);\n    return 0;\n}\n";
+
+// a directory of its own for each test, the tests run in parallel
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("yalc-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn yalc() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_yalc"))
+}
+
+#[test]
+fn executable_of_an_extensionless_input() {
+    let dir = temp_dir("extensionless");
+    let input = dir.join("prog");
+    fs::write(&input, PROGRAM).unwrap();
+
+    let status = yalc().arg(&input).args(["--emit", "exe"]).status().unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(&input).unwrap(), PROGRAM);
+
+    let output = Command::new(dir.join("prog.out")).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "42\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn output_overwriting_the_input() {
+    let dir = temp_dir("overwrite");
+    let input = dir.join("prog.yal");
+    fs::write(&input, PROGRAM).unwrap();
+
+    let output = yalc()
+        .arg(&input)
+        .args(["--emit", "exe", "-o"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("the output would overwrite the input"));
+    assert_eq!(fs::read_to_string(&input).unwrap(), PROGRAM);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn lint_flags_in_command_line_order() {
+    let dir = temp_dir("lints");
+    let input = dir.join("unused.yal");
+    fs::write(&input, "fn main() -> int {\n    let x = 1;\n    return 0;\n}\n").unwrap();
+
+    let check = |flags: &[&str]| {
+        let output = yalc().arg(&input).args(flags).output().unwrap();
+        (output.status.success(), String::from_utf8(output.stderr).unwrap())
+    };
+
+    let (success, stderr) = check(&["-D", "warnings", "-A", "unused_variables"]);
+    assert!(success);
+    assert_eq!(stderr, "");
+
+    let (success, stderr) = check(&["-A", "unused_variables", "-D", "warnings"]);
+    assert!(!success);
+    assert!(stderr.contains("error[W0001]"));
+
+    let (success, stderr) = check(&["-D", "unused_variables", "-W", "warnings"]);
+    assert!(success);
+    assert!(stderr.contains("warning[W0001]"));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,160 @@
+extern crate yal;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use yal::json::{self, Json};
+
+// the second line doesn't parse, the rest of the document must still be analyzed
+const DOCUMENT: &str = "\
+fn main() -> int {
+    let broken = ;
+    let total = 40 + 2;
+    printInt(total);
+    return 0;
+}
+";
+
+fn frame(message: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", message.len(), message)
+}
+
+// send every message, then read all the messages the server answered before exiting
+fn run_session(messages: &[String]) -> (Vec<Json>, i32) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_yalc"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("can't start yalc");
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        for message in messages {
+            stdin.write_all(frame(message).as_bytes()).unwrap();
+        }
+    }
+    let output = child.wait_with_output().unwrap();
+    let mut stdout = String::from_utf8(output.stdout).unwrap();
+
+    let mut responses = Vec::new();
+    while !stdout.is_empty() {
+        let header_end = stdout.find("\r\n\r\n").expect("no header end");
+        let length: usize = stdout[..header_end]
+            .trim_start_matches("Content-Length: ")
+            .parse()
+            .expect("invalid length");
+        let body_start = header_end + 4;
+        responses.push(json::parse(&stdout[body_start..body_start + length]).expect("invalid JSON"));
+        stdout = stdout[body_start + length..].to_string();
+    }
+    (responses, output.status.code().unwrap())
+}
+
+fn request(id: usize, method: &str, params: Json) -> String {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id.into()),
+        ("method", method.into()),
+        ("params", params),
+    ]).to_string()
+}
+
+fn notification(method: &str, params: Json) -> String {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ]).to_string()
+}
+
+fn position(uri: &str, line: usize, character: usize) -> Json {
+    Json::object(vec![
+        ("textDocument", Json::object(vec![("uri", uri.into())])),
+        (
+            "position",
+            Json::object(vec![("line", line.into()), ("character", character.into())]),
+        ),
+    ])
+}
+
+fn response(responses: &[Json], id: usize) -> &Json {
+    responses
+        .iter()
+        .find(|response| response.get("id").and_then(Json::as_usize) == Some(id))
+        .expect("no response")
+}
+
+#[test]
+fn session() {
+    let uri = "file:///test.yal";
+    let open = Json::object(vec![
+        (
+            "textDocument",
+            Json::object(vec![
+                ("uri", uri.into()),
+                ("languageId", "yal".into()),
+                ("version", 1.into()),
+                ("text", DOCUMENT.into()),
+            ]),
+        ),
+    ]);
+    let messages = vec![
+        request(1, "initialize", Json::object(vec![("capabilities", Json::object(vec![]))])),
+        notification("initialized", Json::object(vec![])),
+        notification("textDocument/didOpen", open),
+        request(2, "textDocument/hover", position(uri, 2, 17)),
+        request(3, "textDocument/definition", position(uri, 3, 14)),
+        request(4, "textDocument/hover", position(uri, 1, 0)),
+        request(5, "unknown/method", Json::Null),
+        request(6, "shutdown", Json::Null),
+        notification("exit", Json::Null),
+    ];
+    let (responses, code) = run_session(&messages);
+    assert_eq!(code, 0);
+
+    let capabilities = response(&responses, 1).path(&["result", "capabilities"]).unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+    assert_eq!(capabilities.get("definitionProvider"), Some(&Json::Bool(true)));
+
+    // only the syntax error is reported, nothing about the code it made the parser drop
+    let diagnostics = responses
+        .iter()
+        .find(|message| {
+            message.get("method").and_then(Json::as_str)
+                == Some("textDocument/publishDiagnostics")
+        })
+        .expect("no diagnostics");
+    assert_eq!(diagnostics.path(&["params", "uri"]).and_then(Json::as_str), Some(uri));
+    let diagnostics = diagnostics
+        .path(&["params", "diagnostics"])
+        .and_then(Json::as_array)
+        .unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].get("code").and_then(Json::as_str), Some("E0004"));
+    assert_eq!(
+        diagnostics[0].path(&["range", "start", "line"]).and_then(Json::as_usize),
+        Some(1)
+    );
+
+    let hover = response(&responses, 2).get("result").unwrap();
+    assert_eq!(hover.path(&["contents", "value"]).and_then(Json::as_str), Some("int"));
+    assert_eq!(hover.path(&["range", "start", "line"]).and_then(Json::as_usize), Some(2));
+
+    let definition = response(&responses, 3).get("result").unwrap();
+    assert_eq!(definition.get("uri").and_then(Json::as_str), Some(uri));
+    assert_eq!(
+        definition.path(&["range", "start", "line"]).and_then(Json::as_usize),
+        Some(2)
+    );
+
+    assert_eq!(response(&responses, 4).get("result"), Some(&Json::Null));
+    assert!(response(&responses, 5).path(&["error", "code"]).is_some());
+    assert_eq!(response(&responses, 6).get("result"), Some(&Json::Null));
+}
+
+#[test]
+fn exit_without_shutdown() {
+    let (responses, code) = run_session(&[notification("exit", Json::Null)]);
+    assert!(responses.is_empty());
+    assert_eq!(code, 1);
+}
@@ -0,0 +1,88 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// run a whole session, its output holds the prompts and the values of the entries
+fn run_session(input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_yalc"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("can't start yalc");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn values(stdout: &str) -> Vec<&str> {
+    stdout
+        .split(">> ")
+        .map(|value| value.trim_end_matches(".. ").trim())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+#[test]
+fn expression_value() {
+    let (stdout, stderr) = run_session("1 + 2\n'a'\n\"yal\"\n");
+    assert_eq!(values(&stdout), vec!["3: int", "'a': char", "\"yal\": string"]);
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn variables_persist() {
+    let input = "\
+let x = 1;
+x
+x = x + 41;
+x
+let s = \"hello\";
+s
+fn inc() -> int {
+    x++;
+    return x;
+}
+inc()
+x
+";
+    let (stdout, stderr) = run_session(input);
+    assert_eq!(
+        values(&stdout),
+        vec!["1: int", "42: int", "\"hello\": string", "43: int", "43: int"]
+    );
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn assignments_persist() {
+    let (stdout, stderr) = run_session("let counter = 0;\ncounter += 5;\ncounter\n");
+    assert_eq!(values(&stdout), vec!["5: int"]);
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn failed_entry_is_dropped() {
+    let input = "let x = 1;\nlet x = 2;\nlet y = undefined;\nx\ny\n";
+    let (stdout, stderr) = run_session(input);
+    assert_eq!(values(&stdout), vec!["1: int"]);
+    assert!(stderr.contains("error[E0040]"));
+    assert_eq!(stderr.matches("error[E0016]").count(), 2);
+}
+
+#[test]
+fn declarations_and_commands() {
+    let input = "fn square(x: int) -> int {\n    return x * x;\n}\nsquare(7)\n:quit\n1\n";
+    let (stdout, stderr) = run_session(input);
+    assert_eq!(values(&stdout), vec!["49: int"]);
+    assert_eq!(stderr, "");
+}